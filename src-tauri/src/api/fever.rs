@@ -0,0 +1,261 @@
+// https://web.archive.org/web/20230616124016/https://feedafever.com/api
+
+use chrono::{TimeZone, Utc};
+use rusqlite::Connection;
+use serde_json::{json, Map, Value};
+
+use crate::api::server::{credentials, Reply, Request};
+use crate::assets;
+use crate::error::{Error, Result};
+use crate::models::feeds;
+use crate::models::items::{
+    self, Item, ItemOrder, ItemReadOption, ItemStatus, ItemToUpdate, ItemToUpdateAll,
+};
+use crate::publisher;
+
+const API_VERSION: i32 = 3;
+const GROUP_ID: i32 = 1;
+const MAX_ITEMS: u64 = 50;
+
+pub fn handle(db: &Connection, req: &Request) -> Result<Reply> {
+    let mut res = Map::new();
+    res.insert("api_version".to_string(), json!(API_VERSION));
+
+    if req.param("api").is_none() || !is_authorized(db, req) {
+        res.insert("auth".to_string(), json!(0));
+        return Ok(Reply::json(&Value::Object(res)));
+    }
+
+    res.insert("auth".to_string(), json!(1));
+    res.insert(
+        "last_refreshed_on_time".to_string(),
+        json!(last_refreshed_on_time(db)?),
+    );
+
+    if let Some(mark) = req.param("mark") {
+        mark_as(db, req, mark)?;
+    }
+
+    if req.param("groups").is_some() {
+        res.insert(
+            "groups".to_string(),
+            json!([{ "id": GROUP_ID, "title": "All" }]),
+        );
+        res.insert("feeds_groups".to_string(), feeds_groups(db)?);
+    }
+
+    if req.param("feeds").is_some() {
+        res.insert("feeds".to_string(), feeds(db)?);
+        res.insert("feeds_groups".to_string(), feeds_groups(db)?);
+    }
+
+    if req.param("favicons").is_some() {
+        res.insert("favicons".to_string(), json!([]));
+    }
+
+    if req.param("items").is_some() {
+        res.insert("items".to_string(), items(db, req)?);
+        res.insert(
+            "total_items".to_string(),
+            json!(items::count_all(db, &ItemReadOption::default())?),
+        );
+    }
+
+    if req.param("unread_item_ids").is_some() {
+        let opt = ItemReadOption {
+            status: Some(ItemStatus::Unread),
+            ..Default::default()
+        };
+        res.insert("unread_item_ids".to_string(), json!(item_ids(db, &opt)?));
+    }
+
+    if req.param("saved_item_ids").is_some() {
+        let opt = ItemReadOption {
+            is_saved: Some(true),
+            ..Default::default()
+        };
+        res.insert("saved_item_ids".to_string(), json!(item_ids(db, &opt)?));
+    }
+
+    Ok(Reply::json(&Value::Object(res)))
+}
+
+fn is_authorized(db: &Connection, req: &Request) -> bool {
+    match (credentials(db), req.param("api_key")) {
+        (Some((username, password)), Some(api_key)) => {
+            let expected = format!("{:x}", md5::compute(format!("{}:{}", username, password)));
+            api_key.eq_ignore_ascii_case(&expected)
+        }
+        _ => false,
+    }
+}
+
+fn last_refreshed_on_time(db: &Connection) -> Result<i64> {
+    Ok(feeds::read_all(db)?
+        .iter()
+        .map(|x| x.checked_at.timestamp())
+        .max()
+        .unwrap_or(0))
+}
+
+fn feeds(db: &Connection) -> Result<Value> {
+    Ok(feeds::read_all(db)?
+        .iter()
+        .map(|x| {
+            json!({
+                "id": x.id,
+                "favicon_id": 0,
+                "title": x.title,
+                "url": x.link,
+                "site_url": x.link,
+                "is_spark": 0,
+                "last_updated_on_time": x.checked_at.timestamp(),
+            })
+        })
+        .collect())
+}
+
+fn feeds_groups(db: &Connection) -> Result<Value> {
+    let feed_ids = feeds::read_all(db)?
+        .iter()
+        .map(|x| x.id.to_string())
+        .collect::<Vec<_>>()
+        .join(",");
+
+    Ok(json!([{ "group_id": GROUP_ID, "feed_ids": feed_ids }]))
+}
+
+fn items(db: &Connection, req: &Request) -> Result<Value> {
+    let mut opt = ItemReadOption {
+        order_by: Some(ItemOrder::ReceivedDateAsc),
+        limit: Some(MAX_ITEMS),
+        ..Default::default()
+    };
+
+    if let Some(with_ids) = req.param("with_ids") {
+        opt.ids = Some(parse_ids(with_ids)?);
+    } else if let Some(max_id) = req.param("max_id") {
+        opt.max_id = Some(parse_id(max_id)?);
+        opt.order_by = Some(ItemOrder::ReceivedDateDesc);
+    } else if let Some(since_id) = req.param("since_id") {
+        opt.since_id = Some(parse_id(since_id)?);
+    }
+
+    let mut items = items::read_all(db, &opt)?;
+    assets::restore(db, &mut items)?;
+
+    Ok(items.iter().map(item).collect())
+}
+
+fn item(x: &Item) -> Value {
+    json!({
+        "id": x.id,
+        "feed_id": x.feed.id,
+        "title": x.title,
+        "author": x.author.clone().unwrap_or_default(),
+        "html": x.description,
+        "url": x.link,
+        "is_saved": i32::from(x.is_saved),
+        "is_read": i32::from(matches!(x.status, ItemStatus::Read)),
+        "created_on_time": x.published_at.timestamp(),
+    })
+}
+
+fn item_ids(db: &Connection, opt: &ItemReadOption) -> Result<String> {
+    Ok(items::read_all(db, opt)?
+        .iter()
+        .map(|x| x.id.to_string())
+        .collect::<Vec<_>>()
+        .join(","))
+}
+
+fn mark_as(db: &Connection, req: &Request, mark: &str) -> Result<()> {
+    let id = parse_id(req.param("id").unwrap_or_default())?;
+    let before = req
+        .param("before")
+        .and_then(|x| x.parse::<i64>().ok())
+        .and_then(|x| Utc.timestamp_opt(x, 0).single())
+        .map(|x| x.fixed_offset());
+
+    match (mark, req.param("as").unwrap_or_default()) {
+        ("item", status @ ("read" | "unread")) => {
+            mark_item(
+                db,
+                &ItemToUpdate {
+                    id,
+                    status: Some(if status == "read" {
+                        ItemStatus::Read
+                    } else {
+                        ItemStatus::Unread
+                    }),
+                    is_saved: None,
+                },
+            )?;
+        }
+        ("item", saved @ ("saved" | "unsaved")) => {
+            mark_item(
+                db,
+                &ItemToUpdate {
+                    id,
+                    status: None,
+                    is_saved: Some(saved == "saved"),
+                },
+            )?;
+            publisher::refresh(db);
+        }
+        ("feed", "read") => {
+            items::update_all(
+                db,
+                &ItemToUpdateAll {
+                    status: Some(ItemStatus::Read),
+                    is_saved: None,
+                    opt: Some(ItemReadOption {
+                        feed: Some(id),
+                        published_before: before,
+                        ..Default::default()
+                    }),
+                },
+            )?;
+        }
+        ("group", "read") => {
+            if id == 0 || id == GROUP_ID {
+                items::update_all(
+                    db,
+                    &ItemToUpdateAll {
+                        status: Some(ItemStatus::Read),
+                        is_saved: None,
+                        opt: Some(ItemReadOption {
+                            published_before: before,
+                            ..Default::default()
+                        }),
+                    },
+                )?;
+            }
+        }
+        (mark, status) => return Err(Error::InvalidValue(format!("{}:{}", mark, status))),
+    }
+
+    Ok(())
+}
+
+// Clients may still know of items cleaned up since their last sync, which is not worth failing for.
+fn mark_item(db: &Connection, arg: &ItemToUpdate) -> Result<()> {
+    match items::update(db, arg) {
+        Ok(_) | Err(Error::ItemNotFound(_)) => Ok(()),
+        Err(err) => Err(err),
+    }
+}
+
+fn parse_id(x: &str) -> Result<i32> {
+    x.trim()
+        .parse()
+        .map_err(|_| Error::InvalidValue(x.to_string()))
+}
+
+fn parse_ids(x: &str) -> Result<Vec<i32>> {
+    x.split(',')
+        .filter(|x| !x.trim().is_empty())
+        .take(MAX_ITEMS as usize)
+        .map(parse_id)
+        .collect()
+}
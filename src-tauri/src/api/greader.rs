@@ -0,0 +1,403 @@
+// https://github.com/theoldreader/api, https://freshrss.github.io/FreshRSS/en/developers/06_GoogleReader_API.html
+
+use chrono::{TimeZone, Utc};
+use percent_encoding::percent_decode_str;
+use rusqlite::Connection;
+use serde_json::{json, Value};
+use sha1_smol::Sha1;
+
+use crate::api::server::{credentials, Reply, Request};
+use crate::assets;
+use crate::error::{Error, Result};
+use crate::models::feeds;
+use crate::models::items::{self, Item, ItemOrder, ItemReadOption, ItemStatus, ItemToUpdateAll};
+use crate::publisher;
+
+const ITEM_ID_PREFIX: &str = "tag:google.com,2005:reader/item/";
+const READING_LIST: &str = "user/-/state/com.google/reading-list";
+const READ: &str = "user/-/state/com.google/read";
+const STARRED: &str = "user/-/state/com.google/starred";
+const DEFAULT_ITEMS: u64 = 20;
+const MAX_ITEMS: u64 = 1000;
+
+enum Stream {
+    ReadingList,
+    Read,
+    Starred,
+    Feed(i32),
+}
+
+impl Stream {
+    fn parse(x: &str) -> Result<Self> {
+        if let Some(id) = x.strip_prefix("feed/") {
+            return Ok(Self::Feed(
+                id.parse().map_err(|_| Error::InvalidValue(x.to_string()))?,
+            ));
+        }
+
+        match x.rsplit_once("/state/com.google/").map(|(_, state)| state) {
+            Some("reading-list") => Ok(Self::ReadingList),
+            Some("read") => Ok(Self::Read),
+            Some("starred") => Ok(Self::Starred),
+            _ => Err(Error::InvalidValue(x.to_string())),
+        }
+    }
+
+    fn apply(&self, opt: &mut ItemReadOption) {
+        match self {
+            Self::ReadingList => {}
+            Self::Read => opt.status = Some(ItemStatus::Read),
+            Self::Starred => opt.is_saved = Some(true),
+            Self::Feed(id) => opt.feed = Some(*id),
+        }
+    }
+}
+
+pub fn handle(db: &Connection, req: &Request) -> Result<Reply> {
+    if req.path == "/accounts/ClientLogin" {
+        return client_login(db, req);
+    }
+
+    if !is_authorized(db, req) {
+        return Ok(Reply::status(401));
+    }
+
+    match req.path.trim_start_matches("/reader/api/0/") {
+        "token" => Ok(Reply::text(&auth_token(db).unwrap_or_default())),
+        "user-info" => user_info(db),
+        "subscription/list" => subscription_list(db),
+        "tag/list" => Ok(Reply::json(&json!({ "tags": [{ "id": STARRED }] }))),
+        "unread-count" => unread_count(db),
+        "stream/items/ids" => stream_item_ids(db, req),
+        "stream/items/contents" => stream_items_contents(db, req),
+        "edit-tag" => edit_tag(db, req),
+        "mark-all-as-read" => mark_all_as_read(db, req),
+        path => match path.strip_prefix("stream/contents") {
+            Some(stream) => stream_contents(db, req, stream.trim_start_matches('/')),
+            None => Ok(Reply::status(404)),
+        },
+    }
+}
+
+fn auth_token(db: &Connection) -> Option<String> {
+    let (username, password) = credentials(db)?;
+    let hash = Sha1::from(format!("{}:{}", username, password)).hexdigest();
+    Some(format!("{}/{}", username, hash))
+}
+
+fn is_authorized(db: &Connection, req: &Request) -> bool {
+    match (auth_token(db), &req.authorization) {
+        (Some(token), Some(authorization)) => authorization
+            .strip_prefix("GoogleLogin auth=")
+            .is_some_and(|x| x == token),
+        _ => false,
+    }
+}
+
+fn client_login(db: &Connection, req: &Request) -> Result<Reply> {
+    match (credentials(db), req.param("Email"), req.param("Passwd")) {
+        (Some((username, password)), Some(email), Some(passwd))
+            if username == email && password == passwd =>
+        {
+            let token = auth_token(db).unwrap_or_default();
+            Ok(Reply::text(&format!(
+                "SID={}\nLSID=null\nAuth={}\n",
+                token, token
+            )))
+        }
+        _ => {
+            let mut reply = Reply::text("Error=BadAuthentication\n");
+            reply.status = 401;
+            Ok(reply)
+        }
+    }
+}
+
+fn user_info(db: &Connection) -> Result<Reply> {
+    let username = credentials(db).map(|(x, _)| x).unwrap_or_default();
+    Ok(Reply::json(&json!({
+        "userId": "1",
+        "userName": username,
+        "userProfileId": "1",
+        "userEmail": "",
+    })))
+}
+
+fn subscription_list(db: &Connection) -> Result<Reply> {
+    let subscriptions: Vec<Value> = feeds::read_all(db)?
+        .iter()
+        .map(|x| {
+            json!({
+                "id": format!("feed/{}", x.id),
+                "title": x.title,
+                "categories": [],
+                "url": x.link,
+                "htmlUrl": x.link,
+                "iconUrl": "",
+            })
+        })
+        .collect();
+
+    Ok(Reply::json(&json!({ "subscriptions": subscriptions })))
+}
+
+fn unread_count(db: &Connection) -> Result<Reply> {
+    let mut unreadcounts = vec![];
+    let mut max = 0;
+
+    for feed in feeds::read_all(db)? {
+        let opt = ItemReadOption {
+            feed: Some(feed.id),
+            status: Some(ItemStatus::Unread),
+            ..Default::default()
+        };
+        let count = items::count_all(db, &opt)?;
+        max += count;
+
+        unreadcounts.push(json!({
+            "id": format!("feed/{}", feed.id),
+            "count": count,
+            "newestItemTimestampUsec": (feed.checked_at.timestamp() * 1_000_000).to_string(),
+        }));
+    }
+
+    Ok(Reply::json(&json!({
+        "max": max,
+        "unreadcounts": unreadcounts,
+    })))
+}
+
+fn stream_item_ids(db: &Connection, req: &Request) -> Result<Reply> {
+    let (items, continuation) = read_stream(db, req, req.param("s").unwrap_or(READING_LIST))?;
+    let item_refs: Vec<Value> = items
+        .iter()
+        .map(|x| {
+            json!({
+                "id": x.id.to_string(),
+                "directStreamIds": [],
+                "timestampUsec": (x.published_at.timestamp() * 1_000_000).to_string(),
+            })
+        })
+        .collect();
+
+    let mut res = json!({ "itemRefs": item_refs });
+    if let Some(continuation) = continuation {
+        res["continuation"] = json!(continuation);
+    }
+
+    Ok(Reply::json(&res))
+}
+
+fn stream_contents(db: &Connection, req: &Request, stream: &str) -> Result<Reply> {
+    let stream = if stream.is_empty() {
+        req.param("s").unwrap_or(READING_LIST).to_string()
+    } else {
+        percent_decode_str(stream).decode_utf8_lossy().to_string()
+    };
+
+    let (items, continuation) = read_stream(db, req, &stream)?;
+
+    let mut res = json!({
+        "id": stream,
+        "updated": Utc::now().timestamp(),
+        "items": items.iter().map(item).collect::<Vec<_>>(),
+    });
+    if let Some(continuation) = continuation {
+        res["continuation"] = json!(continuation);
+    }
+
+    Ok(Reply::json(&res))
+}
+
+fn stream_items_contents(db: &Connection, req: &Request) -> Result<Reply> {
+    let opt = ItemReadOption {
+        ids: Some(parse_item_ids(&req.params("i"))?),
+        order_by: Some(ItemOrder::PublishedDateDesc),
+        ..Default::default()
+    };
+    let mut items = items::read_all(db, &opt)?;
+    assets::restore(db, &mut items)?;
+    let items: Vec<Value> = items.iter().map(item).collect();
+
+    Ok(Reply::json(&json!({
+        "id": READING_LIST,
+        "updated": Utc::now().timestamp(),
+        "items": items,
+    })))
+}
+
+fn edit_tag(db: &Connection, req: &Request) -> Result<Reply> {
+    let ids = parse_item_ids(&req.params("i"))?;
+    let tags = req
+        .params("a")
+        .into_iter()
+        .map(|x| (x, true))
+        .chain(req.params("r").into_iter().map(|x| (x, false)));
+
+    let mut saved_changed = false;
+    for (tag, added) in tags {
+        let (status, is_saved) = match tag.rsplit_once("/state/com.google/").map(|(_, x)| x) {
+            Some("read") => (
+                Some(if added {
+                    ItemStatus::Read
+                } else {
+                    ItemStatus::Unread
+                }),
+                None,
+            ),
+            Some("kept-unread") if added => (Some(ItemStatus::Unread), None),
+            Some("starred") => (None, Some(added)),
+            _ => continue,
+        };
+
+        items::update_all(
+            db,
+            &ItemToUpdateAll {
+                status,
+                is_saved,
+                opt: Some(ItemReadOption {
+                    ids: Some(ids.clone()),
+                    ..Default::default()
+                }),
+            },
+        )?;
+        saved_changed |= is_saved.is_some();
+    }
+
+    if saved_changed {
+        publisher::refresh(db);
+    }
+
+    Ok(Reply::text("OK"))
+}
+
+fn mark_all_as_read(db: &Connection, req: &Request) -> Result<Reply> {
+    let mut opt = ItemReadOption {
+        published_before: req
+            .param("ts")
+            .and_then(|x| x.parse::<i64>().ok())
+            .and_then(|x| Utc.timestamp_opt(x / 1_000_000, 0).single())
+            .map(|x| x.fixed_offset()),
+        ..Default::default()
+    };
+    Stream::parse(req.param("s").unwrap_or(READING_LIST))?.apply(&mut opt);
+
+    items::update_all(
+        db,
+        &ItemToUpdateAll {
+            status: Some(ItemStatus::Read),
+            is_saved: None,
+            opt: Some(opt),
+        },
+    )?;
+
+    Ok(Reply::text("OK"))
+}
+
+fn read_stream(
+    db: &Connection,
+    req: &Request,
+    stream: &str,
+) -> Result<(Vec<Item>, Option<String>)> {
+    let limit = req
+        .param("n")
+        .and_then(|x| x.parse::<u64>().ok())
+        .unwrap_or(DEFAULT_ITEMS)
+        .min(MAX_ITEMS);
+    let page = req
+        .param("c")
+        .and_then(|x| x.parse::<u64>().ok())
+        .unwrap_or(0);
+
+    let mut opt = ItemReadOption {
+        order_by: Some(ItemOrder::PublishedDateDesc),
+        limit: Some(limit),
+        offset: Some(page),
+        ..Default::default()
+    };
+    Stream::parse(stream)?.apply(&mut opt);
+
+    if req.param("r") == Some("o") {
+        opt.order_by = Some(ItemOrder::ReceivedDateAsc);
+    }
+
+    for target in req.params("xt") {
+        match Stream::parse(target) {
+            Ok(Stream::Read) => opt.status = Some(ItemStatus::Unread),
+            Ok(Stream::Starred) => opt.is_saved = Some(false),
+            _ => {}
+        }
+    }
+
+    if let Some(target) = req.param("it") {
+        Stream::parse(target)?.apply(&mut opt);
+    }
+
+    opt.published_after = req
+        .param("ot")
+        .and_then(|x| x.parse::<i64>().ok())
+        .and_then(|x| Utc.timestamp_opt(x, 0).single())
+        .map(|x| x.fixed_offset());
+    opt.published_before = req
+        .param("nt")
+        .and_then(|x| x.parse::<i64>().ok())
+        .and_then(|x| Utc.timestamp_opt(x, 0).single())
+        .map(|x| x.fixed_offset());
+
+    let mut items = items::read_all(db, &opt)?;
+    assets::restore(db, &mut items)?;
+    let continuation = if items.len() as u64 == limit {
+        Some((page + 1).to_string())
+    } else {
+        None
+    };
+
+    Ok((items, continuation))
+}
+
+fn item(x: &Item) -> Value {
+    let mut categories = vec![READING_LIST.to_string(), format!("feed/{}", x.feed.id)];
+    if matches!(x.status, ItemStatus::Read) {
+        categories.push(READ.to_string());
+    }
+    if x.is_saved {
+        categories.push(STARRED.to_string());
+    }
+
+    json!({
+        "id": format!("{}{:016x}", ITEM_ID_PREFIX, x.id),
+        "crawlTimeMsec": (x.published_at.timestamp_millis()).to_string(),
+        "timestampUsec": (x.published_at.timestamp() * 1_000_000).to_string(),
+        "published": x.published_at.timestamp(),
+        "updated": x.published_at.timestamp(),
+        "title": x.title,
+        "author": x.author.clone().unwrap_or_default(),
+        "canonical": [{ "href": x.link }],
+        "alternate": [{ "href": x.link, "type": "text/html" }],
+        "summary": { "direction": "ltr", "content": x.description },
+        "categories": categories,
+        "origin": {
+            "streamId": format!("feed/{}", x.feed.id),
+            "title": x.feed.title,
+            "htmlUrl": x.feed.link,
+        },
+    })
+}
+
+fn parse_item_ids(xs: &[&str]) -> Result<Vec<i32>> {
+    xs.iter()
+        .map(|x| {
+            let id = if let Some(hex) = x.strip_prefix(ITEM_ID_PREFIX) {
+                i64::from_str_radix(hex, 16)
+            } else if x.len() == 16 {
+                i64::from_str_radix(x, 16)
+            } else {
+                x.parse::<i64>()
+            };
+
+            id.ok()
+                .and_then(|x| i32::try_from(x).ok())
+                .ok_or(Error::InvalidValue(x.to_string()))
+        })
+        .collect()
+}
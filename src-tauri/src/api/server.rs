@@ -0,0 +1,177 @@
+use std::io::Read;
+use std::path::Path;
+use std::thread;
+
+use rusqlite::Connection;
+use serde_json::Value;
+use tiny_http::{Header, Method, Response, Server};
+
+use crate::api::{fever, greader};
+use crate::error::{Error, Result};
+use crate::models::database::open_connection;
+use crate::models::settings::{self, SettingKey};
+use crate::publisher::{self, PublishFormat};
+
+pub struct Request {
+    pub method: Method,
+    pub path: String,
+    pub params: Vec<(String, String)>,
+    pub authorization: Option<String>,
+}
+
+impl Request {
+    pub fn param(&self, key: &str) -> Option<&str> {
+        self.params
+            .iter()
+            .find(|(k, _)| k == key)
+            .map(|(_, v)| v.as_str())
+    }
+
+    pub fn params(&self, key: &str) -> Vec<&str> {
+        self.params
+            .iter()
+            .filter(|(k, _)| k == key)
+            .map(|(_, v)| v.as_str())
+            .collect()
+    }
+}
+
+pub struct Reply {
+    pub status: u16,
+    pub content_type: &'static str,
+    pub body: String,
+}
+
+impl Reply {
+    pub fn json(value: &Value) -> Self {
+        Self {
+            status: 200,
+            content_type: "application/json; charset=utf-8",
+            body: value.to_string(),
+        }
+    }
+
+    pub fn text(body: &str) -> Self {
+        Self {
+            status: 200,
+            content_type: "text/plain; charset=utf-8",
+            body: body.to_string(),
+        }
+    }
+
+    pub fn status(status: u16) -> Self {
+        Self {
+            status,
+            content_type: "text/plain; charset=utf-8",
+            body: String::new(),
+        }
+    }
+}
+
+pub fn start(app_data_dir: &Path) -> Result<()> {
+    let db = open_connection(app_data_dir)?;
+    if !enabled(&db) {
+        return Ok(());
+    }
+
+    let address = settings::read(&db, &SettingKey::ApiServerAddress)?.value;
+    let server = Server::http(address).map_err(|err| Error::StartServerFailure(err.to_string()))?;
+
+    thread::spawn(move || serve(&db, &server));
+
+    Ok(())
+}
+
+pub fn serve(db: &Connection, server: &Server) {
+    for mut request in server.incoming_requests() {
+        let req = parse(&mut request);
+        let reply = match route(db, &req) {
+            Ok(reply) => reply,
+            Err(Error::InvalidValue(_)) => Reply::status(400),
+            Err(err) => {
+                eprintln!("Error handling {} {}: {}", req.method, req.path, err);
+                Reply::status(500)
+            }
+        };
+
+        let response = Response::from_string(reply.body)
+            .with_status_code(reply.status)
+            .with_header(Header::from_bytes("Content-Type", reply.content_type).unwrap());
+        let _ = request.respond(response);
+    }
+}
+
+fn route(db: &Connection, req: &Request) -> Result<Reply> {
+    if req.path == "/fever" || req.path.starts_with("/fever/") {
+        fever::handle(db, req)
+    } else if req.path == "/accounts/ClientLogin" || req.path.starts_with("/reader/api/0/") {
+        greader::handle(db, req)
+    } else if let Some(format) = req.path.strip_prefix("/saved.") {
+        saved(db, req, format)
+    } else {
+        Ok(Reply::status(404))
+    }
+}
+
+fn saved(db: &Connection, req: &Request, format: &str) -> Result<Reply> {
+    let Ok(format) = format.parse::<PublishFormat>() else {
+        return Ok(Reply::status(404));
+    };
+    let feed = match req.param("feed") {
+        Some(feed) => Some(
+            feed.parse::<i32>()
+                .map_err(|_| Error::InvalidValue(feed.to_string()))?,
+        ),
+        None => None,
+    };
+
+    Ok(Reply {
+        status: 200,
+        content_type: format.content_type(),
+        body: publisher::publish(db, &format, feed)?,
+    })
+}
+
+fn parse(request: &mut tiny_http::Request) -> Request {
+    let (path, query) = match request.url().split_once('?') {
+        Some((path, query)) => (path.to_string(), query.to_string()),
+        None => (request.url().to_string(), String::new()),
+    };
+
+    let mut params: Vec<(String, String)> = url::form_urlencoded::parse(query.as_bytes())
+        .into_owned()
+        .collect();
+
+    let mut body = String::new();
+    if *request.method() == Method::Post && request.as_reader().read_to_string(&mut body).is_ok() {
+        params.extend(url::form_urlencoded::parse(body.as_bytes()).into_owned());
+    }
+
+    let authorization = request
+        .headers()
+        .iter()
+        .find(|x| x.field.equiv("Authorization"))
+        .map(|x| x.value.to_string());
+
+    Request {
+        method: request.method().clone(),
+        path,
+        params,
+        authorization,
+    }
+}
+
+fn enabled(db: &Connection) -> bool {
+    settings::boolean(db, &SettingKey::ApiServer)
+}
+
+pub fn credentials(db: &Connection) -> Option<(String, String)> {
+    let username = settings::value(db, &SettingKey::ApiUsername);
+    let password = settings::value(db, &SettingKey::ApiPassword);
+
+    if username.is_empty() || password.is_empty() {
+        None
+    } else {
+        Some((username, password))
+    }
+}
@@ -0,0 +1,276 @@
+use std::collections::hash_map::Entry;
+use std::collections::HashMap;
+use std::fs;
+use std::path::{Path, PathBuf};
+
+use rusqlite::Connection;
+use sha1_smol::Sha1;
+
+use crate::error::{Error, Result};
+use crate::http::HttpClient;
+use crate::models::assets::{self, AssetToCreate};
+use crate::models::items::{self, Item, ItemToCreate};
+use crate::models::settings::{self, SettingKey};
+use crate::sanitizer::{image_sources, rewrite_images};
+use crate::DbState;
+
+pub const PROTOCOL: &str = "collie-asset";
+const MAX_ASSET_SIZE: u64 = 10 * 1024 * 1024;
+const EXTENSIONS: [(&str, &str); 7] = [
+    ("avif", "image/avif"),
+    ("gif", "image/gif"),
+    ("jpeg", "image/jpeg"),
+    ("jpg", "image/jpeg"),
+    ("png", "image/png"),
+    ("svg", "image/svg+xml"),
+    ("webp", "image/webp"),
+];
+
+pub fn cache_dir(app_data_dir: &Path) -> PathBuf {
+    app_data_dir.join("assets")
+}
+
+pub fn enabled(db: &Connection) -> bool {
+    settings::boolean(db, &SettingKey::AssetCache)
+}
+
+/// The URL of the cached file, served by the custom protocol.
+pub fn asset_url(path: &str) -> String {
+    if cfg!(windows) {
+        format!("https://{}.localhost/{}", PROTOCOL, path)
+    } else {
+        format!("{}://localhost/{}", PROTOCOL, path)
+    }
+}
+
+/// Downloads the images and thumbnails of the new items into `dir` and rewrites their
+/// references to the cached files. Returns the number of cached assets.
+pub fn cache_items(
+    db: &Connection,
+    dir: &Path,
+    args: &[ItemToCreate],
+    client: &HttpClient,
+) -> Result<usize> {
+    fs::create_dir_all(dir)?;
+
+    let mut cached = 0;
+    let mut clients = HashMap::new();
+    for arg in args {
+        if let Some(item) = items::read_by_fingerprint(db, &arg.fingerprint())? {
+            let client = match clients.entry(item.feed.id) {
+                Entry::Occupied(x) => x.into_mut(),
+                Entry::Vacant(x) => x.insert(client.for_feed_id(db, item.feed.id)?),
+            };
+            cached += cache_item(db, dir, client, &item)?;
+        }
+    }
+
+    Ok(cached)
+}
+
+fn cache_item(db: &Connection, dir: &Path, client: &HttpClient, item: &Item) -> Result<usize> {
+    let mut sources = HashMap::new();
+
+    for url in image_sources(&item.description)
+        .into_iter()
+        .chain(item.thumbnail.clone())
+    {
+        if sources.contains_key(&url) || !url.starts_with("http") {
+            continue;
+        }
+
+        match download(client, dir, &url) {
+            Ok(path) => {
+                let _ = assets::create(
+                    db,
+                    &AssetToCreate {
+                        item: item.id,
+                        url: url.clone(),
+                        path: path.clone(),
+                    },
+                );
+                sources.insert(url, asset_url(&path));
+            }
+            Err(err) => eprintln!("Error caching {}: {}", url, err),
+        }
+    }
+
+    if sources.is_empty() {
+        return Ok(0);
+    }
+
+    let thumbnail = item
+        .thumbnail
+        .as_ref()
+        .map(|x| sources.get(x).unwrap_or(x).as_str());
+    items::update_assets(
+        db,
+        item.id,
+        &rewrite_images(&item.description, &sources),
+        thumbnail,
+    )?;
+
+    Ok(sources.len())
+}
+
+fn download(client: &HttpClient, dir: &Path, url: &str) -> Result<String> {
+    let path = file_name(url);
+    if dir.join(&path).exists() {
+        return Ok(path);
+    }
+
+    let res = client.get(url)?.error_for_status()?;
+    let is_image = res
+        .headers()
+        .get("Content-Type")
+        .and_then(|x| x.to_str().ok())
+        .is_some_and(|x| x.starts_with("image/"));
+    if !is_image || res.content_length().is_some_and(|x| x > MAX_ASSET_SIZE) {
+        return Err(Error::InvalidValue(url.to_string()));
+    }
+
+    let bytes = res.bytes()?;
+    if bytes.len() as u64 > MAX_ASSET_SIZE {
+        return Err(Error::InvalidValue(url.to_string()));
+    }
+
+    fs::write(dir.join(&path), bytes)?;
+    Ok(path)
+}
+
+fn file_name(url: &str) -> String {
+    let hash = Sha1::from(url).hexdigest();
+    let extension = url
+        .split(['?', '#'])
+        .next()
+        .and_then(|x| x.rsplit_once('.'))
+        .map(|(_, x)| x.to_lowercase())
+        .filter(|x| EXTENSIONS.iter().any(|(ext, _)| ext == x));
+
+    match extension {
+        Some(extension) => format!("{}.{}", hash, extension),
+        None => hash,
+    }
+}
+
+fn mime_type(path: &str) -> &'static str {
+    path.rsplit_once('.')
+        .and_then(|(_, x)| EXTENSIONS.iter().find(|(ext, _)| *ext == x))
+        .map_or("application/octet-stream", |(_, mime)| mime)
+}
+
+/// Points the cached images and thumbnails of the items back to their original URLs, for
+/// readers outside the app, which cannot load the custom protocol.
+pub fn restore(db: &Connection, items: &mut [Item]) -> Result<()> {
+    let ids = items.iter().map(|x| x.id).collect::<Vec<_>>();
+    let originals = assets::read_by_items(db, &ids)?
+        .into_iter()
+        .map(|x| (asset_url(&x.path), x.url))
+        .collect::<HashMap<_, _>>();
+    if originals.is_empty() {
+        return Ok(());
+    }
+
+    for item in items {
+        if item.description.contains(PROTOCOL) {
+            item.description = rewrite_images(&item.description, &originals);
+        }
+        if let Some(original) = item.thumbnail.as_ref().and_then(|x| originals.get(x)) {
+            item.thumbnail = Some(original.clone());
+        }
+    }
+
+    Ok(())
+}
+
+/// Reads the asset requested through the custom protocol. Evicted files are fetched from the
+/// original URL again so that the rewritten references keep working while online. The
+/// database is only locked to look the asset up, not while it is fetched.
+pub fn read(
+    db_state: &DbState,
+    dir: &Path,
+    uri: &str,
+    client: &HttpClient,
+) -> Result<Option<(&'static str, Vec<u8>)>> {
+    let path = uri
+        .split(['?', '#'])
+        .next()
+        .and_then(|x| x.rsplit('/').next())
+        .unwrap_or_default();
+    if path.is_empty() || !path.chars().all(|x| x.is_ascii_alphanumeric() || x == '.') {
+        return Ok(None);
+    }
+
+    if let Ok(bytes) = fs::read(dir.join(path)) {
+        return Ok(Some((mime_type(path), bytes)));
+    }
+
+    let asset = {
+        let db = db_state.lock();
+        match assets::read_by_path(&db, path)? {
+            Some(asset) => {
+                let feed = items::read(&db, asset.item)?.map(|x| x.feed.id);
+                let client = match feed {
+                    Some(feed) => client.for_feed_id(&db, feed)?,
+                    None => client.clone(),
+                };
+                Some((asset, client))
+            }
+            None => None,
+        }
+    };
+
+    match asset {
+        Some((asset, client)) => {
+            let res = client.get(&asset.url)?.error_for_status()?;
+            Ok(Some((mime_type(path), res.bytes()?.to_vec())))
+        }
+        None => Ok(None),
+    }
+}
+
+/// Removes the assets of deleted items and files no longer referenced, then the oldest files
+/// until the cache fits in `asset_cache_size` megabytes. Returns the number of removed files.
+pub fn evict(db: &Connection, dir: &Path) -> Result<usize> {
+    if !dir.exists() {
+        return Ok(0);
+    }
+
+    assets::delete_orphans(db)?;
+
+    let mut created_at = HashMap::new();
+    for asset in assets::read_all(db)? {
+        let entry = created_at.entry(asset.path).or_insert(asset.created_at);
+        *entry = (*entry).min(asset.created_at);
+    }
+
+    let mut removed = 0;
+    let mut files = vec![];
+    for entry in fs::read_dir(dir)? {
+        let entry = entry?;
+        let name = entry.file_name().to_string_lossy().to_string();
+        match created_at.get(&name) {
+            Some(created_at) => files.push((*created_at, entry.path(), entry.metadata()?.len())),
+            None => {
+                fs::remove_file(entry.path())?;
+                removed += 1;
+            }
+        }
+    }
+
+    let limit = settings::integer(db, &SettingKey::AssetCacheSize) as u64 * 1024 * 1024;
+    let mut total = files.iter().map(|(_, _, size)| size).sum::<u64>();
+
+    files.sort_by_key(|(created_at, _, _)| *created_at);
+    for (_, path, size) in files {
+        if total <= limit {
+            break;
+        }
+
+        fs::remove_file(path)?;
+        total -= size;
+        removed += 1;
+    }
+
+    Ok(removed)
+}
@@ -0,0 +1,61 @@
+use encoding_rs::{Encoding, UTF_16BE, UTF_16LE, UTF_8};
+use regex::{bytes, Regex};
+use std::sync::OnceLock;
+
+// Declarations are expected near the start of the document, like browsers do.
+const PRESCAN_SIZE: usize = 1024;
+const XML_DECLARATION: &str = r#"^(\s*<\?xml[^>]*?encoding\s*=\s*["'])([A-Za-z0-9._:-]+)"#;
+const META_CHARSET: &str = r#"(?i)<meta[^>]+charset\s*=\s*["']?([A-Za-z0-9._:-]+)"#;
+
+/// Decodes a fetched document, trusting the byte order mark, the `charset` of the
+/// Content-Type header, the XML declaration and the HTML meta charset, in that order.
+/// Falls back to UTF-8.
+pub fn decode(bytes: &[u8], content_type: Option<&str>) -> String {
+    let (encoding, bytes) = match Encoding::for_bom(bytes) {
+        Some((encoding, bom_length)) => (encoding, &bytes[bom_length..]),
+        None => (
+            content_type
+                .and_then(header_charset)
+                .or_else(|| declared_charset(bytes))
+                .unwrap_or(UTF_8),
+            bytes,
+        ),
+    };
+
+    let decoded = encoding.decode_without_bom_handling(bytes).0;
+
+    // Feed parsers honor the XML declaration, which may not match the decoded text anymore.
+    static XML: OnceLock<Regex> = OnceLock::new();
+    XML.get_or_init(|| Regex::new(XML_DECLARATION).unwrap())
+        .replace(&decoded, "${1}UTF-8")
+        .into_owned()
+}
+
+fn header_charset(content_type: &str) -> Option<&'static Encoding> {
+    content_type
+        .split(';')
+        .skip(1)
+        .filter_map(|x| x.split_once('='))
+        .find(|(key, _)| key.trim().eq_ignore_ascii_case("charset"))
+        .and_then(|(_, value)| Encoding::for_label(value.trim().trim_matches('"').as_bytes()))
+}
+
+fn declared_charset(bytes: &[u8]) -> Option<&'static Encoding> {
+    static XML: OnceLock<bytes::Regex> = OnceLock::new();
+    static META: OnceLock<bytes::Regex> = OnceLock::new();
+
+    let head = &bytes[..bytes.len().min(PRESCAN_SIZE)];
+    let xml = XML.get_or_init(|| bytes::Regex::new(XML_DECLARATION).unwrap());
+    let meta = META.get_or_init(|| bytes::Regex::new(META_CHARSET).unwrap());
+
+    let label = match xml.captures(head) {
+        Some(x) => x.get(2)?.as_bytes(),
+        None => meta.captures(head)?.get(1)?.as_bytes(),
+    };
+
+    // A document readable enough to declare UTF-16 in ASCII cannot actually be UTF-16.
+    match Encoding::for_label(label)? {
+        x if x == UTF_16LE || x == UTF_16BE => Some(UTF_8),
+        x => Some(x),
+    }
+}
@@ -1,91 +1,95 @@
-use tauri::State;
+use std::ops::ControlFlow;
+use tauri::{AppHandle, State};
 
-use crate::models::settings;
-use crate::models::settings::SettingKey;
-use crate::syndication::Feed as _Feed;
 use crate::{
-    models::feeds::{self, Feed, FeedToCreate, FeedToUpdate},
-    producer::create_new_items,
-    syndication::{fetch_content, fetch_feed_title, find_feed_link},
-    DbState,
+    app_data_dir, favicon,
+    models::feeds::{self, Feed, FeedHistory, FeedToCreate, FeedToUpdate},
+    producer::{create_new_items, FeedOutcome},
+    syndication::discover_feed,
+    worker, DbState, HttpState, WorkerState,
 };
 
 use crate::error::Error;
 
-#[tauri::command]
-pub fn create_feed(db_state: State<DbState>, arg: FeedToCreate) -> Result<String, String> {
-    if arg.link.is_empty() {
-        return Err(Error::EmptyString.to_string());
-    }
-
-    let db = db_state.db.lock().unwrap();
-    let proxy = settings::read(&db, &SettingKey::Proxy)
-        .map(|x| x.value)
-        .ok();
-
-    let html_content = fetch_content(&arg.link, proxy.as_deref()).unwrap();
-    let is_feed = html_content.parse::<_Feed>().is_ok();
-
-    let link = if is_feed {
-        arg.link.clone()
-    } else if let Some(rss_link) = find_feed_link(&html_content).unwrap() {
-        rss_link
-    } else {
-        return Err(Error::InvalidFeedLink(arg.link).to_string());
+#[tauri::command(async)]
+pub fn create_feed(
+    app: AppHandle,
+    db_state: State<DbState>,
+    http_state: State<HttpState>,
+    arg: FeedToCreate,
+) -> Result<String, Error> {
+    let icons_dir = favicon::icons_dir(&app_data_dir(&app)?);
+    let client = http_state.lock().clone();
+    let feed_client = match arg.proxy.as_deref() {
+        Some(proxy) => client.with_proxy(proxy)?,
+        None => client.clone(),
     };
 
-    let title = match fetch_feed_title(&link, proxy.as_deref()) {
-        Ok(title) => title,
-        Err(err) => return Err(err.to_string()),
-    };
+    let (link, title) = discover_feed(&arg.link, &feed_client)?;
 
     let arg = FeedToCreate {
         title,
         link,
         fetch_old_items: arg.fetch_old_items,
+        proxy: arg.proxy,
+    };
+
+    let id = {
+        let db = db_state.lock();
+        feeds::create(&db, &arg)?;
+        let id = db.last_insert_rowid() as i32;
+        let _ = create_new_items(&db, &client, Some(id), |_| ControlFlow::Continue(()));
+        id
     };
 
-    match feeds::create(&db, &arg) {
-        Ok(_) => {
-            let _ = create_new_items(&db, proxy.as_deref());
-            Ok("New feed added".to_string())
-        }
-        Err(err) => Err(err.to_string()),
+    if let Err(err) = favicon::refresh_feed(&db_state, &icons_dir, &client, id) {
+        eprintln!("Error fetching favicon of {}: {}", arg.link, err);
     }
+    Ok("New feed added".to_string())
 }
 
 #[tauri::command]
-pub fn read_all_feeds(db_state: State<DbState>) -> Result<Vec<Feed>, String> {
-    let db = db_state.db.lock().unwrap();
-    match feeds::read_all(&db) {
-        Ok(feeds) => Ok(feeds),
-        Err(err) => Err(err.to_string()),
-    }
+pub fn read_all_feeds(db_state: State<DbState>) -> Result<Vec<Feed>, Error> {
+    let db = db_state.lock();
+    feeds::read_all(&db)
 }
 
 #[tauri::command]
-pub fn read_feed(db_state: State<DbState>, id: i32) -> Result<Option<Feed>, String> {
-    let db = db_state.db.lock().unwrap();
-    match feeds::read(&db, id) {
-        Ok(feed) => Ok(feed),
-        Err(err) => Err(err.to_string()),
-    }
+pub fn read_feed(db_state: State<DbState>, id: i32) -> Result<Option<Feed>, Error> {
+    let db = db_state.lock();
+    feeds::read(&db, id)
 }
 
 #[tauri::command]
-pub fn update_feed(db_state: State<DbState>, arg: FeedToUpdate) -> Result<String, String> {
-    let db = db_state.db.lock().unwrap();
-    match feeds::update(&db, &arg) {
-        Ok(_) => Ok("Feed updated".to_string()),
-        Err(err) => Err(err.to_string()),
-    }
+pub fn update_feed(db_state: State<DbState>, arg: FeedToUpdate) -> Result<String, Error> {
+    let db = db_state.lock();
+    feeds::update(&db, &arg)?;
+    Ok("Feed updated".to_string())
 }
 
 #[tauri::command]
-pub fn delete_feed(db_state: State<DbState>, id: i32) -> Result<String, String> {
-    let db = db_state.db.lock().unwrap();
-    match feeds::delete(&db, id) {
-        Ok(_) => Ok("Feed deleted".to_string()),
-        Err(err) => Err(err.to_string()),
-    }
+pub fn delete_feed(db_state: State<DbState>, id: i32) -> Result<String, Error> {
+    let db = db_state.lock();
+    feeds::delete(&db, id)?;
+    Ok("Feed deleted".to_string())
+}
+
+#[tauri::command]
+pub fn read_feed_histories(db_state: State<DbState>, id: i32) -> Result<Vec<FeedHistory>, Error> {
+    let db = db_state.lock();
+    feeds::read_histories(&db, id)
+}
+
+// Async so that waiting for the worker does not block the main thread.
+#[tauri::command(async)]
+pub fn refresh_all_feeds(worker_state: State<WorkerState>) -> Result<Vec<FeedOutcome>, Error> {
+    worker::refresh(&worker_state, None)
+}
+
+#[tauri::command(async)]
+pub fn refresh_feed(worker_state: State<WorkerState>, id: i32) -> Result<FeedOutcome, Error> {
+    worker::refresh(&worker_state, Some(id))?
+        .into_iter()
+        .next()
+        .ok_or(Error::FeedNotFound(id))
 }
@@ -1,42 +1,67 @@
-use tauri::State;
+use tauri::{AppHandle, State};
 
 use crate::{
+    error::Error,
+    extractor,
     models::items::{self, Item, ItemReadOption, ItemToUpdate, ItemToUpdateAll},
-    DbState,
+    publisher, tray, DbState, HttpState,
 };
 
 #[tauri::command]
-pub fn read_all_items(db_state: State<DbState>, opt: ItemReadOption) -> Result<Vec<Item>, String> {
-    let db = db_state.db.lock().unwrap();
-    match items::read_all(&db, &opt) {
-        Ok(items) => Ok(items),
-        Err(err) => Err(err.to_string()),
-    }
+pub fn read_all_items(db_state: State<DbState>, opt: ItemReadOption) -> Result<Vec<Item>, Error> {
+    let db = db_state.lock();
+    items::read_all(&db, &opt)
 }
 
 #[tauri::command]
-pub fn count_all_items(db_state: State<DbState>, opt: ItemReadOption) -> Result<i64, String> {
-    let db = db_state.db.lock().unwrap();
-    match items::count_all(&db, &opt) {
-        Ok(count) => Ok(count),
-        Err(err) => Err(err.to_string()),
-    }
+pub fn count_all_items(db_state: State<DbState>, opt: ItemReadOption) -> Result<i64, Error> {
+    let db = db_state.lock();
+    items::count_all(&db, &opt)
 }
 
 #[tauri::command]
-pub fn update_item(db_state: State<DbState>, arg: ItemToUpdate) -> Result<String, String> {
-    let db = db_state.db.lock().unwrap();
-    match items::update(&db, &arg) {
-        Ok(_) => Ok("Item updated".to_string()),
-        Err(err) => Err(err.to_string()),
+pub fn update_item(
+    app: AppHandle,
+    db_state: State<DbState>,
+    arg: ItemToUpdate,
+) -> Result<String, Error> {
+    let db = db_state.lock();
+    items::update(&db, &arg)?;
+    if arg.status.is_some() {
+        tray::update(&app, &db);
     }
+    if arg.is_saved.is_some() {
+        publisher::refresh(&db);
+    }
+    Ok("Item updated".to_string())
 }
 
 #[tauri::command]
-pub fn update_items(db_state: State<DbState>, arg: ItemToUpdateAll) -> Result<String, String> {
-    let db = db_state.db.lock().unwrap();
-    match items::update_all(&db, &arg) {
-        Ok(_) => Ok("Items updated".to_string()),
-        Err(err) => Err(err.to_string()),
+pub fn update_items(
+    app: AppHandle,
+    db_state: State<DbState>,
+    arg: ItemToUpdateAll,
+) -> Result<String, Error> {
+    let db = db_state.lock();
+    if let Some(ids) = arg.opt.as_ref().and_then(|x| x.ids.as_deref()) {
+        items::check_exist(&db, ids)?;
+    }
+    items::update_all(&db, &arg)?;
+    if arg.status.is_some() {
+        tray::update(&app, &db);
     }
+    if arg.is_saved.is_some() {
+        publisher::refresh(&db);
+    }
+    Ok("Items updated".to_string())
+}
+
+#[tauri::command(async)]
+pub fn extract_item_content(
+    db_state: State<DbState>,
+    http_state: State<HttpState>,
+    id: i32,
+) -> Result<Option<String>, Error> {
+    let client = http_state.lock().clone();
+    extractor::extract_item_content(&db_state, id, &client)
 }
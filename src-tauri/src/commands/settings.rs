@@ -1,33 +1,57 @@
 use tauri::State;
 
 use crate::{
-    models::settings::{self, Setting, SettingKey, SettingToUpdate},
-    DbState,
+    error::Error,
+    models::settings::{self, Setting, SettingKey, SettingSchema, SettingToUpdate},
+    worker::{self, Message},
+    DbState, HttpState, WorkerState,
 };
 
 #[tauri::command]
-pub fn read_all_settings(db_state: State<DbState>) -> Result<Vec<Setting>, String> {
-    let db = db_state.db.lock().unwrap();
-    match settings::read_all(&db) {
-        Ok(settings) => Ok(settings),
-        Err(err) => Err(err.to_string()),
-    }
+pub fn read_all_settings(db_state: State<DbState>) -> Result<Vec<Setting>, Error> {
+    let db = db_state.lock();
+    settings::read_all(&db)
+}
+
+#[tauri::command]
+pub fn read_setting(db_state: State<DbState>, key: SettingKey) -> Result<Setting, Error> {
+    let db = db_state.lock();
+    settings::read(&db, &key)
+}
+
+#[tauri::command]
+pub fn read_setting_schemas() -> Vec<SettingSchema> {
+    SettingKey::ALL.iter().map(SettingKey::schema).collect()
 }
 
 #[tauri::command]
-pub fn read_setting(db_state: State<DbState>, key: SettingKey) -> Result<Setting, String> {
-    let db = db_state.db.lock().unwrap();
-    match settings::read(&db, &key) {
-        Ok(setting) => Ok(setting),
-        Err(err) => Err(err.to_string()),
+pub fn update_setting(
+    db_state: State<DbState>,
+    http_state: State<HttpState>,
+    worker_state: State<WorkerState>,
+    arg: SettingToUpdate,
+) -> Result<String, Error> {
+    let db = db_state.lock();
+    settings::update(&db, &arg)?;
+    if let Err(err) = http_state.lock().reload(&db) {
+        eprintln!("Error reloading http client: {}", err);
     }
+    let _ = worker::send(&worker_state, Message::SettingsUpdated);
+    Ok("Setting updated".to_string())
 }
 
 #[tauri::command]
-pub fn update_setting(db_state: State<DbState>, arg: SettingToUpdate) -> Result<String, String> {
-    let db = db_state.db.lock().unwrap();
-    match settings::update(&db, &arg) {
-        Ok(_) => Ok("Setting updated".to_string()),
-        Err(err) => Err(err.to_string()),
+pub fn reset_setting(
+    db_state: State<DbState>,
+    http_state: State<HttpState>,
+    worker_state: State<WorkerState>,
+    key: SettingKey,
+) -> Result<Setting, Error> {
+    let db = db_state.lock();
+    let setting = settings::reset(&db, &key)?;
+    if let Err(err) = http_state.lock().reload(&db) {
+        eprintln!("Error reloading http client: {}", err);
     }
+    let _ = worker::send(&worker_state, Message::SettingsUpdated);
+    Ok(setting)
 }
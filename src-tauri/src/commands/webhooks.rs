@@ -0,0 +1,44 @@
+use tauri::State;
+
+use crate::{
+    error::Error,
+    models::webhooks::{self, Webhook, WebhookDelivery, WebhookToCreate, WebhookToUpdate},
+    DbState,
+};
+
+#[tauri::command]
+pub fn create_webhook(db_state: State<DbState>, arg: WebhookToCreate) -> Result<String, Error> {
+    let db = db_state.lock();
+    webhooks::create(&db, &arg)?;
+    Ok("New webhook added".to_string())
+}
+
+#[tauri::command]
+pub fn read_all_webhooks(db_state: State<DbState>) -> Result<Vec<Webhook>, Error> {
+    let db = db_state.lock();
+    webhooks::read_all(&db)
+}
+
+#[tauri::command]
+pub fn update_webhook(db_state: State<DbState>, arg: WebhookToUpdate) -> Result<String, Error> {
+    let db = db_state.lock();
+    webhooks::update(&db, &arg)?;
+    Ok("Webhook updated".to_string())
+}
+
+#[tauri::command]
+pub fn delete_webhook(db_state: State<DbState>, id: i32) -> Result<String, Error> {
+    let db = db_state.lock();
+    webhooks::delete(&db, id)?;
+    Ok("Webhook deleted".to_string())
+}
+
+#[tauri::command]
+pub fn read_webhook_deliveries(
+    db_state: State<DbState>,
+    webhook: i32,
+    limit: Option<u64>,
+) -> Result<Vec<WebhookDelivery>, Error> {
+    let db = db_state.lock();
+    webhooks::read_all_deliveries(&db, webhook, limit)
+}
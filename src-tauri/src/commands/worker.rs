@@ -0,0 +1,25 @@
+use tauri::State;
+
+use crate::{
+    error::Error,
+    worker::{self, Message},
+    WorkerState,
+};
+
+#[tauri::command]
+pub fn pause_worker(worker_state: State<WorkerState>) -> Result<String, Error> {
+    worker::send(&worker_state, Message::Pause)?;
+    Ok("Worker paused".to_string())
+}
+
+#[tauri::command]
+pub fn resume_worker(worker_state: State<WorkerState>) -> Result<String, Error> {
+    worker::send(&worker_state, Message::Resume)?;
+    Ok("Worker resumed".to_string())
+}
+
+#[tauri::command]
+pub fn wake_worker(worker_state: State<WorkerState>) -> Result<String, Error> {
+    worker::send(&worker_state, Message::Wake)?;
+    Ok("Worker woken".to_string())
+}
@@ -0,0 +1,139 @@
+use std::sync::OnceLock;
+
+use chrono::{DateTime, FixedOffset, NaiveDate};
+use regex::Regex;
+
+const TIMEZONES: [(&str, &str); 28] = [
+    ("UT", "+0000"),
+    ("UTC", "+0000"),
+    ("GMT", "+0000"),
+    ("Z", "+0000"),
+    ("EST", "-0500"),
+    ("EDT", "-0400"),
+    ("CST", "-0600"),
+    ("CDT", "-0500"),
+    ("MST", "-0700"),
+    ("MDT", "-0600"),
+    ("PST", "-0800"),
+    ("PDT", "-0700"),
+    ("AKST", "-0900"),
+    ("AKDT", "-0800"),
+    ("HST", "-1000"),
+    ("WET", "+0000"),
+    ("BST", "+0100"),
+    ("CET", "+0100"),
+    ("CEST", "+0200"),
+    ("EET", "+0200"),
+    ("EEST", "+0300"),
+    ("MSK", "+0300"),
+    ("IST", "+0530"),
+    ("KST", "+0900"),
+    ("JST", "+0900"),
+    ("AEST", "+1000"),
+    ("AEDT", "+1100"),
+    ("NZST", "+1200"),
+];
+
+const DATE_TIME_FORMATS: [&str; 6] = [
+    "%d %B %Y %H:%M:%S%.f %z",
+    "%d %B %Y %H:%M %z",
+    "%B %d %Y %H:%M:%S%.f %z",
+    "%B %d %Y %H:%M %z",
+    "%Y-%m-%d %H:%M:%S%.f %z",
+    "%Y-%m-%d %H:%M %z",
+];
+
+const DATE_FORMATS: [&str; 4] = ["%Y-%m-%d", "%d %B %Y", "%B %d %Y", "%Y/%m/%d"];
+
+/// Parses the dates found in real-world feeds: RFC 2822 and RFC 3339, but also dates with
+/// localized or missing weekdays, two-digit years, named timezones and no timezone at all,
+/// which are assumed to be UTC.
+pub fn parse(x: &str) -> Option<DateTime<FixedOffset>> {
+    let x = x.trim();
+    if let Ok(date) = DateTime::parse_from_rfc2822(x).or(DateTime::parse_from_rfc3339(x)) {
+        return Some(date);
+    }
+
+    let normalized = normalize(x);
+    let (date, timezone) = match time_pattern().captures(&normalized) {
+        Some(captures) => (captures[1].to_string(), captures.get(2).map(|x| x.as_str())),
+        None => {
+            return DATE_FORMATS
+                .iter()
+                .find_map(|format| NaiveDate::parse_from_str(&normalized, format).ok())
+                .and_then(|x| x.and_hms_opt(0, 0, 0))
+                .map(|x| x.and_utc().fixed_offset());
+        }
+    };
+
+    let with_offset = format!("{} {}", date, offset(timezone)?);
+    DATE_TIME_FORMATS
+        .iter()
+        .find_map(|format| DateTime::parse_from_str(&with_offset, format).ok())
+}
+
+// Drops weekdays, comments and commas, separates the date from the time and expands
+// two-digit years like RFC 2822 does.
+fn normalize(x: &str) -> String {
+    static WEEKDAY: OnceLock<Regex> = OnceLock::new();
+    static COMMENT: OnceLock<Regex> = OnceLock::new();
+    static ISO_SEPARATOR: OnceLock<Regex> = OnceLock::new();
+    static SHORT_YEAR: OnceLock<Regex> = OnceLock::new();
+
+    // A leading word is a weekday if a comma, a month or a day and a month follow it.
+    let weekday = WEEKDAY
+        .get_or_init(|| Regex::new(r"^\p{L}+\.?(?:,\s*|\s+(\d{1,2}\s+\p{L}|\p{L}))").unwrap());
+    let comment = COMMENT.get_or_init(|| Regex::new(r"\s*\([^)]*\)$").unwrap());
+    let iso_separator = ISO_SEPARATOR.get_or_init(|| Regex::new(r"^(\d{4}-\d{2}-\d{2})T").unwrap());
+    let short_year = SHORT_YEAR.get_or_init(|| Regex::new(r"^(\d{1,2} \p{L}+) (\d{2}) ").unwrap());
+
+    let x = comment.replace(x, "");
+    let x = weekday.replace(&x, "$1");
+    let x = iso_separator
+        .replace(&x, "$1 ")
+        .replace(',', " ")
+        .replace("Sept ", "Sep ");
+    let x = x.split_whitespace().collect::<Vec<_>>().join(" ");
+
+    short_year
+        .replace(&x, |captures: &regex::Captures| {
+            let year = captures[2].parse::<i32>().unwrap_or_default();
+            let century = if year < 50 { 2000 } else { 1900 };
+            format!("{} {} ", &captures[1], century + year)
+        })
+        .into_owned()
+}
+
+fn time_pattern() -> &'static Regex {
+    static TIME: OnceLock<Regex> = OnceLock::new();
+    TIME.get_or_init(|| {
+        Regex::new(concat!(
+            r"^(.*?\d{1,2}:\d{2}(?::\d{2}(?:\.\d+)?)?)\s*",
+            r"((?:GMT|UTC)?[+-]\d{2}(?::?\d{2})?|[A-Za-z]{1,5})?$"
+        ))
+        .unwrap()
+    })
+}
+
+// No timezone means UTC.
+fn offset(timezone: Option<&str>) -> Option<String> {
+    let Some(timezone) = timezone else {
+        return Some("+0000".to_string());
+    };
+
+    // Such as "GMT+0900", written by JavaScript.
+    let numeric = timezone.trim_start_matches("GMT").trim_start_matches("UTC");
+    if numeric.starts_with(['+', '-']) {
+        let digits = numeric.replace(':', "");
+        return Some(if digits.len() == 3 {
+            format!("{}00", digits)
+        } else {
+            digits
+        });
+    }
+
+    TIMEZONES
+        .iter()
+        .find(|(name, _)| name.eq_ignore_ascii_case(timezone))
+        .map(|(_, offset)| offset.to_string())
+}
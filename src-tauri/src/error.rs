@@ -1,3 +1,5 @@
+use serde::ser::{Serialize, SerializeStruct, Serializer};
+use serde_json::{json, Value};
 use std::io;
 
 #[derive(thiserror::Error, Debug)]
@@ -8,6 +10,9 @@ pub enum Error {
     #[error("invalid key `{0}` for `{1}`")]
     InvalidEnumKey(String, String),
 
+    #[error("invalid setting `{key}`: {reason}")]
+    InvalidSetting { key: String, reason: String },
+
     #[error("invalid feed link `{0}`")]
     InvalidFeedLink(String),
 
@@ -17,17 +22,41 @@ pub enum Error {
     #[error("failed to parse syndication feed")]
     SyndicationParsingFailure,
 
+    #[error("feed is unreachable: HTTP {status}")]
+    FeedUnreachable { status: u16 },
+
     #[error("failed to fetch feed: {0}")]
     FetchFeedFailure(String),
 
     #[error("failed to fetch feed items: {0}")]
     FetchFeedItemsFailure(String),
 
+    #[error("failed to start api server: {0}")]
+    StartServerFailure(String),
+
+    #[error("item `{0}` not found")]
+    ItemNotFound(i32),
+
+    #[error("feed `{0}` not found")]
+    FeedNotFound(i32),
+
+    #[error("webhook `{0}` not found")]
+    WebhookNotFound(i32),
+
+    #[error("failed to sync with miniflux: {0}")]
+    MinifluxSyncFailure(String),
+
+    #[error("worker stopped")]
+    WorkerStopped,
+
+    #[error("app data directory not found")]
+    AppDataDirNotFound,
+
     #[error("empty string")]
     EmptyString,
 
-    #[error("unknown")]
-    Unknown,
+    #[error("nothing to update")]
+    NothingToUpdate,
 
     #[error(transparent)]
     RusqliteError {
@@ -47,6 +76,12 @@ pub enum Error {
         source: reqwest::Error,
     },
 
+    #[error(transparent)]
+    SerdeJsonError {
+        #[from]
+        source: serde_json::Error,
+    },
+
     #[error(transparent)]
     IoError {
         #[from]
@@ -54,4 +89,70 @@ pub enum Error {
     },
 }
 
+impl Error {
+    /// A stable identifier the frontend can match on, unlike the message.
+    pub fn code(&self) -> &'static str {
+        match self {
+            Self::InvalidValue(_) => "invalid_value",
+            Self::InvalidEnumKey(_, _) => "invalid_enum_key",
+            Self::InvalidSetting { .. } => "invalid_setting",
+            Self::InvalidFeedLink(_) => "invalid_feed_link",
+            Self::Forbidden => "forbidden",
+            Self::SyndicationParsingFailure => "syndication_parsing_failure",
+            Self::FeedUnreachable { .. } => "feed_unreachable",
+            Self::FetchFeedFailure(_) => "fetch_feed_failure",
+            Self::FetchFeedItemsFailure(_) => "fetch_feed_items_failure",
+            Self::StartServerFailure(_) => "start_server_failure",
+            Self::ItemNotFound(_) => "item_not_found",
+            Self::FeedNotFound(_) => "feed_not_found",
+            Self::WebhookNotFound(_) => "webhook_not_found",
+            Self::MinifluxSyncFailure(_) => "miniflux_sync_failure",
+            Self::WorkerStopped => "worker_stopped",
+            Self::AppDataDirNotFound => "app_data_dir_not_found",
+            Self::EmptyString => "empty_string",
+            Self::NothingToUpdate => "nothing_to_update",
+            Self::RusqliteError { .. } => "database",
+            Self::SeaQueryError { .. } => "query",
+            Self::ReqwestError { .. } => "network",
+            Self::SerdeJsonError { .. } => "json",
+            Self::IoError { .. } => "io",
+        }
+    }
+
+    /// The input the error is about, such as the key of an invalid setting.
+    pub fn field(&self) -> Option<&str> {
+        match self {
+            Self::InvalidSetting { key, .. } => Some(key),
+            Self::InvalidFeedLink(_) => Some("link"),
+            _ => None,
+        }
+    }
+
+    /// Details to react on, in addition to the code.
+    pub fn detail(&self) -> Option<Value> {
+        match self {
+            Self::InvalidValue(value) | Self::InvalidFeedLink(value) => Some(json!(value)),
+            Self::InvalidEnumKey(key, _) => Some(json!(key)),
+            Self::InvalidSetting { reason, .. } => Some(json!(reason)),
+            Self::FeedUnreachable { status } => Some(json!({ "status": status })),
+            Self::ItemNotFound(id) | Self::FeedNotFound(id) | Self::WebhookNotFound(id) => {
+                Some(json!({ "id": id }))
+            }
+            _ => None,
+        }
+    }
+}
+
+// Commands return errors as `{ code, message, field, detail }`.
+impl Serialize for Error {
+    fn serialize<S: Serializer>(&self, serializer: S) -> std::result::Result<S::Ok, S::Error> {
+        let mut state = serializer.serialize_struct("Error", 4)?;
+        state.serialize_field("code", self.code())?;
+        state.serialize_field("message", &self.to_string())?;
+        state.serialize_field("field", &self.field())?;
+        state.serialize_field("detail", &self.detail())?;
+        state.end()
+    }
+}
+
 pub type Result<T> = std::result::Result<T, Error>;
@@ -0,0 +1,117 @@
+use std::sync::OnceLock;
+
+use regex::Regex;
+use scraper::{ElementRef, Html, Selector};
+
+use crate::error::{Error, Result};
+use crate::http::HttpClient;
+use crate::models::items;
+use crate::sanitizer::sanitize;
+use crate::syndication::fetch_content;
+use crate::DbState;
+
+// Same heuristics as Readability: paragraphs vote for their parent, weighted by the class and id.
+const POSITIVE: &str = r"(?i)article|body|content|entry|main|page|post|story|text";
+const NEGATIVE: &str = r"(?i)ad-|banner|comment|footer|header|menu|nav|promo|related|share|sidebar|social|sponsor|widget";
+const MIN_PARAGRAPH_LENGTH: usize = 25;
+const MIN_SCORE: f64 = 10.0;
+
+/// Downloads the page at `link` and extracts its main article body.
+pub fn fetch_full_content(link: &str, client: &HttpClient) -> Result<Option<String>> {
+    let html_content = fetch_content(link, client)?;
+    Ok(extract(&html_content, link))
+}
+
+/// Extracts the full content of the item and stores it, if any. The database is not locked
+/// while the page is fetched, so that other commands are not held up.
+pub fn extract_item_content(
+    db_state: &DbState,
+    id: i32,
+    client: &HttpClient,
+) -> Result<Option<String>> {
+    let (item, client) = {
+        let db = db_state.lock();
+        let item = items::read(&db, id)?.ok_or(Error::ItemNotFound(id))?;
+        let client = client.for_feed_id(&db, item.feed.id)?;
+        (item, client)
+    };
+    let content = fetch_full_content(&item.link, &client)?;
+    if let Some(content) = &content {
+        items::update_full_content(&db_state.lock(), id, content)?;
+    }
+
+    Ok(content)
+}
+
+/// Extracts the main article body of the HTML document and returns it sanitized,
+/// with relative links resolved against `base`. Returns `None` if no body was found.
+pub fn extract(html_content: &str, base: &str) -> Option<String> {
+    let document = Html::parse_document(html_content);
+    let selector =
+        Selector::parse("article, main, section, div, td, [itemprop='articleBody']").unwrap();
+
+    let best = document
+        .select(&selector)
+        .map(|x| (score(&x), x))
+        .filter(|(score, _)| *score >= MIN_SCORE)
+        .max_by(|(a, _), (b, _)| a.total_cmp(b))
+        .map(|(_, x)| x)?;
+
+    Some(sanitize(&best.inner_html(), Some(base)))
+}
+
+fn score(element: &ElementRef) -> f64 {
+    let paragraphs = element
+        .children()
+        .filter_map(ElementRef::wrap)
+        .filter(|x| matches!(x.value().name(), "p" | "pre" | "blockquote"))
+        .map(|x| x.text().collect::<String>())
+        .filter(|x| x.trim().len() >= MIN_PARAGRAPH_LENGTH)
+        .map(|x| 1.0 + x.matches(',').count() as f64 + (x.len() as f64 / 100.0).min(3.0))
+        .sum::<f64>();
+
+    if paragraphs == 0.0 {
+        return 0.0;
+    }
+
+    let name = format!(
+        "{} {}",
+        element.value().attr("class").unwrap_or_default(),
+        element.value().attr("id").unwrap_or_default()
+    );
+    let mut weight = match element.value().name() {
+        "article" => 10.0,
+        "main" => 5.0,
+        _ => 0.0,
+    };
+    if regex(&POSITIVE_REGEX, POSITIVE).is_match(&name) {
+        weight += 25.0;
+    }
+    if regex(&NEGATIVE_REGEX, NEGATIVE).is_match(&name) {
+        weight -= 25.0;
+    }
+
+    (paragraphs + weight) * (1.0 - link_density(element))
+}
+
+fn link_density(element: &ElementRef) -> f64 {
+    let length = element.text().map(str::len).sum::<usize>();
+    if length == 0 {
+        return 0.0;
+    }
+
+    let links = element
+        .select(&Selector::parse("a").unwrap())
+        .flat_map(|x| x.text())
+        .map(str::len)
+        .sum::<usize>();
+
+    links as f64 / length as f64
+}
+
+static POSITIVE_REGEX: OnceLock<Regex> = OnceLock::new();
+static NEGATIVE_REGEX: OnceLock<Regex> = OnceLock::new();
+
+fn regex(cell: &'static OnceLock<Regex>, pattern: &str) -> &'static Regex {
+    cell.get_or_init(|| Regex::new(pattern).unwrap())
+}
@@ -0,0 +1,150 @@
+use std::fs;
+use std::path::{Path, PathBuf};
+use std::time::Duration;
+
+use rusqlite::Connection;
+use scraper::{Html, Selector};
+use url::Url;
+
+use crate::assets::asset_url;
+use crate::error::{Error, Result};
+use crate::http::HttpClient;
+use crate::models::feeds::{self, Feed, FeedStatus};
+use crate::DbState;
+
+const MAX_AGE: Duration = Duration::from_secs(7 * 24 * 60 * 60);
+const MAX_ICON_SIZE: usize = 1024 * 1024;
+
+pub fn icons_dir(app_data_dir: &Path) -> PathBuf {
+    app_data_dir.join("icons")
+}
+
+pub fn icon_url(feed: i32) -> String {
+    asset_url(&format!("icons/{}", feed))
+}
+
+/// Fetches the favicons of the subscribed feeds that have none or a week-old one.
+/// Returns the number of updated icons.
+pub fn refresh(db: &Connection, dir: &Path, client: &HttpClient) -> Result<usize> {
+    fs::create_dir_all(dir)?;
+
+    let mut updated = 0;
+    for feed in feeds::read_all(db)? {
+        if feed.status != FeedStatus::Subscribed || !is_stale(dir, feed.id) {
+            continue;
+        }
+
+        match client
+            .for_feed(&feed)
+            .and_then(|client| fetch(&client, dir, &feed))
+        {
+            Ok(true) => {
+                feeds::update_icon(db, feed.id, &icon_url(feed.id))?;
+                updated += 1;
+            }
+            Ok(false) => {}
+            Err(err) => eprintln!("Error fetching favicon of {}: {}", feed.link, err),
+        }
+    }
+
+    Ok(updated)
+}
+
+/// Fetches the favicon of a single feed, such as a new one. The database is only locked to
+/// read the feed and store the icon, not while the icon is fetched.
+pub fn refresh_feed(db_state: &DbState, dir: &Path, client: &HttpClient, id: i32) -> Result<bool> {
+    let feed = feeds::read(&db_state.lock(), id)?.ok_or(Error::FeedNotFound(id))?;
+
+    fs::create_dir_all(dir)?;
+    let fetched = fetch(&client.for_feed(&feed)?, dir, &feed)?;
+    if fetched {
+        feeds::update_icon(&db_state.lock(), id, &icon_url(id))?;
+    }
+
+    Ok(fetched)
+}
+
+/// Reads the favicon requested through the asset protocol.
+pub fn read(dir: &Path, uri: &str) -> Option<(&'static str, Vec<u8>)> {
+    let feed = uri.rsplit('/').next()?.parse::<i32>().ok()?;
+    let bytes = fs::read(dir.join(feed.to_string())).ok()?;
+
+    mime_type(&bytes).map(|x| (x, bytes))
+}
+
+/// Finds the icons declared by the page, from the most to the least preferred.
+pub fn find_favicon_links(html_content: &str, base: &Url) -> Vec<String> {
+    let document = Html::parse_document(html_content);
+
+    [
+        "link[rel~='icon'][href]",
+        "link[rel='apple-touch-icon'][href]",
+    ]
+    .iter()
+    .flat_map(|x| {
+        let selector = Selector::parse(x).unwrap();
+        document
+            .select(&selector)
+            .filter_map(|x| base.join(x.value().attr("href")?).ok())
+            .map(String::from)
+            .collect::<Vec<_>>()
+    })
+    .collect()
+}
+
+fn is_stale(dir: &Path, feed: i32) -> bool {
+    fs::metadata(dir.join(feed.to_string()))
+        .and_then(|x| x.modified())
+        .map(|x| x.elapsed().map_or(true, |x| x > MAX_AGE))
+        .unwrap_or(true)
+}
+
+// An empty file is written when no icon was found so that the site is not asked again until it goes stale.
+fn fetch(client: &HttpClient, dir: &Path, feed: &Feed) -> Result<bool> {
+    let site = feed.site_link.as_deref().unwrap_or(&feed.link);
+    let base = Url::parse(site).map_err(|_| Error::InvalidValue(site.to_string()))?;
+
+    let mut candidates = client
+        .get(base.as_str())
+        .and_then(|x| Ok(x.text()?))
+        .map(|x| find_favicon_links(&x, &base))
+        .unwrap_or_default();
+    candidates.extend(base.join("/favicon.ico").ok().map(String::from));
+    candidates.extend(feed.image.clone());
+
+    let path = dir.join(feed.id.to_string());
+    for candidate in candidates {
+        if let Some(bytes) = download(client, &candidate) {
+            fs::write(path, bytes)?;
+            return Ok(true);
+        }
+    }
+
+    fs::write(path, [])?;
+    Ok(false)
+}
+
+fn download(client: &HttpClient, url: &str) -> Option<Vec<u8>> {
+    let res = client.get(url).ok()?.error_for_status().ok()?;
+    let bytes = res.bytes().ok()?;
+
+    if bytes.len() > MAX_ICON_SIZE || mime_type(&bytes).is_none() {
+        return None;
+    }
+
+    Some(bytes.to_vec())
+}
+
+fn mime_type(bytes: &[u8]) -> Option<&'static str> {
+    match bytes {
+        [0x89, b'P', b'N', b'G', ..] => Some("image/png"),
+        [b'G', b'I', b'F', b'8', ..] => Some("image/gif"),
+        [0xFF, 0xD8, 0xFF, ..] => Some("image/jpeg"),
+        [0, 0, 1, 0, ..] => Some("image/x-icon"),
+        [b'R', b'I', b'F', b'F', _, _, _, _, b'W', b'E', b'B', b'P', ..] => Some("image/webp"),
+        _ if String::from_utf8_lossy(&bytes[..bytes.len().min(256)]).contains("<svg") => {
+            Some("image/svg+xml")
+        }
+        _ => None,
+    }
+}
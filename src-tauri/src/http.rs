@@ -0,0 +1,311 @@
+use std::collections::{BTreeMap, HashMap};
+use std::sync::{Arc, Mutex, PoisonError};
+use std::thread;
+use std::time::Duration;
+
+use reqwest::blocking::{Client, RequestBuilder, Response};
+use reqwest::header::{HeaderMap, HeaderName, HeaderValue, COOKIE, LOCATION, USER_AGENT};
+use reqwest::redirect::Policy;
+use reqwest::{Method, StatusCode};
+use rusqlite::Connection;
+use url::Url;
+
+use crate::error::{Error, Result};
+use crate::models::feeds::{self, Credentials, Feed};
+use crate::models::settings::{self, SettingKey};
+
+pub const DEFAULT_USER_AGENT: &str = concat!(
+    "Collie/",
+    env!("CARGO_PKG_VERSION"),
+    " (+https://github.com/parksb/collie)"
+);
+const MAX_REDIRECTS: usize = 10;
+const RETRY_DELAY: Duration = Duration::from_millis(500);
+
+#[derive(Clone, Debug, PartialEq)]
+pub struct HttpConfig {
+    pub connect_timeout: Duration,
+    pub timeout: Duration,
+    pub retries: u32,
+    pub user_agent: String,
+    pub proxy: Option<String>,
+}
+
+impl Default for HttpConfig {
+    fn default() -> Self {
+        Self {
+            connect_timeout: Duration::from_secs(10),
+            timeout: Duration::from_secs(30),
+            retries: 2,
+            user_agent: DEFAULT_USER_AGENT.to_string(),
+            proxy: None,
+        }
+    }
+}
+
+impl HttpConfig {
+    pub fn from_settings(db: &Connection) -> Self {
+        let seconds = |key| Duration::from_secs(settings::integer(db, &key) as u64);
+        let user_agent = settings::value(db, &SettingKey::UserAgent);
+        let proxy = settings::value(db, &SettingKey::Proxy);
+
+        Self {
+            connect_timeout: seconds(SettingKey::HttpConnectTimeout),
+            timeout: seconds(SettingKey::HttpTimeout),
+            retries: settings::integer(db, &SettingKey::HttpRetries) as u32,
+            user_agent: if user_agent.trim().is_empty() {
+                DEFAULT_USER_AGENT.to_string()
+            } else {
+                user_agent
+            },
+            proxy: Some(proxy).filter(|x| !x.is_empty()),
+        }
+    }
+}
+
+pub struct Fetched {
+    pub response: Response,
+    /// Where a chain of permanent redirects ended, if the link was permanently redirected.
+    pub redirected_to: Option<String>,
+}
+
+/// The client shared by everything that fetches feeds, pages and images. Cloning is cheap
+/// and keeps the connection pool.
+#[derive(Clone)]
+pub struct HttpClient {
+    client: Client,
+    config: HttpConfig,
+    user_agent: Option<String>,
+    credentials: Option<Credentials>,
+    headers: BTreeMap<String, String>,
+    // Clients for the proxies of feeds that do not use the global one.
+    proxied: Arc<Mutex<HashMap<String, Client>>>,
+}
+
+impl HttpClient {
+    pub fn new(config: HttpConfig) -> Result<Self> {
+        // An invalid global proxy is ignored, as it always has been.
+        let proxy = config
+            .proxy
+            .as_deref()
+            .filter(|x| reqwest::Proxy::all(*x).is_ok());
+
+        Ok(Self {
+            client: build(&config, proxy)?,
+            config,
+            user_agent: None,
+            credentials: None,
+            headers: BTreeMap::new(),
+            proxied: Arc::new(Mutex::new(HashMap::new())),
+        })
+    }
+
+    pub fn from_settings(db: &Connection) -> Result<Self> {
+        Self::new(HttpConfig::from_settings(db))
+    }
+
+    /// Rebuilds the client if the settings it was built from changed.
+    pub fn reload(&mut self, db: &Connection) -> Result<()> {
+        let config = HttpConfig::from_settings(db);
+        if config != self.config {
+            *self = Self::new(config)?;
+        }
+
+        Ok(())
+    }
+
+    /// The same client going through `proxy` instead of the global proxy, or connecting
+    /// directly if it is empty.
+    pub fn with_proxy(&self, proxy: &str) -> Result<Self> {
+        if self.config.proxy.as_deref() == Some(proxy) {
+            return Ok(self.clone());
+        }
+
+        let mut proxied = self.proxied.lock().unwrap_or_else(PoisonError::into_inner);
+        let client = match proxied.get(proxy) {
+            Some(client) => client.clone(),
+            None => {
+                let client = build(&self.config, Some(proxy))?;
+                proxied.insert(proxy.to_string(), client.clone());
+                client
+            }
+        };
+
+        Ok(Self {
+            client,
+            ..self.clone()
+        })
+    }
+
+    /// The same client, going through the proxy and sending the user agent, credentials and
+    /// headers configured for the feed.
+    pub fn for_feed(&self, feed: &Feed) -> Result<Self> {
+        let client = match feed.proxy.as_deref() {
+            Some(proxy) => self.with_proxy(proxy)?,
+            None => self.clone(),
+        };
+
+        Ok(Self {
+            user_agent: feed.user_agent.clone().filter(|x| !x.trim().is_empty()),
+            credentials: feed.credentials.clone(),
+            headers: feed.headers.clone(),
+            ..client
+        })
+    }
+
+    /// The client for the feed with the id, such as the feed of an item whose page or images
+    /// are fetched. Falls back to the same client if the feed no longer exists.
+    pub fn for_feed_id(&self, db: &Connection, id: i32) -> Result<Self> {
+        match feeds::read(db, id)? {
+            Some(feed) => self.for_feed(&feed),
+            None => Ok(self.clone()),
+        }
+    }
+
+    pub fn config(&self) -> &HttpConfig {
+        &self.config
+    }
+
+    /// Starts a request that is not a fetch, such as an API call or a webhook delivery. It
+    /// goes through the same proxy with the same timeouts and user agent, but redirects are
+    /// not followed and no feed credentials are sent.
+    pub fn prepare(&self, method: Method, link: &str) -> RequestBuilder {
+        self.client
+            .request(method, link)
+            .header(USER_AGENT, &self.config.user_agent)
+    }
+
+    /// Sends the request, retrying transient failures.
+    pub fn execute(&self, request: RequestBuilder) -> Result<Response> {
+        let mut attempt = 0;
+        loop {
+            // Only requests with streamed bodies cannot be cloned, and those are not retried.
+            let Some(cloned) = request.try_clone() else {
+                return Ok(request.send()?);
+            };
+            let result = cloned.send();
+
+            let is_transient = match &result {
+                Ok(response) => is_transient_status(response.status()),
+                Err(err) => err.is_timeout() || err.is_connect(),
+            };
+            if !is_transient || attempt >= self.config.retries {
+                return Ok(result?);
+            }
+
+            thread::sleep(backoff(attempt));
+            attempt += 1;
+        }
+    }
+
+    pub fn get(&self, link: &str) -> Result<Response> {
+        Ok(self.fetch(link)?.response)
+    }
+
+    /// Follows redirects like a browser would, retrying transient failures of each request.
+    /// Credentials and custom headers are only sent to the origin of the link.
+    pub fn fetch(&self, link: &str) -> Result<Fetched> {
+        let mut url = Url::parse(link).map_err(|_| Error::InvalidValue(link.to_string()))?;
+        let origin = url.origin();
+        let mut redirected_to = None;
+        let mut is_permanent = true;
+
+        for _ in 0..=MAX_REDIRECTS {
+            let response = self.send(&url, url.origin() == origin)?;
+            let location = response
+                .headers()
+                .get(LOCATION)
+                .and_then(|x| x.to_str().ok())
+                .and_then(|x| url.join(x).ok());
+
+            match location {
+                Some(location) if response.status().is_redirection() => {
+                    is_permanent &= matches!(
+                        response.status(),
+                        StatusCode::MOVED_PERMANENTLY | StatusCode::PERMANENT_REDIRECT
+                    );
+                    redirected_to = is_permanent.then(|| location.to_string());
+                    url = location;
+                }
+                _ => {
+                    return Ok(Fetched {
+                        response,
+                        redirected_to,
+                    })
+                }
+            }
+        }
+
+        Err(Error::InvalidValue(format!(
+            "too many redirects from {}",
+            link
+        )))
+    }
+
+    fn send(&self, url: &Url, authorize: bool) -> Result<Response> {
+        self.execute(self.request(url, authorize))
+    }
+
+    // Custom headers replace the default ones, but not the credentials.
+    fn request(&self, url: &Url, authorize: bool) -> RequestBuilder {
+        let user_agent = self.user_agent.as_ref().unwrap_or(&self.config.user_agent);
+        let request = self.client.get(url.clone()).header(USER_AGENT, user_agent);
+        if !authorize {
+            return request;
+        }
+
+        let headers = self
+            .headers
+            .iter()
+            .filter_map(|(name, value)| {
+                Some((
+                    HeaderName::from_bytes(name.as_bytes()).ok()?,
+                    HeaderValue::from_str(value).ok()?,
+                ))
+            })
+            .collect::<HeaderMap>();
+        let request = request.headers(headers);
+
+        match &self.credentials {
+            Some(Credentials::Basic { username, password }) => {
+                request.basic_auth(username, Some(password))
+            }
+            Some(Credentials::Bearer { token }) => request.bearer_auth(token),
+            Some(Credentials::Cookie { cookie }) => request.header(COOKIE, cookie),
+            None => request,
+        }
+    }
+}
+
+// Without a proxy, the system proxy is used. An empty one disables proxies altogether.
+fn build(config: &HttpConfig, proxy: Option<&str>) -> Result<Client> {
+    let builder = Client::builder()
+        .connect_timeout(config.connect_timeout)
+        .timeout(config.timeout)
+        .gzip(true)
+        .brotli(true)
+        .redirect(Policy::none());
+
+    Ok(match proxy {
+        Some("") => builder.no_proxy(),
+        Some(proxy) => builder.proxy(reqwest::Proxy::all(proxy)?),
+        None => builder,
+    }
+    .build()?)
+}
+
+fn is_transient_status(status: StatusCode) -> bool {
+    matches!(
+        status,
+        StatusCode::TOO_MANY_REQUESTS
+            | StatusCode::BAD_GATEWAY
+            | StatusCode::SERVICE_UNAVAILABLE
+            | StatusCode::GATEWAY_TIMEOUT
+    )
+}
+
+// Exponential backoff with jitter so that feeds on the same host do not retry in lockstep.
+fn backoff(attempt: u32) -> Duration {
+    let max = RETRY_DELAY * 2u32.pow(attempt);
+    max / 2 + Duration::from_millis(fastrand::u64(0..=max.as_millis() as u64 / 2))
+}
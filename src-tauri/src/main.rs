@@ -2,75 +2,221 @@
 #![cfg_attr(not(debug_assertions), windows_subsystem = "windows")]
 
 use rusqlite::Connection;
-use std::{fs, path::PathBuf, sync::Mutex};
-use tauri::Manager;
+use std::{
+    fs,
+    path::PathBuf,
+    sync::{atomic::AtomicBool, mpsc::Sender, Arc, Mutex, MutexGuard, PoisonError},
+    thread::JoinHandle,
+};
+use tauri::{http::ResponseBuilder, AppHandle, Manager};
 
 pub mod models {
+    pub mod assets;
     pub mod database;
     pub mod feeds;
     pub mod items;
     pub mod settings;
+    pub mod webhooks;
+}
+
+pub mod api {
+    pub mod fever;
+    pub mod greader;
+    pub mod server;
 }
 
 pub mod commands {
     pub mod feeds;
     pub mod items;
     pub mod settings;
+    pub mod webhooks;
+    pub mod worker;
 }
 
+pub mod assets;
+pub mod charset;
+pub mod date;
 pub mod error;
+pub mod extractor;
+pub mod favicon;
+pub mod http;
+pub mod miniflux;
+pub mod network;
+pub mod notifier;
 pub mod producer;
+pub mod publisher;
+pub mod sanitizer;
 pub mod syndication;
+pub mod tray;
+pub mod webhook;
 pub mod worker;
 
 #[cfg(test)]
 mod tests {
+    mod assets;
+    mod charset;
+    mod commands;
+    mod database;
+    mod date;
+    mod extractor;
+    mod favicon;
+    mod fever;
+    mod greader;
+    mod helpers;
+    mod http;
+    mod miniflux;
+    mod network;
+    mod notifier;
+    mod producer;
+    mod publisher;
+    mod sanitizer;
+    mod settings;
     mod syndication;
+    mod webhook;
 }
 
 pub struct DbState {
     db: Mutex<Connection>,
 }
 
+pub struct HttpState {
+    client: Mutex<http::HttpClient>,
+}
+
+// A command that panicked while holding a lock poisons it, which would make every later
+// command panic too. SQLite rolls back what the panicking command left unfinished, and the
+// client is only replaced as a whole, so both are still safe to use.
+impl DbState {
+    pub fn lock(&self) -> MutexGuard<'_, Connection> {
+        self.db.lock().unwrap_or_else(PoisonError::into_inner)
+    }
+}
+
+impl HttpState {
+    pub fn lock(&self) -> MutexGuard<'_, http::HttpClient> {
+        self.client.lock().unwrap_or_else(PoisonError::into_inner)
+    }
+}
+
+pub struct WorkerState {
+    sender: Mutex<Sender<worker::Message>>,
+    thread: Mutex<Option<JoinHandle<()>>>,
+    paused: Arc<AtomicBool>,
+    stopping: Arc<AtomicBool>,
+}
+
 fn main() {
-    let _ = tauri::Builder::default()
+    let app = tauri::Builder::default()
         .invoke_handler(tauri::generate_handler![
             commands::feeds::create_feed,
             commands::feeds::read_all_feeds,
             commands::feeds::read_feed,
             commands::feeds::update_feed,
             commands::feeds::delete_feed,
+            commands::feeds::read_feed_histories,
+            commands::feeds::refresh_all_feeds,
+            commands::feeds::refresh_feed,
             commands::items::read_all_items,
             commands::items::count_all_items,
             commands::items::update_item,
             commands::items::update_items,
+            commands::items::extract_item_content,
             commands::settings::read_all_settings,
             commands::settings::read_setting,
+            commands::settings::read_setting_schemas,
             commands::settings::update_setting,
+            commands::settings::reset_setting,
+            commands::webhooks::create_webhook,
+            commands::webhooks::read_all_webhooks,
+            commands::webhooks::update_webhook,
+            commands::webhooks::delete_webhook,
+            commands::webhooks::read_webhook_deliveries,
+            commands::worker::pause_worker,
+            commands::worker::resume_worker,
+            commands::worker::wake_worker,
         ])
-        .setup(|app| {
-            let app_data_dir = if cfg!(dev) {
-                PathBuf::from("data")
+        .register_uri_scheme_protocol(assets::PROTOCOL, |app, request| {
+            let client = app.state::<HttpState>().lock().clone();
+            let app_data_dir = app_data_dir(app)?;
+
+            let asset = if request.uri().contains("/icons/") {
+                favicon::read(&favicon::icons_dir(&app_data_dir), request.uri())
             } else {
-                app.handle().path_resolver().app_data_dir().unwrap()
+                let dir = assets::cache_dir(&app_data_dir);
+                assets::read(&app.state::<DbState>(), &dir, request.uri(), &client)?
             };
 
-            fs::create_dir_all(&app_data_dir).unwrap();
-            let db = models::database::open_connection(&app_data_dir).unwrap();
+            match asset {
+                Some((mime_type, bytes)) => ResponseBuilder::new().mimetype(mime_type).body(bytes),
+                None => ResponseBuilder::new().status(404).body(vec![]),
+            }
+        })
+        .setup(|app| {
+            let app_data_dir = app_data_dir(&app.handle())?;
+
+            fs::create_dir_all(&app_data_dir)?;
+            let db = models::database::open_connection(&app_data_dir)?;
             let _ = models::database::migrate(&db);
+            if let Err(err) = sanitizer::sanitize_legacy_items(&db) {
+                eprintln!("Error sanitizing items: {}", err);
+            }
 
+            tray::update(&app.handle(), &db);
             app.manage(DbState { db: Mutex::new(db) });
-            worker::start(app, &app_data_dir);
+            worker::start(app, &app_data_dir)?;
+
+            if let Err(err) = api::server::start(&app_data_dir) {
+                eprintln!("Error starting api server: {}", err);
+            }
 
             Ok(())
         })
-        .on_window_event(|event| {
-            if let tauri::WindowEvent::CloseRequested { api, .. } = event.event() {
+        .system_tray(tray::build())
+        .on_system_tray_event(tray::handle_event)
+        .on_window_event(|event| match event.event() {
+            tauri::WindowEvent::CloseRequested { api, .. } => {
                 hide_window(&event);
                 api.prevent_close();
             }
+            tauri::WindowEvent::Focused(true) => worker::focused(&event.window().app_handle()),
+            _ => {}
         })
-        .run(tauri::generate_context!("tauri.conf.json"));
+        .build(tauri::generate_context!("tauri.conf.json"));
+
+    match app {
+        Ok(app) => app.run(|app, event| {
+            if let tauri::RunEvent::Exit = event {
+                worker::stop(&app.state::<WorkerState>());
+            }
+        }),
+        Err(err) => eprintln!("Error starting collie: {}", err),
+    }
+}
+
+pub fn app_data_dir(app: &AppHandle) -> error::Result<PathBuf> {
+    if cfg!(dev) {
+        Ok(PathBuf::from("data"))
+    } else {
+        app.path_resolver()
+            .app_data_dir()
+            .ok_or(error::Error::AppDataDirNotFound)
+    }
+}
+
+#[cfg(target_os = "macos")]
+pub fn show_window(app: &AppHandle) {
+    let _ = app.show();
+    if let Some(window) = app.get_window("main") {
+        let _ = window.set_focus();
+    }
+}
+
+#[cfg(not(target_os = "macos"))]
+pub fn show_window(app: &AppHandle) {
+    if let Some(window) = app.get_window("main") {
+        let _ = window.show();
+        let _ = window.set_focus();
+    }
 }
 
 #[cfg(target_os = "macos")]
@@ -80,5 +226,5 @@ fn hide_window(event: &tauri::GlobalWindowEvent) {
 
 #[cfg(not(target_os = "macos"))]
 fn hide_window(event: &tauri::GlobalWindowEvent) {
-    event.window().hide().unwrap();
+    let _ = event.window().hide();
 }
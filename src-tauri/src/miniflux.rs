@@ -0,0 +1,338 @@
+// https://miniflux.app/docs/api.html
+
+use std::collections::HashMap;
+
+use chrono::{DateTime, FixedOffset, TimeZone, Utc};
+use reqwest::blocking::RequestBuilder;
+use reqwest::Method;
+use rusqlite::Connection;
+use serde::de::DeserializeOwned;
+use serde::Deserialize;
+use serde_json::{json, Value};
+
+use crate::error::{Error, Result};
+use crate::http::HttpClient;
+use crate::models::feeds::{self, FeedToCreate};
+use crate::models::items::{self, ItemStatus, ItemToCreate, ItemToSync};
+use crate::models::settings::{self, SettingKey, SettingToUpdate};
+use crate::publisher;
+use crate::sanitizer::sanitize;
+
+const PAGE_SIZE: usize = 100;
+
+#[derive(Deserialize)]
+struct RemoteFeed {
+    id: i64,
+    title: String,
+    feed_url: String,
+}
+
+#[derive(Deserialize)]
+struct RemoteEntries {
+    total: usize,
+    entries: Vec<RemoteEntry>,
+}
+
+#[derive(Deserialize, Clone)]
+struct RemoteEntry {
+    id: i64,
+    feed_id: i64,
+    status: String,
+    title: String,
+    url: String,
+    content: String,
+    author: String,
+    starred: bool,
+    published_at: DateTime<FixedOffset>,
+    changed_at: DateTime<FixedOffset>,
+}
+
+impl RemoteEntry {
+    fn status(&self) -> Option<ItemStatus> {
+        match self.status.as_str() {
+            "read" => Some(ItemStatus::Read),
+            "unread" => Some(ItemStatus::Unread),
+            _ => None,
+        }
+    }
+}
+
+pub struct Miniflux {
+    url: String,
+    api_key: String,
+    client: HttpClient,
+}
+
+impl Miniflux {
+    pub fn new(url: &str, api_key: &str, client: &HttpClient) -> Self {
+        Self {
+            url: url.trim_end_matches('/').to_string(),
+            api_key: api_key.to_string(),
+            client: client.clone(),
+        }
+    }
+
+    pub fn from_settings(db: &Connection, client: &HttpClient) -> Option<Self> {
+        let url = settings::value(db, &SettingKey::MinifluxUrl);
+        let api_key = settings::value(db, &SettingKey::MinifluxApiKey);
+
+        if url.is_empty() || api_key.is_empty() {
+            None
+        } else {
+            Some(Self::new(&url, &api_key, client))
+        }
+    }
+
+    fn get<T: DeserializeOwned>(&self, path: &str) -> Result<T> {
+        let body = self.send(
+            self.client
+                .prepare(Method::GET, &format!("{}{}", self.url, path)),
+        )?;
+        Ok(serde_json::from_str(&body)?)
+    }
+
+    fn put(&self, path: &str, body: Option<Value>) -> Result<()> {
+        let mut req = self
+            .client
+            .prepare(Method::PUT, &format!("{}{}", self.url, path));
+        if let Some(body) = body {
+            req = req
+                .header("Content-Type", "application/json")
+                .body(body.to_string());
+        }
+
+        self.send(req).map(|_| ())
+    }
+
+    fn send(&self, req: RequestBuilder) -> Result<String> {
+        let res = self
+            .client
+            .execute(req.header("X-Auth-Token", &self.api_key))?;
+        if !res.status().is_success() {
+            return Err(Error::MinifluxSyncFailure(format!(
+                "{} {}",
+                res.status(),
+                res.url()
+            )));
+        }
+
+        Ok(res.text()?)
+    }
+}
+
+/// Pulls feeds and entries changed since the last sync, then pushes local read/saved changes back.
+/// Conflicts are resolved by comparing the local `updated_at` with the remote `changed_at`.
+/// Returns the number of items changed locally.
+pub fn sync(db: &Connection, miniflux: &Miniflux) -> Result<usize> {
+    let started_at = Utc::now();
+    let synced_at = Utc
+        .timestamp_opt(settings::integer(db, &SettingKey::MinifluxSyncedAt), 0)
+        .single()
+        .unwrap_or_default()
+        .fixed_offset();
+
+    let feed_ids = pull_feeds(db, miniflux)?;
+
+    let mut changed = 0;
+    let mut entries = HashMap::new();
+    for entry in pull_entries(miniflux, synced_at)? {
+        if merge(db, &feed_ids, &entry)? {
+            changed += 1;
+        }
+        entries.insert(entry.id, entry);
+    }
+
+    changed += push(db, miniflux, &feed_ids, &entries, synced_at)?;
+
+    // Merged entries may have been saved or unsaved.
+    if changed > 0 {
+        publisher::refresh(db);
+    }
+
+    settings::update(
+        db,
+        &SettingToUpdate {
+            key: SettingKey::MinifluxSyncedAt,
+            value: started_at.timestamp().to_string(),
+        },
+    )?;
+
+    Ok(changed)
+}
+
+fn pull_feeds(db: &Connection, miniflux: &Miniflux) -> Result<HashMap<i64, i32>> {
+    let locals = feeds::read_all(db)?;
+    let mut feed_ids = HashMap::new();
+
+    for remote in miniflux.get::<Vec<RemoteFeed>>("/v1/feeds")? {
+        let local = locals
+            .iter()
+            .find(|x| x.remote_id == Some(remote.id) || x.link == remote.feed_url);
+
+        let id = match local {
+            Some(feed) => feed.id,
+            None => {
+                feeds::create(
+                    db,
+                    &FeedToCreate {
+                        title: remote.title.clone(),
+                        link: remote.feed_url.clone(),
+                        fetch_old_items: true,
+                        proxy: None,
+                    },
+                )?;
+                db.last_insert_rowid() as i32
+            }
+        };
+
+        if local.and_then(|x| x.remote_id) != Some(remote.id) {
+            feeds::update_remote_id(db, id, remote.id)?;
+        }
+
+        feed_ids.insert(remote.id, id);
+    }
+
+    Ok(feed_ids)
+}
+
+fn pull_entries(miniflux: &Miniflux, synced_at: DateTime<FixedOffset>) -> Result<Vec<RemoteEntry>> {
+    let mut entries = vec![];
+
+    loop {
+        let mut path = format!(
+            "/v1/entries?order=id&direction=asc&limit={}&offset={}",
+            PAGE_SIZE,
+            entries.len()
+        );
+        if synced_at.timestamp() > 0 {
+            path.push_str(&format!("&changed_after={}", synced_at.timestamp()));
+        }
+
+        let page = miniflux.get::<RemoteEntries>(&path)?;
+        let is_last = page.entries.is_empty();
+        entries.extend(page.entries);
+
+        if is_last || entries.len() >= page.total {
+            return Ok(entries);
+        }
+    }
+}
+
+fn merge(db: &Connection, feed_ids: &HashMap<i64, i32>, entry: &RemoteEntry) -> Result<bool> {
+    let (Some(status), Some(feed)) = (entry.status(), feed_ids.get(&entry.feed_id)) else {
+        return Ok(false);
+    };
+
+    let arg = ItemToCreate {
+        author: Some(entry.author.trim().to_string()).filter(|x| !x.is_empty()),
+        title: entry.title.trim().to_string(),
+        description: sanitize(entry.content.trim(), Some(&entry.url)),
+        link: entry.url.trim().to_string(),
+        status: ItemStatus::Unread,
+        published_at: entry.published_at,
+        feed: *feed,
+        thumbnail: None,
+        raw_description: Some(entry.content.trim().to_string()),
+    };
+
+    let local = match items::read_by_remote_id(db, entry.id)? {
+        Some(item) => Some(item),
+        None => items::read_by_fingerprint(db, &arg.fingerprint())?,
+    };
+
+    let Some(item) = local else {
+        items::create(db, &arg)?;
+        items::update_synced(
+            db,
+            &ItemToSync {
+                id: db.last_insert_rowid() as i32,
+                remote_id: entry.id,
+                status: Some(status),
+                is_saved: Some(entry.starred),
+                updated_at: entry.changed_at,
+            },
+        )?;
+        return Ok(true);
+    };
+
+    // Latest change wins: keep the local state so that it is pushed back.
+    if let Some(updated_at) = item.updated_at.filter(|x| *x > entry.changed_at) {
+        items::update_synced(
+            db,
+            &ItemToSync {
+                id: item.id,
+                remote_id: entry.id,
+                status: None,
+                is_saved: None,
+                updated_at,
+            },
+        )?;
+        return Ok(false);
+    }
+
+    let changed = item.status != status || item.is_saved != entry.starred;
+    items::update_synced(
+        db,
+        &ItemToSync {
+            id: item.id,
+            remote_id: entry.id,
+            status: Some(status),
+            is_saved: Some(entry.starred),
+            updated_at: entry.changed_at,
+        },
+    )?;
+
+    Ok(changed)
+}
+
+fn push(
+    db: &Connection,
+    miniflux: &Miniflux,
+    feed_ids: &HashMap<i64, i32>,
+    entries: &HashMap<i64, RemoteEntry>,
+    synced_at: DateTime<FixedOffset>,
+) -> Result<usize> {
+    let mut changed = 0;
+    let mut read = vec![];
+    let mut unread = vec![];
+
+    for item in items::read_all_updated_since(db, synced_at)? {
+        let (Some(remote_id), Some(updated_at)) = (item.remote_id, item.updated_at) else {
+            continue;
+        };
+
+        let entry = match entries.get(&remote_id) {
+            Some(entry) => entry.clone(),
+            None => miniflux.get::<RemoteEntry>(&format!("/v1/entries/{}", remote_id))?,
+        };
+
+        if updated_at <= entry.changed_at {
+            if !entries.contains_key(&remote_id) && merge(db, feed_ids, &entry)? {
+                changed += 1;
+            }
+            continue;
+        }
+
+        if Some(&item.status) != entry.status().as_ref() {
+            match item.status {
+                ItemStatus::Read => read.push(remote_id),
+                ItemStatus::Unread => unread.push(remote_id),
+            }
+        }
+
+        if item.is_saved != entry.starred {
+            miniflux.put(&format!("/v1/entries/{}/bookmark", remote_id), None)?;
+        }
+    }
+
+    for (ids, status) in [(read, ItemStatus::Read), (unread, ItemStatus::Unread)] {
+        if !ids.is_empty() {
+            miniflux.put(
+                "/v1/entries",
+                Some(json!({ "entry_ids": ids, "status": status.to_string() })),
+            )?;
+        }
+    }
+
+    Ok(changed)
+}
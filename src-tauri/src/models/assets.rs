@@ -0,0 +1,126 @@
+use chrono::{DateTime, FixedOffset, Utc};
+use rusqlite::{Connection, Row};
+use sea_query::{Expr, Query, SqliteQueryBuilder};
+use sea_query_rusqlite::RusqliteBinder;
+
+use crate::error::Result;
+
+use super::database::{Assets, Items};
+
+#[derive(Debug)]
+pub struct Asset {
+    pub id: i32,
+    pub item: i32,
+    pub url: String,
+    pub path: String,
+    pub created_at: DateTime<FixedOffset>,
+}
+
+impl TryFrom<&Row<'_>> for Asset {
+    type Error = rusqlite::Error;
+
+    fn try_from(row: &Row) -> rusqlite::Result<Self> {
+        Ok(Self {
+            id: row.get("id")?,
+            item: row.get("item")?,
+            url: row.get("url")?,
+            path: row.get("path")?,
+            created_at: row.get("created_at")?,
+        })
+    }
+}
+
+pub struct AssetToCreate {
+    pub item: i32,
+    pub url: String,
+    pub path: String,
+}
+
+pub fn create(db: &Connection, arg: &AssetToCreate) -> Result<usize> {
+    let (sql, values) = Query::insert()
+        .into_table(Assets::Table)
+        .columns([Assets::Item, Assets::Url, Assets::Path, Assets::CreatedAt])
+        .values_panic([
+            arg.item.into(),
+            arg.url.clone().into(),
+            arg.path.clone().into(),
+            Utc::now().into(),
+        ])
+        .build_rusqlite(SqliteQueryBuilder);
+
+    Ok(db.execute(sql.as_str(), &*values.as_params())?)
+}
+
+pub fn read_all(db: &Connection) -> Result<Vec<Asset>> {
+    let (sql, values) = Query::select()
+        .columns([
+            Assets::Id,
+            Assets::Item,
+            Assets::Url,
+            Assets::Path,
+            Assets::CreatedAt,
+        ])
+        .from(Assets::Table)
+        .build_rusqlite(SqliteQueryBuilder);
+
+    let mut stmt = db.prepare(sql.as_str())?;
+    let rows = stmt.query_map(&*values.as_params(), |x| Asset::try_from(x))?;
+
+    Ok(rows.collect::<rusqlite::Result<Vec<Asset>>>()?)
+}
+
+pub fn read_by_path(db: &Connection, path: &str) -> Result<Option<Asset>> {
+    let (sql, values) = Query::select()
+        .columns([
+            Assets::Id,
+            Assets::Item,
+            Assets::Url,
+            Assets::Path,
+            Assets::CreatedAt,
+        ])
+        .from(Assets::Table)
+        .and_where(Expr::col(Assets::Path).eq(path))
+        .limit(1)
+        .build_rusqlite(SqliteQueryBuilder);
+
+    let mut stmt = db.prepare(sql.as_str())?;
+    let mut rows = stmt.query(&*values.as_params())?;
+
+    Ok(rows.next()?.map(Asset::try_from).transpose()?)
+}
+
+pub fn read_by_items(db: &Connection, items: &[i32]) -> Result<Vec<Asset>> {
+    let (sql, values) = Query::select()
+        .columns([
+            Assets::Id,
+            Assets::Item,
+            Assets::Url,
+            Assets::Path,
+            Assets::CreatedAt,
+        ])
+        .from(Assets::Table)
+        .and_where(Expr::col(Assets::Item).is_in(items.iter().copied()))
+        .build_rusqlite(SqliteQueryBuilder);
+
+    let mut stmt = db.prepare(sql.as_str())?;
+    let rows = stmt.query_map(&*values.as_params(), |x| Asset::try_from(x))?;
+
+    Ok(rows.collect::<rusqlite::Result<Vec<Asset>>>()?)
+}
+
+/// Deletes the assets of items that no longer exist.
+pub fn delete_orphans(db: &Connection) -> Result<usize> {
+    let (sql, values) = Query::delete()
+        .from_table(Assets::Table)
+        .and_where(
+            Expr::col(Assets::Item).not_in_subquery(
+                Query::select()
+                    .column(Items::Id)
+                    .from(Items::Table)
+                    .to_owned(),
+            ),
+        )
+        .build_rusqlite(SqliteQueryBuilder);
+
+    Ok(db.execute(sql.as_str(), &*values.as_params())?)
+}
@@ -1,12 +1,16 @@
 use std::path::Path;
+use std::str::FromStr;
 
+use rusqlite::types::{FromSqlError, FromSqlResult, ValueRef};
 use rusqlite::Connection;
 use sea_query::{
     ColumnDef, Expr, ForeignKey, ForeignKeyAction, Iden, Index, Query, SqliteQueryBuilder, Table,
 };
 use sea_query_rusqlite::RusqliteBinder;
+use serde::{Deserialize, Deserializer};
 
-use crate::error::Result;
+use crate::error::{Error, Result};
+use crate::models::settings::SettingKey;
 
 #[derive(Iden)]
 pub enum Feeds {
@@ -17,6 +21,31 @@ pub enum Feeds {
     Status,
     CheckedAt,
     FetchOldItems,
+    RemoteId,
+    FetchFullContent,
+    SiteLink,
+    Description,
+    Language,
+    Image,
+    Icon,
+    SelfLink,
+    UserAgent,
+    Credentials,
+    Headers,
+    Proxy,
+    Notification,
+    NotificationFilter,
+}
+
+#[derive(Iden)]
+pub enum FeedHistories {
+    Table,
+    Id,
+    Feed,
+    OldLink,
+    NewLink,
+    Reason,
+    CreatedAt,
 }
 
 #[derive(Iden)]
@@ -32,6 +61,44 @@ pub enum Items {
     IsSaved,
     PublishedAt,
     Feed,
+    RemoteId,
+    UpdatedAt,
+    FullContent,
+    RawDescription,
+    Thumbnail,
+}
+
+#[derive(Iden)]
+pub enum Assets {
+    Table,
+    Id,
+    Item,
+    Url,
+    Path,
+    CreatedAt,
+}
+
+#[derive(Iden)]
+pub enum Webhooks {
+    Table,
+    Id,
+    Url,
+    Feed,
+    Filter,
+    Format,
+    Template,
+}
+
+#[derive(Iden)]
+pub enum WebhookDeliveries {
+    Table,
+    Id,
+    Webhook,
+    Link,
+    StatusCode,
+    Error,
+    Attempts,
+    DeliveredAt,
 }
 
 #[derive(Iden)]
@@ -42,7 +109,11 @@ pub enum Settings {
 }
 
 pub fn open_connection(path: &Path) -> Result<Connection> {
-    Ok(Connection::open(path.join("collie.db"))?)
+    let db = Connection::open(path.join("collie.db"))?;
+    // SQLite ignores foreign keys, and so their `ON DELETE CASCADE`, unless told otherwise on
+    // each connection.
+    db.pragma_update(None, "foreign_keys", true)?;
+    Ok(db)
 }
 
 pub fn migrate(db: &Connection) -> Result<()> {
@@ -139,38 +210,299 @@ pub fn migrate(db: &Connection) -> Result<()> {
         .col(ColumnDef::new(Settings::Value).text().not_null())
         .build(SqliteQueryBuilder);
 
+    let create_table_webhooks = Table::create()
+        .table(Webhooks::Table)
+        .if_not_exists()
+        .col(
+            ColumnDef::new(Webhooks::Id)
+                .integer()
+                .not_null()
+                .auto_increment()
+                .primary_key(),
+        )
+        .col(ColumnDef::new(Webhooks::Url).text().not_null())
+        .col(ColumnDef::new(Webhooks::Feed).integer())
+        .col(ColumnDef::new(Webhooks::Filter).text())
+        .col(
+            ColumnDef::new(Webhooks::Format)
+                .text()
+                .check(Expr::col(Webhooks::Format).is_in(["json", "form"]))
+                .not_null()
+                .default("json"),
+        )
+        .col(ColumnDef::new(Webhooks::Template).text())
+        .foreign_key(
+            ForeignKey::create()
+                .name("fk_webhooks_feeds")
+                .from(Webhooks::Table, Webhooks::Feed)
+                .to(Feeds::Table, Feeds::Id)
+                .on_delete(ForeignKeyAction::Cascade)
+                .on_update(ForeignKeyAction::Cascade),
+        )
+        .build(SqliteQueryBuilder);
+
+    let create_table_webhook_deliveries = Table::create()
+        .table(WebhookDeliveries::Table)
+        .if_not_exists()
+        .col(
+            ColumnDef::new(WebhookDeliveries::Id)
+                .integer()
+                .not_null()
+                .auto_increment()
+                .primary_key(),
+        )
+        .col(
+            ColumnDef::new(WebhookDeliveries::Webhook)
+                .integer()
+                .not_null(),
+        )
+        .col(ColumnDef::new(WebhookDeliveries::Link).text().not_null())
+        .col(ColumnDef::new(WebhookDeliveries::StatusCode).integer())
+        .col(ColumnDef::new(WebhookDeliveries::Error).text())
+        .col(
+            ColumnDef::new(WebhookDeliveries::Attempts)
+                .integer()
+                .not_null(),
+        )
+        .col(
+            ColumnDef::new(WebhookDeliveries::DeliveredAt)
+                .date_time()
+                .not_null(),
+        )
+        .foreign_key(
+            ForeignKey::create()
+                .name("fk_webhook_deliveries_webhooks")
+                .from(WebhookDeliveries::Table, WebhookDeliveries::Webhook)
+                .to(Webhooks::Table, Webhooks::Id)
+                .on_delete(ForeignKeyAction::Cascade)
+                .on_update(ForeignKeyAction::Cascade),
+        )
+        .build(SqliteQueryBuilder);
+
+    let create_table_feed_histories = Table::create()
+        .table(FeedHistories::Table)
+        .if_not_exists()
+        .col(
+            ColumnDef::new(FeedHistories::Id)
+                .integer()
+                .not_null()
+                .auto_increment()
+                .primary_key(),
+        )
+        .col(ColumnDef::new(FeedHistories::Feed).integer().not_null())
+        .col(ColumnDef::new(FeedHistories::OldLink).text().not_null())
+        .col(ColumnDef::new(FeedHistories::NewLink).text().not_null())
+        .col(
+            ColumnDef::new(FeedHistories::Reason)
+                .text()
+                .check(Expr::col(FeedHistories::Reason).is_in([
+                    "permanent_redirect",
+                    "new_feed_url",
+                    "self_link",
+                ]))
+                .not_null(),
+        )
+        .col(
+            ColumnDef::new(FeedHistories::CreatedAt)
+                .date_time()
+                .not_null(),
+        )
+        .foreign_key(
+            ForeignKey::create()
+                .name("fk_feed_histories_feeds")
+                .from(FeedHistories::Table, FeedHistories::Feed)
+                .to(Feeds::Table, Feeds::Id)
+                .on_delete(ForeignKeyAction::Cascade)
+                .on_update(ForeignKeyAction::Cascade),
+        )
+        .build(SqliteQueryBuilder);
+
+    let create_table_assets = Table::create()
+        .table(Assets::Table)
+        .if_not_exists()
+        .col(
+            ColumnDef::new(Assets::Id)
+                .integer()
+                .not_null()
+                .auto_increment()
+                .primary_key(),
+        )
+        .col(ColumnDef::new(Assets::Item).integer().not_null())
+        .col(ColumnDef::new(Assets::Url).text().not_null())
+        .col(ColumnDef::new(Assets::Path).text().not_null())
+        .col(ColumnDef::new(Assets::CreatedAt).date_time().not_null())
+        .index(
+            Index::create()
+                .unique()
+                .name("uk_assets_item_url")
+                .col(Assets::Item)
+                .col(Assets::Url),
+        )
+        .foreign_key(
+            ForeignKey::create()
+                .name("fk_assets_items")
+                .from(Assets::Table, Assets::Item)
+                .to(Items::Table, Items::Id)
+                .on_delete(ForeignKeyAction::Cascade)
+                .on_update(ForeignKeyAction::Cascade),
+        )
+        .build(SqliteQueryBuilder);
+
     db.execute_batch(
         &[
             create_table_feeds,
             create_table_items,
             create_table_settings,
+            create_table_webhooks,
+            create_table_webhook_deliveries,
+            create_table_assets,
+            create_table_feed_histories,
         ]
         .join(";"),
     )?;
 
-    let _ = insert_settings(db, "db_scheme_version", "1");
-    let _ = insert_settings(db, "polling_frequency", "300");
-    let _ = insert_settings(db, "notification", "1");
-    let _ = insert_settings(db, "theme", "system");
-    let _ = insert_settings(db, "items_order", "ReceivedDateDesc");
-    let _ = insert_settings(db, "proxy", "");
-    let _ = insert_settings(db, "fetch_old_items", "1");
+    for key in SettingKey::ALL {
+        let _ = insert_settings(db, &key.to_string(), key.schema().default);
+    }
 
-    let add_feeds_columns = Table::alter()
-        .table(Feeds::Table)
-        .add_column_if_not_exists(
-            ColumnDef::new(Feeds::FetchOldItems)
-                .boolean()
-                .not_null()
-                .default(true),
-        )
-        .build(SqliteQueryBuilder);
+    add_column_if_not_exists(
+        db,
+        Feeds::Table,
+        ColumnDef::new(Feeds::FetchOldItems)
+            .boolean()
+            .not_null()
+            .default(true),
+    )?;
+    add_column_if_not_exists(db, Feeds::Table, ColumnDef::new(Feeds::RemoteId).integer())?;
+    add_column_if_not_exists(db, Items::Table, ColumnDef::new(Items::RemoteId).integer())?;
+    add_column_if_not_exists(
+        db,
+        Items::Table,
+        ColumnDef::new(Items::UpdatedAt).date_time(),
+    )?;
+    add_column_if_not_exists(
+        db,
+        Feeds::Table,
+        ColumnDef::new(Feeds::FetchFullContent)
+            .boolean()
+            .not_null()
+            .default(false),
+    )?;
+    add_column_if_not_exists(db, Items::Table, ColumnDef::new(Items::FullContent).text())?;
+    add_column_if_not_exists(
+        db,
+        Items::Table,
+        ColumnDef::new(Items::RawDescription).text(),
+    )?;
+    add_column_if_not_exists(db, Items::Table, ColumnDef::new(Items::Thumbnail).text())?;
+    add_column_if_not_exists(db, Feeds::Table, ColumnDef::new(Feeds::SiteLink).text())?;
+    add_column_if_not_exists(db, Feeds::Table, ColumnDef::new(Feeds::Description).text())?;
+    add_column_if_not_exists(db, Feeds::Table, ColumnDef::new(Feeds::Language).text())?;
+    add_column_if_not_exists(db, Feeds::Table, ColumnDef::new(Feeds::Image).text())?;
+    add_column_if_not_exists(db, Feeds::Table, ColumnDef::new(Feeds::Icon).text())?;
+    add_column_if_not_exists(db, Feeds::Table, ColumnDef::new(Feeds::SelfLink).text())?;
+    add_column_if_not_exists(db, Feeds::Table, ColumnDef::new(Feeds::UserAgent).text())?;
+    add_column_if_not_exists(db, Feeds::Table, ColumnDef::new(Feeds::Credentials).text())?;
+    add_column_if_not_exists(db, Feeds::Table, ColumnDef::new(Feeds::Headers).text())?;
+    add_column_if_not_exists(db, Feeds::Table, ColumnDef::new(Feeds::Proxy).text())?;
+    add_column_if_not_exists(
+        db,
+        Feeds::Table,
+        ColumnDef::new(Feeds::Notification)
+            .boolean()
+            .not_null()
+            .default(true),
+    )?;
+    add_column_if_not_exists(
+        db,
+        Feeds::Table,
+        ColumnDef::new(Feeds::NotificationFilter).text(),
+    )?;
 
-    db.execute(&add_feeds_columns, [])?;
+    // Left behind by deletes made before foreign keys were enforced.
+    delete_orphans(db, Items::Table, Items::Feed, Feeds::Table, Feeds::Id)?;
+    delete_orphans(db, Assets::Table, Assets::Item, Items::Table, Items::Id)?;
+    delete_orphans(db, Webhooks::Table, Webhooks::Feed, Feeds::Table, Feeds::Id)?;
+    delete_orphans(
+        db,
+        WebhookDeliveries::Table,
+        WebhookDeliveries::Webhook,
+        Webhooks::Table,
+        Webhooks::Id,
+    )?;
+    delete_orphans(
+        db,
+        FeedHistories::Table,
+        FeedHistories::Feed,
+        Feeds::Table,
+        Feeds::Id,
+    )?;
+
+    Ok(())
+}
+
+// SQLite does not support `ADD COLUMN IF NOT EXISTS`, so check the table info first.
+fn add_column_if_not_exists<T: Iden + 'static>(
+    db: &Connection,
+    table: T,
+    column: &mut ColumnDef,
+) -> Result<()> {
+    let exists: bool = db.query_row(
+        "SELECT COUNT(*) > 0 FROM pragma_table_info(?1) WHERE name = ?2",
+        [table.to_string(), column.get_column_name()],
+        |row| row.get(0),
+    )?;
+
+    if !exists {
+        let sql = Table::alter()
+            .table(table)
+            .add_column(column)
+            .build(SqliteQueryBuilder);
+        db.execute(&sql, [])?;
+    }
 
     Ok(())
 }
 
+/// Deletes the rows of `table` whose `column` refers to no row of `parent`. Rows referring to
+/// nothing (`NULL`) are kept.
+fn delete_orphans<T: Iden + 'static, P: Iden + 'static>(
+    db: &Connection,
+    table: T,
+    column: T,
+    parent: P,
+    parent_column: P,
+) -> Result<usize> {
+    let (sql, values) = Query::delete()
+        .from_table(table)
+        .and_where(
+            Expr::col(column).not_in_subquery(
+                Query::select()
+                    .column(parent_column)
+                    .from(parent)
+                    .to_owned(),
+            ),
+        )
+        .build_rusqlite(SqliteQueryBuilder);
+
+    Ok(db.execute(sql.as_str(), &*values.as_params())?)
+}
+
+/// Reads enums stored as the text of their `Display` implementation.
+pub fn parse_enum<T: FromStr<Err = Error>>(value: ValueRef) -> FromSqlResult<T> {
+    value
+        .as_str()?
+        .parse()
+        .map_err(|err: Error| FromSqlError::Other(Box::new(err)))
+}
+
+/// Tells a missing field (unchanged) apart from `null` (cleared) in the arguments of updates.
+pub fn deserialize_some<'de, T: Deserialize<'de>, D: Deserializer<'de>>(
+    deserializer: D,
+) -> std::result::Result<Option<T>, D::Error> {
+    T::deserialize(deserializer).map(Some)
+}
+
 fn insert_settings(db: &Connection, key: &str, value: &str) -> Result<usize> {
     let (insert_settings_sql, insert_settings_values) = Query::insert()
         .into_table(Settings::Table)
@@ -1,18 +1,22 @@
 use core::fmt;
 use std::{
+    collections::BTreeMap,
     fmt::{Display, Formatter},
     str::FromStr,
 };
 
 use chrono::{DateTime, FixedOffset, Utc};
+use regex::Regex;
+use reqwest::header::{HeaderName, HeaderValue};
+use rusqlite::types::{FromSql, FromSqlResult, ValueRef};
 use rusqlite::{Connection, Row};
-use sea_query::{Expr, Query, SqliteQueryBuilder};
+use sea_query::{Expr, Order, Query, SqliteQueryBuilder};
 use sea_query_rusqlite::RusqliteBinder;
-use serde::{Deserialize, Serialize};
+use serde::{Deserialize, Serialize, Serializer};
 
 use crate::error::{Error, Result};
 
-use super::database::Feeds;
+use super::database::{deserialize_some, parse_enum, FeedHistories, Feeds};
 
 #[derive(Serialize, Deserialize, Debug, PartialEq)]
 pub enum FeedStatus {
@@ -44,6 +48,124 @@ impl FromStr for FeedStatus {
     }
 }
 
+impl FromSql for FeedStatus {
+    fn column_result(value: ValueRef) -> FromSqlResult<Self> {
+        parse_enum(value)
+    }
+}
+
+#[derive(Serialize, Deserialize, Debug, PartialEq)]
+pub enum FeedMoveReason {
+    PermanentRedirect,
+    NewFeedUrl,
+    SelfLink,
+}
+
+impl Display for FeedMoveReason {
+    fn fmt(&self, f: &mut Formatter) -> fmt::Result {
+        match self {
+            Self::PermanentRedirect => write!(f, "permanent_redirect"),
+            Self::NewFeedUrl => write!(f, "new_feed_url"),
+            Self::SelfLink => write!(f, "self_link"),
+        }
+    }
+}
+
+impl FromStr for FeedMoveReason {
+    type Err = Error;
+
+    fn from_str(x: &str) -> std::result::Result<Self, Self::Err> {
+        match x {
+            "permanent_redirect" => Ok(Self::PermanentRedirect),
+            "new_feed_url" => Ok(Self::NewFeedUrl),
+            "self_link" => Ok(Self::SelfLink),
+            _ => Err(Error::InvalidEnumKey(
+                x.to_string(),
+                "FeedMoveReason".to_string(),
+            )),
+        }
+    }
+}
+
+impl FromSql for FeedMoveReason {
+    fn column_result(value: ValueRef) -> FromSqlResult<Self> {
+        parse_enum(value)
+    }
+}
+
+#[derive(Serialize, Deserialize, Debug, PartialEq, Clone)]
+pub enum Credentials {
+    Basic { username: String, password: String },
+    Bearer { token: String },
+    Cookie { cookie: String },
+}
+
+impl Credentials {
+    /// Takes the secret from `stored` if it is empty, as redacted ones sent back are.
+    fn unredacted(self, stored: Option<&Credentials>) -> Self {
+        match (self, stored) {
+            (
+                Self::Basic { username, password },
+                Some(Self::Basic {
+                    password: stored, ..
+                }),
+            ) if password.is_empty() => Self::Basic {
+                username,
+                password: stored.clone(),
+            },
+            (Self::Bearer { token }, Some(Self::Bearer { token: stored })) if token.is_empty() => {
+                Self::Bearer {
+                    token: stored.clone(),
+                }
+            }
+            (Self::Cookie { cookie }, Some(Self::Cookie { cookie: stored }))
+                if cookie.is_empty() =>
+            {
+                Self::Cookie {
+                    cookie: stored.clone(),
+                }
+            }
+            (credentials, _) => credentials,
+        }
+    }
+}
+
+trait Redact {
+    fn redacted(&self) -> Self;
+}
+
+impl Redact for Option<Credentials> {
+    fn redacted(&self) -> Self {
+        self.as_ref().map(|x| match x {
+            Credentials::Basic { username, .. } => Credentials::Basic {
+                username: username.clone(),
+                password: String::new(),
+            },
+            Credentials::Bearer { .. } => Credentials::Bearer {
+                token: String::new(),
+            },
+            Credentials::Cookie { .. } => Credentials::Cookie {
+                cookie: String::new(),
+            },
+        })
+    }
+}
+
+// Header values often hold tokens or cookies, so only the names are shown.
+impl Redact for BTreeMap<String, String> {
+    fn redacted(&self) -> Self {
+        self.keys().map(|x| (x.clone(), String::new())).collect()
+    }
+}
+
+// Secrets are written to the database but never leave the backend.
+fn serialize_redacted<T: Redact + Serialize, S: Serializer>(
+    value: &T,
+    serializer: S,
+) -> std::result::Result<S::Ok, S::Error> {
+    value.redacted().serialize(serializer)
+}
+
 #[derive(Serialize, Debug)]
 pub struct Feed {
     pub id: i32,
@@ -52,26 +174,104 @@ pub struct Feed {
     pub status: FeedStatus,
     pub checked_at: DateTime<FixedOffset>,
     pub fetch_old_items: bool,
+    pub remote_id: Option<i64>,
+    pub fetch_full_content: bool,
+    pub site_link: Option<String>,
+    pub description: Option<String>,
+    pub language: Option<String>,
+    pub image: Option<String>,
+    pub icon: Option<String>,
+    pub self_link: Option<String>,
+    pub user_agent: Option<String>,
+    #[serde(serialize_with = "serialize_redacted")]
+    pub credentials: Option<Credentials>,
+    #[serde(serialize_with = "serialize_redacted")]
+    pub headers: BTreeMap<String, String>,
+    pub proxy: Option<String>,
+    pub notification: bool,
+    pub notification_filter: Option<String>,
 }
 
-impl From<&Row<'_>> for Feed {
-    fn from(row: &Row) -> Self {
-        Self {
-            id: row.get_unwrap("id"),
-            title: row.get_unwrap("title"),
-            link: row.get_unwrap("link"),
-            status: FeedStatus::from_str(&row.get_unwrap::<&str, String>("status")).unwrap(),
-            checked_at: row.get_unwrap("checked_at"),
-            fetch_old_items: row.get_unwrap("fetch_old_items"),
-        }
+impl TryFrom<&Row<'_>> for Feed {
+    type Error = rusqlite::Error;
+
+    fn try_from(row: &Row) -> rusqlite::Result<Self> {
+        Ok(Self {
+            id: row.get("id")?,
+            title: row.get("title")?,
+            link: row.get("link")?,
+            status: row.get("status")?,
+            checked_at: row.get("checked_at")?,
+            fetch_old_items: row.get("fetch_old_items")?,
+            remote_id: row.get("remote_id")?,
+            fetch_full_content: row.get("fetch_full_content")?,
+            site_link: row.get("site_link")?,
+            description: row.get("description")?,
+            language: row.get("language")?,
+            image: row.get("image")?,
+            icon: row.get("icon")?,
+            self_link: row.get("self_link")?,
+            user_agent: row.get("user_agent")?,
+            credentials: row
+                .get::<_, Option<String>>("credentials")?
+                .and_then(|x| serde_json::from_str(&x).ok()),
+            headers: row
+                .get::<_, Option<String>>("headers")?
+                .and_then(|x| serde_json::from_str(&x).ok())
+                .unwrap_or_default(),
+            proxy: row.get("proxy")?,
+            notification: row.get("notification")?,
+            notification_filter: row.get("notification_filter")?,
+        })
+    }
+}
+
+pub struct FeedMetadata {
+    pub site_link: Option<String>,
+    pub description: Option<String>,
+    pub language: Option<String>,
+    pub image: Option<String>,
+    pub self_link: Option<String>,
+}
+
+#[derive(Serialize, Debug)]
+pub struct FeedHistory {
+    pub id: i32,
+    pub feed: i32,
+    pub old_link: String,
+    pub new_link: String,
+    pub reason: FeedMoveReason,
+    pub created_at: DateTime<FixedOffset>,
+}
+
+impl TryFrom<&Row<'_>> for FeedHistory {
+    type Error = rusqlite::Error;
+
+    fn try_from(row: &Row) -> rusqlite::Result<Self> {
+        Ok(Self {
+            id: row.get("id")?,
+            feed: row.get("feed")?,
+            old_link: row.get("old_link")?,
+            new_link: row.get("new_link")?,
+            reason: row.get("reason")?,
+            created_at: row.get("created_at")?,
+        })
     }
 }
 
+pub struct FeedToMove {
+    pub id: i32,
+    pub old_link: String,
+    pub new_link: String,
+    pub reason: FeedMoveReason,
+}
+
 #[derive(Deserialize)]
 pub struct FeedToCreate {
     pub title: String,
     pub link: String,
     pub fetch_old_items: bool,
+    pub proxy: Option<String>,
 }
 
 #[derive(Deserialize)]
@@ -82,9 +282,24 @@ pub struct FeedToUpdate {
     pub status: Option<FeedStatus>,
     pub checked_at: Option<DateTime<FixedOffset>>,
     pub fetch_old_items: Option<bool>,
+    pub fetch_full_content: Option<bool>,
+    pub user_agent: Option<String>,
+    #[serde(default, deserialize_with = "deserialize_some")]
+    pub credentials: Option<Option<Credentials>>,
+    pub headers: Option<BTreeMap<String, String>>,
+    /// `null` goes through the global proxy, an empty string connects directly.
+    #[serde(default, deserialize_with = "deserialize_some")]
+    pub proxy: Option<Option<String>>,
+    pub notification: Option<bool>,
+    /// Only items with a title matching this regex are notified of. Empty clears it.
+    pub notification_filter: Option<String>,
 }
 
 pub fn create(db: &Connection, arg: &FeedToCreate) -> Result<usize> {
+    if let Some(proxy) = &arg.proxy {
+        validate_proxy(proxy)?;
+    }
+
     let (sql, values) = Query::insert()
         .into_table(Feeds::Table)
         .columns([
@@ -92,12 +307,14 @@ pub fn create(db: &Connection, arg: &FeedToCreate) -> Result<usize> {
             Feeds::Link,
             Feeds::CheckedAt,
             Feeds::FetchOldItems,
+            Feeds::Proxy,
         ])
         .values_panic([
             (*arg.title).into(),
             (*arg.link).into(),
             Utc::now().into(),
             arg.fetch_old_items.into(),
+            arg.proxy.clone().into(),
         ])
         .build_rusqlite(SqliteQueryBuilder);
 
@@ -113,14 +330,28 @@ pub fn read_all(db: &Connection) -> Result<Vec<Feed>> {
             Feeds::Status,
             Feeds::CheckedAt,
             Feeds::FetchOldItems,
+            Feeds::RemoteId,
+            Feeds::FetchFullContent,
+            Feeds::SiteLink,
+            Feeds::Description,
+            Feeds::Language,
+            Feeds::Image,
+            Feeds::Icon,
+            Feeds::SelfLink,
+            Feeds::UserAgent,
+            Feeds::Credentials,
+            Feeds::Headers,
+            Feeds::Proxy,
+            Feeds::Notification,
+            Feeds::NotificationFilter,
         ])
         .from(Feeds::Table)
         .build_rusqlite(SqliteQueryBuilder);
 
     let mut stmt = db.prepare(sql.as_str())?;
-    let rows = stmt.query_map(&*values.as_params(), |x| Ok(Feed::from(x)))?;
+    let rows = stmt.query_map(&*values.as_params(), |x| Feed::try_from(x))?;
 
-    Ok(rows.map(std::result::Result::unwrap).collect::<Vec<Feed>>())
+    Ok(rows.collect::<rusqlite::Result<Vec<Feed>>>()?)
 }
 
 pub fn read(db: &Connection, id: i32) -> Result<Option<Feed>> {
@@ -132,6 +363,20 @@ pub fn read(db: &Connection, id: i32) -> Result<Option<Feed>> {
             Feeds::Status,
             Feeds::CheckedAt,
             Feeds::FetchOldItems,
+            Feeds::RemoteId,
+            Feeds::FetchFullContent,
+            Feeds::SiteLink,
+            Feeds::Description,
+            Feeds::Language,
+            Feeds::Image,
+            Feeds::Icon,
+            Feeds::SelfLink,
+            Feeds::UserAgent,
+            Feeds::Credentials,
+            Feeds::Headers,
+            Feeds::Proxy,
+            Feeds::Notification,
+            Feeds::NotificationFilter,
         ])
         .from(Feeds::Table)
         .and_where(Expr::col(Feeds::Id).eq(id))
@@ -141,7 +386,7 @@ pub fn read(db: &Connection, id: i32) -> Result<Option<Feed>> {
     let mut stmt = db.prepare(sql.as_str())?;
     let mut rows = stmt.query(&*values.as_params())?;
 
-    Ok(rows.next()?.map(Feed::from))
+    Ok(rows.next()?.map(Feed::try_from).transpose()?)
 }
 
 pub fn update(db: &Connection, arg: &FeedToUpdate) -> Result<usize> {
@@ -167,12 +412,177 @@ pub fn update(db: &Connection, arg: &FeedToUpdate) -> Result<usize> {
         vals.push((Feeds::FetchOldItems, fetch_old_items.into()));
     }
 
+    if let Some(fetch_full_content) = arg.fetch_full_content {
+        vals.push((Feeds::FetchFullContent, fetch_full_content.into()));
+    }
+
+    // An empty user agent falls back to the global one.
+    if let Some(user_agent) = &arg.user_agent {
+        let user_agent = Some(user_agent.trim()).filter(|x| !x.is_empty());
+        vals.push((Feeds::UserAgent, user_agent.into()));
+    }
+
+    if let Some(credentials) = &arg.credentials {
+        let stored = read(db, arg.id)?.and_then(|x| x.credentials);
+        let credentials = credentials
+            .clone()
+            .map(|x| serde_json::to_string(&x.unredacted(stored.as_ref())))
+            .transpose()?;
+        vals.push((Feeds::Credentials, credentials.into()));
+    }
+
+    if let Some(headers) = &arg.headers {
+        // Empty values are the redacted ones sent back, and keep what is stored.
+        let stored = read(db, arg.id)?.map(|x| x.headers).unwrap_or_default();
+        let headers = headers
+            .iter()
+            .map(|(name, value)| match stored.get(name) {
+                Some(stored) if value.is_empty() => (name.clone(), stored.clone()),
+                _ => (name.clone(), value.clone()),
+            })
+            .collect::<BTreeMap<_, _>>();
+
+        for (name, value) in &headers {
+            if HeaderName::from_bytes(name.as_bytes()).is_err()
+                || HeaderValue::from_str(value).is_err()
+            {
+                return Err(Error::InvalidValue(name.clone()));
+            }
+        }
+
+        let headers = (!headers.is_empty())
+            .then(|| serde_json::to_string(&headers))
+            .transpose()?;
+        vals.push((Feeds::Headers, headers.into()));
+    }
+
+    if let Some(proxy) = &arg.proxy {
+        if let Some(proxy) = proxy {
+            validate_proxy(proxy)?;
+        }
+        vals.push((Feeds::Proxy, proxy.clone().into()));
+    }
+
+    if let Some(notification) = arg.notification {
+        vals.push((Feeds::Notification, notification.into()));
+    }
+
+    if let Some(filter) = &arg.notification_filter {
+        if Regex::new(filter).is_err() {
+            return Err(Error::InvalidValue(filter.clone()));
+        }
+        let filter = Some(filter.clone()).filter(|x| !x.is_empty());
+        vals.push((Feeds::NotificationFilter, filter.into()));
+    }
+
+    if vals.is_empty() {
+        return Err(Error::NothingToUpdate);
+    }
+
     let (sql, values) = Query::update()
         .table(Feeds::Table)
         .values(vals)
         .and_where(Expr::col(Feeds::Id).eq(arg.id))
         .build_rusqlite(SqliteQueryBuilder);
 
+    match db.execute(sql.as_str(), &*values.as_params())? {
+        0 => Err(Error::FeedNotFound(arg.id)),
+        count => Ok(count),
+    }
+}
+
+fn validate_proxy(proxy: &str) -> Result<()> {
+    if !proxy.is_empty() && reqwest::Proxy::all(proxy).is_err() {
+        return Err(Error::InvalidValue(proxy.to_string()));
+    }
+
+    Ok(())
+}
+
+pub fn update_metadata(db: &Connection, id: i32, arg: &FeedMetadata) -> Result<usize> {
+    let (sql, values) = Query::update()
+        .table(Feeds::Table)
+        .values([
+            (Feeds::SiteLink, arg.site_link.clone().into()),
+            (Feeds::Description, arg.description.clone().into()),
+            (Feeds::Language, arg.language.clone().into()),
+            (Feeds::Image, arg.image.clone().into()),
+            (Feeds::SelfLink, arg.self_link.clone().into()),
+        ])
+        .and_where(Expr::col(Feeds::Id).eq(id))
+        .build_rusqlite(SqliteQueryBuilder);
+
+    Ok(db.execute(sql.as_str(), &*values.as_params())?)
+}
+
+/// Changes the link of the feed and records the move in its history.
+pub fn relocate(db: &Connection, arg: &FeedToMove) -> Result<usize> {
+    let (sql, values) = Query::update()
+        .table(Feeds::Table)
+        .values([(Feeds::Link, arg.new_link.clone().into())])
+        .and_where(Expr::col(Feeds::Id).eq(arg.id))
+        .build_rusqlite(SqliteQueryBuilder);
+    db.execute(sql.as_str(), &*values.as_params())?;
+
+    let (sql, values) = Query::insert()
+        .into_table(FeedHistories::Table)
+        .columns([
+            FeedHistories::Feed,
+            FeedHistories::OldLink,
+            FeedHistories::NewLink,
+            FeedHistories::Reason,
+            FeedHistories::CreatedAt,
+        ])
+        .values_panic([
+            arg.id.into(),
+            arg.old_link.clone().into(),
+            arg.new_link.clone().into(),
+            arg.reason.to_string().into(),
+            Utc::now().into(),
+        ])
+        .build_rusqlite(SqliteQueryBuilder);
+
+    Ok(db.execute(sql.as_str(), &*values.as_params())?)
+}
+
+pub fn read_histories(db: &Connection, feed: i32) -> Result<Vec<FeedHistory>> {
+    let (sql, values) = Query::select()
+        .columns([
+            FeedHistories::Id,
+            FeedHistories::Feed,
+            FeedHistories::OldLink,
+            FeedHistories::NewLink,
+            FeedHistories::Reason,
+            FeedHistories::CreatedAt,
+        ])
+        .from(FeedHistories::Table)
+        .and_where(Expr::col(FeedHistories::Feed).eq(feed))
+        .order_by(FeedHistories::Id, Order::Desc)
+        .build_rusqlite(SqliteQueryBuilder);
+
+    let mut stmt = db.prepare(sql.as_str())?;
+    let rows = stmt.query_map(&*values.as_params(), |x| FeedHistory::try_from(x))?;
+
+    Ok(rows.collect::<rusqlite::Result<Vec<FeedHistory>>>()?)
+}
+
+pub fn update_icon(db: &Connection, id: i32, icon: &str) -> Result<usize> {
+    let (sql, values) = Query::update()
+        .table(Feeds::Table)
+        .values([(Feeds::Icon, icon.into())])
+        .and_where(Expr::col(Feeds::Id).eq(id))
+        .build_rusqlite(SqliteQueryBuilder);
+
+    Ok(db.execute(sql.as_str(), &*values.as_params())?)
+}
+
+pub fn update_remote_id(db: &Connection, id: i32, remote_id: i64) -> Result<usize> {
+    let (sql, values) = Query::update()
+        .table(Feeds::Table)
+        .values([(Feeds::RemoteId, remote_id.into())])
+        .and_where(Expr::col(Feeds::Id).eq(id))
+        .build_rusqlite(SqliteQueryBuilder);
+
     Ok(db.execute(sql.as_str(), &*values.as_params())?)
 }
 
@@ -182,5 +592,8 @@ pub fn delete(db: &Connection, id: i32) -> Result<usize> {
         .and_where(Expr::col(Feeds::Id).eq(id))
         .build_rusqlite(SqliteQueryBuilder);
 
-    Ok(db.execute(sql.as_str(), &*values.as_params())?)
+    match db.execute(sql.as_str(), &*values.as_params())? {
+        0 => Err(Error::FeedNotFound(id)),
+        count => Ok(count),
+    }
 }
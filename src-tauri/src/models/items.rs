@@ -1,18 +1,19 @@
 use core::fmt::{self, Display, Formatter};
 use std::str::FromStr;
 
-use chrono::{DateTime, FixedOffset};
+use chrono::{DateTime, FixedOffset, Utc};
+use rusqlite::types::{FromSql, FromSqlResult, ValueRef};
 use rusqlite::{Connection, Row};
-use sea_query::{Alias, Expr, Func, Order, Query, SqliteQueryBuilder, Values};
+use sea_query::{Alias, Expr, Func, Order, Query, SelectStatement, SqliteQueryBuilder, Values};
 use sea_query_rusqlite::RusqliteBinder;
 use serde::{Deserialize, Serialize};
 use sha1_smol::Sha1;
 
 use crate::error::{Error, Result};
 
-use super::database::{Feeds, Items};
+use super::database::{parse_enum, Feeds, Items};
 
-#[derive(Serialize, Deserialize, Debug)]
+#[derive(Serialize, Deserialize, Debug, PartialEq)]
 pub enum ItemStatus {
     Unread,
     Read,
@@ -42,6 +43,12 @@ impl FromStr for ItemStatus {
     }
 }
 
+impl FromSql for ItemStatus {
+    fn column_result(value: ValueRef) -> FromSqlResult<Self> {
+        parse_enum(value)
+    }
+}
+
 #[derive(Serialize, Debug)]
 pub struct ItemFeed {
     pub id: i32,
@@ -61,30 +68,40 @@ pub struct Item {
     pub is_saved: bool,
     pub published_at: DateTime<FixedOffset>,
     pub feed: ItemFeed,
+    pub remote_id: Option<i64>,
+    pub updated_at: Option<DateTime<FixedOffset>>,
+    pub full_content: Option<String>,
+    pub thumbnail: Option<String>,
 }
 
-impl From<&Row<'_>> for Item {
-    fn from(row: &Row) -> Self {
-        Self {
-            id: row.get_unwrap("id"),
-            fingerprint: row.get_unwrap("fingerprint"),
-            author: row.get_unwrap("author"),
-            title: row.get_unwrap("title"),
-            description: row.get_unwrap("description"),
-            link: row.get_unwrap("link"),
-            status: ItemStatus::from_str(&row.get_unwrap::<&str, String>("status")).unwrap(),
-            is_saved: row.get_unwrap("is_saved"),
-            published_at: row.get_unwrap("published_at"),
+impl TryFrom<&Row<'_>> for Item {
+    type Error = rusqlite::Error;
+
+    fn try_from(row: &Row) -> rusqlite::Result<Self> {
+        Ok(Self {
+            id: row.get("id")?,
+            fingerprint: row.get("fingerprint")?,
+            author: row.get("author")?,
+            title: row.get("title")?,
+            description: row.get("description")?,
+            link: row.get("link")?,
+            status: row.get("status")?,
+            is_saved: row.get("is_saved")?,
+            published_at: row.get("published_at")?,
             feed: ItemFeed {
-                id: row.get_unwrap("feed_id"),
-                title: row.get_unwrap("feed_title"),
-                link: row.get_unwrap("feed_link"),
+                id: row.get("feed_id")?,
+                title: row.get("feed_title")?,
+                link: row.get("feed_link")?,
             },
-        }
+            remote_id: row.get("remote_id")?,
+            updated_at: row.get("updated_at")?,
+            full_content: row.get("full_content")?,
+            thumbnail: row.get("thumbnail")?,
+        })
     }
 }
 
-#[derive(Deserialize, Debug)]
+#[derive(Serialize, Deserialize, Debug)]
 pub struct ItemToCreate {
     pub author: Option<String>,
     pub title: String,
@@ -93,6 +110,9 @@ pub struct ItemToCreate {
     pub status: ItemStatus,
     pub published_at: DateTime<FixedOffset>,
     pub feed: i32,
+    pub thumbnail: Option<String>,
+    #[serde(skip)]
+    pub raw_description: Option<String>,
 }
 
 impl ItemToCreate {
@@ -103,31 +123,44 @@ impl ItemToCreate {
 
 #[derive(Deserialize)]
 pub struct ItemToUpdate {
-    id: i32,
-    status: Option<ItemStatus>,
-    is_saved: Option<bool>,
+    pub id: i32,
+    pub status: Option<ItemStatus>,
+    pub is_saved: Option<bool>,
+}
+
+pub struct ItemToSync {
+    pub id: i32,
+    pub remote_id: i64,
+    pub status: Option<ItemStatus>,
+    pub is_saved: Option<bool>,
+    pub updated_at: DateTime<FixedOffset>,
 }
 
 #[derive(Deserialize)]
 pub struct ItemToUpdateAll {
-    status: Option<ItemStatus>,
-    is_saved: Option<bool>,
-    opt: Option<ItemReadOption>,
+    pub status: Option<ItemStatus>,
+    pub is_saved: Option<bool>,
+    pub opt: Option<ItemReadOption>,
 }
 
 #[derive(Deserialize)]
 pub enum ItemOrder {
+    ReceivedDateAsc,
     ReceivedDateDesc,
     PublishedDateDesc,
     UnreadFirst,
 }
 
-#[derive(Deserialize)]
+#[derive(Deserialize, Default)]
 pub struct ItemReadOption {
     pub ids: Option<Vec<i32>>,
+    pub since_id: Option<i32>,
+    pub max_id: Option<i32>,
     pub feed: Option<i32>,
     pub status: Option<ItemStatus>,
     pub is_saved: Option<bool>,
+    pub published_after: Option<DateTime<FixedOffset>>,
+    pub published_before: Option<DateTime<FixedOffset>>,
     pub order_by: Option<ItemOrder>,
     pub limit: Option<u64>,
     pub offset: Option<u64>,
@@ -145,6 +178,8 @@ pub fn create(db: &Connection, arg: &ItemToCreate) -> Result<usize> {
             Items::Status,
             Items::PublishedAt,
             Items::Feed,
+            Items::RawDescription,
+            Items::Thumbnail,
         ])
         .values_panic([
             arg.fingerprint().into(),
@@ -155,6 +190,8 @@ pub fn create(db: &Connection, arg: &ItemToCreate) -> Result<usize> {
             arg.status.to_string().into(),
             arg.published_at.into(),
             arg.feed.into(),
+            arg.raw_description.clone().into(),
+            arg.thumbnail.clone().into(),
         ])
         .build_rusqlite(SqliteQueryBuilder);
 
@@ -162,36 +199,18 @@ pub fn create(db: &Connection, arg: &ItemToCreate) -> Result<usize> {
 }
 
 pub fn read_all(db: &Connection, opt: &ItemReadOption) -> Result<Vec<Item>> {
-    let mut query = Query::select()
-        .columns([
-            (Items::Table, Items::Id),
-            (Items::Table, Items::Fingerprint),
-            (Items::Table, Items::Author),
-            (Items::Table, Items::Title),
-            (Items::Table, Items::Description),
-            (Items::Table, Items::Link),
-            (Items::Table, Items::Status),
-            (Items::Table, Items::IsSaved),
-            (Items::Table, Items::PublishedAt),
-        ])
-        .expr_as(Expr::col((Feeds::Table, Feeds::Id)), Alias::new("feed_id"))
-        .expr_as(
-            Expr::col((Feeds::Table, Feeds::Title)),
-            Alias::new("feed_title"),
-        )
-        .expr_as(
-            Expr::col((Feeds::Table, Feeds::Link)),
-            Alias::new("feed_link"),
-        )
-        .from(Items::Table)
-        .inner_join(
-            Feeds::Table,
-            Expr::col((Items::Table, Items::Feed)).equals((Feeds::Table, Feeds::Id)),
-        )
-        .clone();
+    let mut query = select();
 
     if let Some(ids) = &opt.ids {
-        query.and_where(Expr::col(Items::Id).is_in(ids.clone()));
+        query.and_where(Expr::col((Items::Table, Items::Id)).is_in(ids.clone()));
+    }
+
+    if let Some(since_id) = &opt.since_id {
+        query.and_where(Expr::col((Items::Table, Items::Id)).gt(*since_id));
+    }
+
+    if let Some(max_id) = &opt.max_id {
+        query.and_where(Expr::col((Items::Table, Items::Id)).lt(*max_id));
     }
 
     if let Some(feed) = &opt.feed {
@@ -206,8 +225,21 @@ pub fn read_all(db: &Connection, opt: &ItemReadOption) -> Result<Vec<Item>> {
         query.and_where(Expr::col(Items::IsSaved).eq(*is_saved));
     }
 
+    if let Some(published_after) = &opt.published_after {
+        query.and_where(Expr::col(Items::PublishedAt).gte(*published_after));
+    }
+
+    if let Some(published_before) = &opt.published_before {
+        query.and_where(Expr::col(Items::PublishedAt).lte(*published_before));
+    }
+
     if let Some(order_by) = &opt.order_by {
         match order_by {
+            ItemOrder::ReceivedDateAsc => {
+                query
+                    .order_by((Items::Table, Items::Id), Order::Asc)
+                    .order_by(Items::PublishedAt, Order::Asc);
+            }
             ItemOrder::ReceivedDateDesc => {
                 query
                     .order_by((Items::Table, Items::Id), Order::Desc)
@@ -241,9 +273,57 @@ pub fn read_all(db: &Connection, opt: &ItemReadOption) -> Result<Vec<Item>> {
 
     let (sql, values) = query.build_rusqlite(SqliteQueryBuilder);
     let mut stmt = db.prepare(sql.as_str())?;
-    let rows = stmt.query_map(&*values.as_params(), |x| Ok(Item::from(x)))?;
+    let rows = stmt.query_map(&*values.as_params(), |x| Item::try_from(x))?;
+
+    Ok(rows.collect::<rusqlite::Result<Vec<Item>>>()?)
+}
+
+pub fn read(db: &Connection, id: i32) -> Result<Option<Item>> {
+    let (sql, values) = select()
+        .and_where(Expr::col((Items::Table, Items::Id)).eq(id))
+        .limit(1)
+        .build_rusqlite(SqliteQueryBuilder);
+
+    let mut stmt = db.prepare(sql.as_str())?;
+    let mut rows = stmt.query(&*values.as_params())?;
+
+    Ok(rows.next()?.map(Item::try_from).transpose()?)
+}
+
+pub fn read_by_fingerprint(db: &Connection, fingerprint: &str) -> Result<Option<Item>> {
+    let (sql, values) = select()
+        .and_where(Expr::col((Items::Table, Items::Fingerprint)).eq(fingerprint))
+        .limit(1)
+        .build_rusqlite(SqliteQueryBuilder);
+
+    let mut stmt = db.prepare(sql.as_str())?;
+    let mut rows = stmt.query(&*values.as_params())?;
+
+    Ok(rows.next()?.map(Item::try_from).transpose()?)
+}
+
+pub fn read_by_remote_id(db: &Connection, remote_id: i64) -> Result<Option<Item>> {
+    let (sql, values) = select()
+        .and_where(Expr::col((Items::Table, Items::RemoteId)).eq(remote_id))
+        .limit(1)
+        .build_rusqlite(SqliteQueryBuilder);
+
+    let mut stmt = db.prepare(sql.as_str())?;
+    let mut rows = stmt.query(&*values.as_params())?;
+
+    Ok(rows.next()?.map(Item::try_from).transpose()?)
+}
+
+pub fn read_all_updated_since(db: &Connection, since: DateTime<FixedOffset>) -> Result<Vec<Item>> {
+    let (sql, values) = select()
+        .and_where(Expr::col((Items::Table, Items::RemoteId)).is_not_null())
+        .and_where(Expr::col((Items::Table, Items::UpdatedAt)).gt(since))
+        .build_rusqlite(SqliteQueryBuilder);
+
+    let mut stmt = db.prepare(sql.as_str())?;
+    let rows = stmt.query_map(&*values.as_params(), |x| Item::try_from(x))?;
 
-    Ok(rows.map(std::result::Result::unwrap).collect::<Vec<Item>>())
+    Ok(rows.collect::<rusqlite::Result<Vec<Item>>>()?)
 }
 
 pub fn count_all(db: &Connection, opt: &ItemReadOption) -> Result<i64> {
@@ -269,14 +349,14 @@ pub fn count_all(db: &Connection, opt: &ItemReadOption) -> Result<i64> {
     let mut rows = stmt.query(&*values.as_params())?;
 
     Ok(if let Some(row) = rows.next()? {
-        row.get_unwrap(0)
+        row.get(0)?
     } else {
         0
     })
 }
 
 pub fn update(db: &Connection, arg: &ItemToUpdate) -> Result<usize> {
-    let mut vals = vec![];
+    let mut vals = vec![(Items::UpdatedAt, Utc::now().into())];
 
     if let Some(status) = &arg.status {
         vals.push((Items::Status, status.to_string().into()));
@@ -292,11 +372,32 @@ pub fn update(db: &Connection, arg: &ItemToUpdate) -> Result<usize> {
         .and_where(Expr::col(Items::Id).eq(arg.id))
         .build_rusqlite(SqliteQueryBuilder);
 
-    Ok(db.execute(sql.as_str(), &*values.as_params())?)
+    match db.execute(sql.as_str(), &*values.as_params())? {
+        0 => Err(Error::ItemNotFound(arg.id)),
+        count => Ok(count),
+    }
+}
+
+/// Fails with the first of `ids` that is not an item, unlike `update_all` which skips it.
+pub fn check_exist(db: &Connection, ids: &[i32]) -> Result<()> {
+    let (sql, values) = Query::select()
+        .column(Items::Id)
+        .from(Items::Table)
+        .and_where(Expr::col(Items::Id).is_in(ids.iter().copied()))
+        .build_rusqlite(SqliteQueryBuilder);
+
+    let mut stmt = db.prepare(sql.as_str())?;
+    let rows = stmt.query_map(&*values.as_params(), |x| x.get::<_, i32>("id"))?;
+    let found = rows.collect::<rusqlite::Result<Vec<i32>>>()?;
+
+    match ids.iter().find(|id| !found.contains(id)) {
+        Some(id) => Err(Error::ItemNotFound(*id)),
+        None => Ok(()),
+    }
 }
 
 pub fn update_all(db: &Connection, arg: &ItemToUpdateAll) -> Result<usize> {
-    let mut vals = vec![];
+    let mut vals = vec![(Items::UpdatedAt, Utc::now().into())];
 
     if let Some(status) = &arg.status {
         vals.push((Items::Status, status.to_string().into()));
@@ -313,6 +414,14 @@ pub fn update_all(db: &Connection, arg: &ItemToUpdateAll) -> Result<usize> {
             query.and_where(Expr::col(Items::Id).is_in(ids.clone()));
         }
 
+        if let Some(since_id) = &opt.since_id {
+            query.and_where(Expr::col(Items::Id).gt(*since_id));
+        }
+
+        if let Some(max_id) = &opt.max_id {
+            query.and_where(Expr::col(Items::Id).lt(*max_id));
+        }
+
         if let Some(feed) = &opt.feed {
             query.and_where(Expr::col(Items::Feed).eq(*feed));
         }
@@ -324,8 +433,134 @@ pub fn update_all(db: &Connection, arg: &ItemToUpdateAll) -> Result<usize> {
         if let Some(is_saved) = &opt.is_saved {
             query.and_where(Expr::col(Items::IsSaved).eq(*is_saved));
         }
+
+        if let Some(published_after) = &opt.published_after {
+            query.and_where(Expr::col(Items::PublishedAt).gte(*published_after));
+        }
+
+        if let Some(published_before) = &opt.published_before {
+            query.and_where(Expr::col(Items::PublishedAt).lte(*published_before));
+        }
     }
 
     let (sql, values) = query.build_rusqlite(SqliteQueryBuilder);
     Ok(db.execute(sql.as_str(), &*values.as_params())?)
 }
+
+pub fn read_all_without_raw_description(db: &Connection) -> Result<Vec<(i32, String)>> {
+    let (sql, values) = Query::select()
+        .columns([Items::Id, Items::Description])
+        .from(Items::Table)
+        .and_where(Expr::col(Items::RawDescription).is_null())
+        .build_rusqlite(SqliteQueryBuilder);
+
+    let mut stmt = db.prepare(sql.as_str())?;
+    let rows = stmt.query_map(&*values.as_params(), |x| {
+        Ok((x.get("id")?, x.get("description")?))
+    })?;
+
+    Ok(rows.collect::<rusqlite::Result<_>>()?)
+}
+
+pub fn update_description(
+    db: &Connection,
+    id: i32,
+    description: &str,
+    raw_description: &str,
+) -> Result<usize> {
+    let (sql, values) = Query::update()
+        .table(Items::Table)
+        .values([
+            (Items::Description, description.into()),
+            (Items::RawDescription, raw_description.into()),
+        ])
+        .and_where(Expr::col(Items::Id).eq(id))
+        .build_rusqlite(SqliteQueryBuilder);
+
+    Ok(db.execute(sql.as_str(), &*values.as_params())?)
+}
+
+pub fn update_assets(
+    db: &Connection,
+    id: i32,
+    description: &str,
+    thumbnail: Option<&str>,
+) -> Result<usize> {
+    let (sql, values) = Query::update()
+        .table(Items::Table)
+        .values([
+            (Items::Description, description.into()),
+            (Items::Thumbnail, thumbnail.into()),
+        ])
+        .and_where(Expr::col(Items::Id).eq(id))
+        .build_rusqlite(SqliteQueryBuilder);
+
+    Ok(db.execute(sql.as_str(), &*values.as_params())?)
+}
+
+pub fn update_full_content(db: &Connection, id: i32, full_content: &str) -> Result<usize> {
+    let (sql, values) = Query::update()
+        .table(Items::Table)
+        .values([(Items::FullContent, full_content.into())])
+        .and_where(Expr::col(Items::Id).eq(id))
+        .build_rusqlite(SqliteQueryBuilder);
+
+    Ok(db.execute(sql.as_str(), &*values.as_params())?)
+}
+
+pub fn update_synced(db: &Connection, arg: &ItemToSync) -> Result<usize> {
+    let mut vals = vec![
+        (Items::RemoteId, arg.remote_id.into()),
+        (Items::UpdatedAt, arg.updated_at.into()),
+    ];
+
+    if let Some(status) = &arg.status {
+        vals.push((Items::Status, status.to_string().into()));
+    }
+
+    if let Some(is_saved) = &arg.is_saved {
+        vals.push((Items::IsSaved, (*is_saved).into()));
+    }
+
+    let (sql, values) = Query::update()
+        .table(Items::Table)
+        .values(vals)
+        .and_where(Expr::col(Items::Id).eq(arg.id))
+        .build_rusqlite(SqliteQueryBuilder);
+
+    Ok(db.execute(sql.as_str(), &*values.as_params())?)
+}
+
+fn select() -> SelectStatement {
+    Query::select()
+        .columns([
+            (Items::Table, Items::Id),
+            (Items::Table, Items::Fingerprint),
+            (Items::Table, Items::Author),
+            (Items::Table, Items::Title),
+            (Items::Table, Items::Description),
+            (Items::Table, Items::Link),
+            (Items::Table, Items::Status),
+            (Items::Table, Items::IsSaved),
+            (Items::Table, Items::PublishedAt),
+            (Items::Table, Items::RemoteId),
+            (Items::Table, Items::UpdatedAt),
+            (Items::Table, Items::FullContent),
+            (Items::Table, Items::Thumbnail),
+        ])
+        .expr_as(Expr::col((Feeds::Table, Feeds::Id)), Alias::new("feed_id"))
+        .expr_as(
+            Expr::col((Feeds::Table, Feeds::Title)),
+            Alias::new("feed_title"),
+        )
+        .expr_as(
+            Expr::col((Feeds::Table, Feeds::Link)),
+            Alias::new("feed_link"),
+        )
+        .from(Items::Table)
+        .inner_join(
+            Feeds::Table,
+            Expr::col((Items::Table, Items::Feed)).equals((Feeds::Table, Feeds::Id)),
+        )
+        .clone()
+}
@@ -1,18 +1,22 @@
 use core::fmt;
-use std::ops::Deref;
+use std::net::SocketAddr;
 use std::{
     fmt::{Display, Formatter},
     str::FromStr,
 };
 
+use chrono::NaiveTime;
+use rusqlite::types::{FromSql, FromSqlResult, ValueRef};
 use rusqlite::{Connection, Row};
 use sea_query::{Expr, Query, SqliteQueryBuilder};
 use sea_query_rusqlite::RusqliteBinder;
+use serde::ser::{SerializeStruct, Serializer};
 use serde::{Deserialize, Serialize};
+use url::Url;
 
 use crate::error::{Error, Result};
 
-use super::database::Settings;
+use super::database::{parse_enum, Settings};
 
 #[derive(Serialize, Deserialize, Debug, PartialEq, Clone)]
 pub enum SettingKey {
@@ -23,6 +27,160 @@ pub enum SettingKey {
     ItemsOrder,
     Proxy,
     FetchOldItems,
+    ApiServer,
+    ApiServerAddress,
+    ApiUsername,
+    ApiPassword,
+    MinifluxUrl,
+    MinifluxApiKey,
+    MinifluxSyncedAt,
+    SavedItemsFeedPath,
+    AssetCache,
+    AssetCacheSize,     // megabytes
+    HttpTimeout,        // seconds
+    HttpConnectTimeout, // seconds
+    HttpRetries,
+    UserAgent,
+    QuietHoursStart,      // HH:MM, local time
+    QuietHoursEnd,        // HH:MM, local time
+    NotificationInterval, // seconds
+}
+
+impl SettingKey {
+    pub const ALL: [SettingKey; 24] = [
+        Self::PollingFrequency,
+        Self::Notification,
+        Self::DbSchemeVersion,
+        Self::Theme,
+        Self::ItemsOrder,
+        Self::Proxy,
+        Self::FetchOldItems,
+        Self::ApiServer,
+        Self::ApiServerAddress,
+        Self::ApiUsername,
+        Self::ApiPassword,
+        Self::MinifluxUrl,
+        Self::MinifluxApiKey,
+        Self::MinifluxSyncedAt,
+        Self::SavedItemsFeedPath,
+        Self::AssetCache,
+        Self::AssetCacheSize,
+        Self::HttpTimeout,
+        Self::HttpConnectTimeout,
+        Self::HttpRetries,
+        Self::UserAgent,
+        Self::QuietHoursStart,
+        Self::QuietHoursEnd,
+        Self::NotificationInterval,
+    ];
+
+    pub fn schema(&self) -> SettingSchema {
+        use SettingType::*;
+
+        let (value_type, default, description) = match self {
+            Self::PollingFrequency => (
+                Integer { min: 30, max: None },
+                "300",
+                "Seconds between checks of all feeds",
+            ),
+            Self::Notification => (Boolean, "1", "Notify of new items"),
+            Self::DbSchemeVersion => (
+                Integer { min: 1, max: None },
+                "1",
+                "Version of the database scheme",
+            ),
+            Self::Theme => (
+                Choice {
+                    options: &["system", "light", "dark", "dracula"],
+                },
+                "system",
+                "Color theme",
+            ),
+            Self::ItemsOrder => (
+                Choice {
+                    options: &[
+                        "ReceivedDateAsc",
+                        "ReceivedDateDesc",
+                        "PublishedDateDesc",
+                        "UnreadFirst",
+                    ],
+                },
+                "ReceivedDateDesc",
+                "Order of the items list",
+            ),
+            Self::Proxy => (
+                Proxy,
+                "",
+                "Proxy for all requests. Empty uses the system proxy",
+            ),
+            Self::FetchOldItems => (Boolean, "1", "Fetch old items of new feeds"),
+            Self::ApiServer => (Boolean, "0", "Serve the Fever and Google Reader APIs"),
+            Self::ApiServerAddress => (
+                Address,
+                "127.0.0.1:8046",
+                "Address the API server listens on",
+            ),
+            Self::ApiUsername => (Text, "collie", "Username of API clients"),
+            Self::ApiPassword => (Text, "", "Password of API clients"),
+            Self::MinifluxUrl => (Url, "", "Miniflux server to sync with. Empty disables sync"),
+            Self::MinifluxApiKey => (Text, "", "API key of the Miniflux server"),
+            Self::MinifluxSyncedAt => (
+                Integer { min: 0, max: None },
+                "0",
+                "Unix time of the last sync with Miniflux",
+            ),
+            Self::SavedItemsFeedPath => (
+                Text,
+                "",
+                "File to publish saved items to. Empty disables publishing",
+            ),
+            Self::AssetCache => (Boolean, "0", "Cache item images for offline reading"),
+            Self::AssetCacheSize => (
+                Integer { min: 0, max: None },
+                "200",
+                "Megabytes of cached images to keep",
+            ),
+            Self::HttpTimeout => (
+                Integer { min: 1, max: None },
+                "30",
+                "Seconds before a request times out",
+            ),
+            Self::HttpConnectTimeout => (
+                Integer { min: 1, max: None },
+                "10",
+                "Seconds before connecting times out",
+            ),
+            Self::HttpRetries => (
+                Integer {
+                    min: 0,
+                    max: Some(10),
+                },
+                "2",
+                "Retries of requests that fail transiently",
+            ),
+            Self::UserAgent => (
+                Text,
+                "",
+                "User agent of requests. Empty identifies as Collie",
+            ),
+            Self::QuietHoursStart => (Time, "", "Local time to stop notifying at, like 22:00"),
+            Self::QuietHoursEnd => (Time, "", "Local time to notify again at, like 07:00"),
+            Self::NotificationInterval => (
+                Integer { min: 0, max: None },
+                "0",
+                "Minimum seconds between notifications",
+            ),
+        };
+
+        SettingSchema {
+            key: self.clone(),
+            value_type,
+            default,
+            description,
+            read_only: *self == Self::DbSchemeVersion,
+            secret: matches!(self, Self::ApiPassword | Self::MinifluxApiKey),
+        }
+    }
 }
 
 impl Display for SettingKey {
@@ -35,6 +193,23 @@ impl Display for SettingKey {
             Self::ItemsOrder => write!(f, "items_order"),
             Self::Proxy => write!(f, "proxy"),
             Self::FetchOldItems => write!(f, "fetch_old_items"),
+            Self::ApiServer => write!(f, "api_server"),
+            Self::ApiServerAddress => write!(f, "api_server_address"),
+            Self::ApiUsername => write!(f, "api_username"),
+            Self::ApiPassword => write!(f, "api_password"),
+            Self::MinifluxUrl => write!(f, "miniflux_url"),
+            Self::MinifluxApiKey => write!(f, "miniflux_api_key"),
+            Self::MinifluxSyncedAt => write!(f, "miniflux_synced_at"),
+            Self::SavedItemsFeedPath => write!(f, "saved_items_feed_path"),
+            Self::AssetCache => write!(f, "asset_cache"),
+            Self::AssetCacheSize => write!(f, "asset_cache_size"),
+            Self::HttpTimeout => write!(f, "http_timeout"),
+            Self::HttpConnectTimeout => write!(f, "http_connect_timeout"),
+            Self::HttpRetries => write!(f, "http_retries"),
+            Self::UserAgent => write!(f, "user_agent"),
+            Self::QuietHoursStart => write!(f, "quiet_hours_start"),
+            Self::QuietHoursEnd => write!(f, "quiet_hours_end"),
+            Self::NotificationInterval => write!(f, "notification_interval"),
         }
     }
 }
@@ -51,6 +226,23 @@ impl FromStr for SettingKey {
             "items_order" => Ok(Self::ItemsOrder),
             "proxy" => Ok(Self::Proxy),
             "fetch_old_items" => Ok(Self::FetchOldItems),
+            "api_server" => Ok(Self::ApiServer),
+            "api_server_address" => Ok(Self::ApiServerAddress),
+            "api_username" => Ok(Self::ApiUsername),
+            "api_password" => Ok(Self::ApiPassword),
+            "miniflux_url" => Ok(Self::MinifluxUrl),
+            "miniflux_api_key" => Ok(Self::MinifluxApiKey),
+            "miniflux_synced_at" => Ok(Self::MinifluxSyncedAt),
+            "saved_items_feed_path" => Ok(Self::SavedItemsFeedPath),
+            "asset_cache" => Ok(Self::AssetCache),
+            "asset_cache_size" => Ok(Self::AssetCacheSize),
+            "http_timeout" => Ok(Self::HttpTimeout),
+            "http_connect_timeout" => Ok(Self::HttpConnectTimeout),
+            "http_retries" => Ok(Self::HttpRetries),
+            "user_agent" => Ok(Self::UserAgent),
+            "quiet_hours_start" => Ok(Self::QuietHoursStart),
+            "quiet_hours_end" => Ok(Self::QuietHoursEnd),
+            "notification_interval" => Ok(Self::NotificationInterval),
             _ => Err(Error::InvalidEnumKey(
                 x.to_string(),
                 "SettingKey".to_string(),
@@ -59,18 +251,132 @@ impl FromStr for SettingKey {
     }
 }
 
+impl FromSql for SettingKey {
+    fn column_result(value: ValueRef) -> FromSqlResult<Self> {
+        parse_enum(value)
+    }
+}
+
+#[derive(Serialize, Debug, PartialEq, Clone)]
+#[serde(tag = "type")]
+pub enum SettingType {
+    /// Stored as "1" or "0", but "true" and "false" are accepted too.
+    Boolean,
+    Integer {
+        min: i64,
+        max: Option<i64>,
+    },
+    Choice {
+        options: &'static [&'static str],
+    },
+    /// "HH:MM" in local time, or empty.
+    Time,
+    /// A socket address like "127.0.0.1:8046".
+    Address,
+    /// A URL, or empty.
+    Url,
+    /// A proxy URL, or empty.
+    Proxy,
+    Text,
+}
+
+impl SettingType {
+    /// The value as it is stored, or why it is invalid.
+    fn parse(&self, value: &str) -> std::result::Result<String, String> {
+        match self {
+            Self::Boolean => match value {
+                "1" | "true" => Ok("1".to_string()),
+                "0" | "false" => Ok("0".to_string()),
+                _ => Err("must be a boolean".to_string()),
+            },
+            Self::Integer { min, max } => match (value.parse::<i64>(), max) {
+                (Ok(x), _) if x < *min => Err(format!("must be at least {}", min)),
+                (Ok(x), Some(max)) if x > *max => Err(format!("must be at most {}", max)),
+                (Ok(x), _) => Ok(x.to_string()),
+                (Err(_), _) => Err("must be an integer".to_string()),
+            },
+            Self::Choice { options } if options.contains(&value) => Ok(value.to_string()),
+            Self::Choice { options } => Err(format!("must be one of {}", options.join(", "))),
+            Self::Time if value.is_empty() => Ok(String::new()),
+            Self::Time => NaiveTime::parse_from_str(value, "%H:%M")
+                .map(|x| x.format("%H:%M").to_string())
+                .map_err(|_| "must be a time like 22:00, or empty".to_string()),
+            Self::Address => value
+                .parse::<SocketAddr>()
+                .map(|_| value.to_string())
+                .map_err(|_| "must be an address like 127.0.0.1:8046".to_string()),
+            Self::Url | Self::Proxy if value.is_empty() => Ok(String::new()),
+            Self::Url => Url::parse(value)
+                .map(|_| value.to_string())
+                .map_err(|_| "must be a URL, or empty".to_string()),
+            Self::Proxy => reqwest::Proxy::all(value)
+                .map(|_| value.to_string())
+                .map_err(|_| "must be a proxy URL, or empty".to_string()),
+            Self::Text => Ok(value.to_string()),
+        }
+    }
+}
+
 #[derive(Serialize, Debug)]
+pub struct SettingSchema {
+    pub key: SettingKey,
+    #[serde(flatten)]
+    pub value_type: SettingType,
+    pub default: &'static str,
+    pub description: &'static str,
+    /// Only changed by migrations.
+    pub read_only: bool,
+    /// Read back empty, and left as stored when updated empty.
+    pub secret: bool,
+}
+
+#[derive(Debug)]
 pub struct Setting {
     pub key: SettingKey,
     pub value: String,
 }
 
-impl From<&Row<'_>> for Setting {
-    fn from(row: &Row) -> Self {
-        Self {
-            key: SettingKey::from_str(&row.get_unwrap::<&str, String>("key")).unwrap(),
-            value: row.get_unwrap("value"),
-        }
+// Secrets are written to the database but never leave the backend.
+impl Serialize for Setting {
+    fn serialize<S: Serializer>(&self, serializer: S) -> std::result::Result<S::Ok, S::Error> {
+        let value = if self.key.schema().secret {
+            ""
+        } else {
+            &self.value
+        };
+
+        let mut state = serializer.serialize_struct("Setting", 2)?;
+        state.serialize_field("key", &self.key)?;
+        state.serialize_field("value", value)?;
+        state.end()
+    }
+}
+
+impl TryFrom<&Row<'_>> for Setting {
+    type Error = rusqlite::Error;
+
+    fn try_from(row: &Row) -> rusqlite::Result<Self> {
+        Ok(Self {
+            key: row.get("key")?,
+            value: row.get("value")?,
+        })
+    }
+}
+
+impl Setting {
+    // Stored values are validated like written ones, since older versions did not validate
+    // them all. Invalid ones read as the default.
+    fn validated(self) -> Self {
+        let schema = self.key.schema();
+        let value = schema
+            .value_type
+            .parse(&self.value)
+            .unwrap_or_else(|reason| {
+                eprintln!("Invalid setting {}: {}", self.key, reason);
+                schema.default.to_string()
+            });
+
+        Self { value, ..self }
     }
 }
 
@@ -87,13 +393,14 @@ pub fn read_all(db: &Connection) -> Result<Vec<Setting>> {
         .build_rusqlite(SqliteQueryBuilder);
 
     let mut stmt = db.prepare(sql.as_str())?;
-    let rows = stmt.query_map(&*values.as_params(), |x| Ok(Setting::from(x)))?;
+    let rows = stmt.query_map(&*values.as_params(), |x| Setting::try_from(x))?;
 
     Ok(rows
-        .map(std::result::Result::unwrap)
-        .collect::<Vec<Setting>>())
+        .map(|x| x.map(Setting::validated))
+        .collect::<rusqlite::Result<Vec<Setting>>>()?)
 }
 
+/// Reads the setting, or its default if it was never stored.
 pub fn read(db: &Connection, key: &SettingKey) -> Result<Setting> {
     let (sql, values) = Query::select()
         .columns([Settings::Key, Settings::Value])
@@ -104,44 +411,75 @@ pub fn read(db: &Connection, key: &SettingKey) -> Result<Setting> {
 
     let mut stmt = db.prepare(sql.as_str())?;
     let mut rows = stmt.query(&*values.as_params())?;
-    match rows.next()? {
-        None => Err(Error::Unknown),
-        row => Ok(row.map(Setting::from).unwrap()),
-    }
+    Ok(match rows.next()? {
+        Some(row) => Setting::try_from(row)?.validated(),
+        None => Setting {
+            key: key.clone(),
+            value: key.schema().default.to_string(),
+        },
+    })
+}
+
+/// The value of the setting, or its default if it cannot be read.
+pub fn value(db: &Connection, key: &SettingKey) -> String {
+    read(db, key).map(|x| x.value).unwrap_or_else(|err| {
+        eprintln!("Error reading setting {}: {}", key, err);
+        key.schema().default.to_string()
+    })
+}
+
+pub fn boolean(db: &Connection, key: &SettingKey) -> bool {
+    value(db, key) == "1"
+}
+
+pub fn integer(db: &Connection, key: &SettingKey) -> i64 {
+    value(db, key).parse().unwrap_or_default()
+}
+
+pub fn time(db: &Connection, key: &SettingKey) -> Option<NaiveTime> {
+    NaiveTime::parse_from_str(&value(db, key), "%H:%M").ok()
 }
 
 pub fn update(db: &Connection, arg: &SettingToUpdate) -> Result<usize> {
-    match arg.key {
-        SettingKey::PollingFrequency => {
-            if arg.value.parse::<i32>().map(|x| x < 30).unwrap_or(false) {
-                return Err(Error::Unknown);
-            }
-        }
-        SettingKey::Notification | SettingKey::FetchOldItems => {
-            if arg.value.parse::<bool>().unwrap_or(false) {
-                return Err(Error::Unknown);
-            }
-        }
-        SettingKey::Proxy => {
-            if arg
-                .value
-                .parse::<String>()
-                .map(|x| reqwest::Proxy::http(x.deref()))
-                .map(|_| false)
-                .unwrap_or(true)
-            {
-                return Err(Error::Unknown);
-            }
-        }
-        SettingKey::DbSchemeVersion => return Err(Error::Forbidden),
-        _ => {}
+    // An empty secret is the redacted one sent back. Resetting it clears it instead.
+    if arg.key.schema().secret && arg.value.is_empty() {
+        return Ok(0);
+    }
+
+    store(db, arg)
+}
+
+fn store(db: &Connection, arg: &SettingToUpdate) -> Result<usize> {
+    let schema = arg.key.schema();
+    if schema.read_only {
+        return Err(Error::Forbidden);
     }
 
+    let value = schema
+        .value_type
+        .parse(&arg.value)
+        .map_err(|reason| Error::InvalidSetting {
+            // Named like the variant, as the frontend knows it.
+            key: format!("{:?}", arg.key),
+            reason,
+        })?;
+
     let (sql, values) = Query::update()
         .table(Settings::Table)
-        .values([(Settings::Value, arg.value.clone().into())])
+        .values([(Settings::Value, value.into())])
         .and_where(Expr::col(Settings::Key).eq(arg.key.to_string()))
         .build_rusqlite(SqliteQueryBuilder);
 
     Ok(db.execute(sql.as_str(), &*values.as_params())?)
 }
+
+/// Sets the setting back to its default.
+pub fn reset(db: &Connection, key: &SettingKey) -> Result<Setting> {
+    let arg = SettingToUpdate {
+        key: key.clone(),
+        value: key.schema().default.to_string(),
+    };
+    store(db, &arg)?;
+
+    read(db, key)
+}
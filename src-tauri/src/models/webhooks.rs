@@ -0,0 +1,329 @@
+use core::fmt;
+use std::{
+    fmt::{Display, Formatter},
+    str::FromStr,
+};
+
+use chrono::{DateTime, FixedOffset, Utc};
+use regex::Regex;
+use rusqlite::types::{FromSql, FromSqlResult, ValueRef};
+use rusqlite::{Connection, Row};
+use sea_query::{Expr, Order, Query, SqliteQueryBuilder};
+use sea_query_rusqlite::RusqliteBinder;
+use serde::{Deserialize, Serialize};
+use url::Url;
+
+use crate::error::{Error, Result};
+
+use super::database::{deserialize_some, parse_enum, WebhookDeliveries, Webhooks};
+
+/// Deliveries kept per webhook. Older ones are deleted as new ones are made.
+pub const DELIVERIES_KEPT: u64 = 100;
+
+#[derive(Serialize, Deserialize, Debug, PartialEq, Clone)]
+pub enum WebhookFormat {
+    Json,
+    Form,
+}
+
+impl Display for WebhookFormat {
+    fn fmt(&self, f: &mut Formatter) -> fmt::Result {
+        match self {
+            Self::Json => write!(f, "json"),
+            Self::Form => write!(f, "form"),
+        }
+    }
+}
+
+impl FromStr for WebhookFormat {
+    type Err = Error;
+
+    fn from_str(x: &str) -> std::result::Result<Self, Self::Err> {
+        match x {
+            "json" => Ok(Self::Json),
+            "form" => Ok(Self::Form),
+            _ => Err(Error::InvalidEnumKey(
+                x.to_string(),
+                "WebhookFormat".to_string(),
+            )),
+        }
+    }
+}
+
+impl FromSql for WebhookFormat {
+    fn column_result(value: ValueRef) -> FromSqlResult<Self> {
+        parse_enum(value)
+    }
+}
+
+#[derive(Serialize, Debug)]
+pub struct Webhook {
+    pub id: i32,
+    pub url: String,
+    pub feed: Option<i32>,
+    pub filter: Option<String>,
+    pub format: WebhookFormat,
+    pub template: Option<String>,
+}
+
+impl TryFrom<&Row<'_>> for Webhook {
+    type Error = rusqlite::Error;
+
+    fn try_from(row: &Row) -> rusqlite::Result<Self> {
+        Ok(Self {
+            id: row.get("id")?,
+            url: row.get("url")?,
+            feed: row.get("feed")?,
+            filter: row.get("filter")?,
+            format: row.get("format")?,
+            template: row.get("template")?,
+        })
+    }
+}
+
+#[derive(Deserialize)]
+pub struct WebhookToCreate {
+    pub url: String,
+    pub feed: Option<i32>,
+    pub filter: Option<String>,
+    pub format: WebhookFormat,
+    pub template: Option<String>,
+}
+
+#[derive(Deserialize)]
+pub struct WebhookToUpdate {
+    pub id: i32,
+    pub url: Option<String>,
+    #[serde(default, deserialize_with = "deserialize_some")]
+    pub feed: Option<Option<i32>>,
+    #[serde(default, deserialize_with = "deserialize_some")]
+    pub filter: Option<Option<String>>,
+    pub format: Option<WebhookFormat>,
+    #[serde(default, deserialize_with = "deserialize_some")]
+    pub template: Option<Option<String>>,
+}
+
+#[derive(Serialize, Debug)]
+pub struct WebhookDelivery {
+    pub id: i32,
+    pub webhook: i32,
+    pub link: String,
+    pub status_code: Option<u16>,
+    pub error: Option<String>,
+    pub attempts: u32,
+    pub delivered_at: DateTime<FixedOffset>,
+}
+
+impl TryFrom<&Row<'_>> for WebhookDelivery {
+    type Error = rusqlite::Error;
+
+    fn try_from(row: &Row) -> rusqlite::Result<Self> {
+        Ok(Self {
+            id: row.get("id")?,
+            webhook: row.get("webhook")?,
+            link: row.get("link")?,
+            status_code: row.get("status_code")?,
+            error: row.get("error")?,
+            attempts: row.get("attempts")?,
+            delivered_at: row.get("delivered_at")?,
+        })
+    }
+}
+
+pub struct WebhookDeliveryToCreate {
+    pub webhook: i32,
+    pub link: String,
+    pub status_code: Option<u16>,
+    pub error: Option<String>,
+    pub attempts: u32,
+}
+
+pub fn create(db: &Connection, arg: &WebhookToCreate) -> Result<usize> {
+    validate_url(&arg.url)?;
+    if let Some(filter) = &arg.filter {
+        validate_filter(filter)?;
+    }
+
+    let (sql, values) = Query::insert()
+        .into_table(Webhooks::Table)
+        .columns([
+            Webhooks::Url,
+            Webhooks::Feed,
+            Webhooks::Filter,
+            Webhooks::Format,
+            Webhooks::Template,
+        ])
+        .values_panic([
+            arg.url.clone().into(),
+            arg.feed.into(),
+            arg.filter.clone().into(),
+            arg.format.to_string().into(),
+            arg.template.clone().into(),
+        ])
+        .build_rusqlite(SqliteQueryBuilder);
+
+    Ok(db.execute(sql.as_str(), &*values.as_params())?)
+}
+
+pub fn read_all(db: &Connection) -> Result<Vec<Webhook>> {
+    let (sql, values) = Query::select()
+        .columns([
+            Webhooks::Id,
+            Webhooks::Url,
+            Webhooks::Feed,
+            Webhooks::Filter,
+            Webhooks::Format,
+            Webhooks::Template,
+        ])
+        .from(Webhooks::Table)
+        .build_rusqlite(SqliteQueryBuilder);
+
+    let mut stmt = db.prepare(sql.as_str())?;
+    let rows = stmt.query_map(&*values.as_params(), |x| Webhook::try_from(x))?;
+
+    Ok(rows.collect::<rusqlite::Result<Vec<Webhook>>>()?)
+}
+
+pub fn update(db: &Connection, arg: &WebhookToUpdate) -> Result<usize> {
+    let mut vals = vec![];
+
+    if let Some(url) = &arg.url {
+        validate_url(url)?;
+        vals.push((Webhooks::Url, url.into()));
+    }
+
+    if let Some(feed) = arg.feed {
+        vals.push((Webhooks::Feed, feed.into()));
+    }
+
+    if let Some(filter) = &arg.filter {
+        if let Some(filter) = filter {
+            validate_filter(filter)?;
+        }
+        vals.push((Webhooks::Filter, filter.clone().into()));
+    }
+
+    if let Some(format) = &arg.format {
+        vals.push((Webhooks::Format, format.to_string().into()));
+    }
+
+    if let Some(template) = &arg.template {
+        vals.push((Webhooks::Template, template.clone().into()));
+    }
+
+    if vals.is_empty() {
+        return Err(Error::NothingToUpdate);
+    }
+
+    let (sql, values) = Query::update()
+        .table(Webhooks::Table)
+        .values(vals)
+        .and_where(Expr::col(Webhooks::Id).eq(arg.id))
+        .build_rusqlite(SqliteQueryBuilder);
+
+    match db.execute(sql.as_str(), &*values.as_params())? {
+        0 => Err(Error::WebhookNotFound(arg.id)),
+        count => Ok(count),
+    }
+}
+
+fn validate_url(url: &str) -> Result<()> {
+    if Url::parse(url).is_err() {
+        return Err(Error::InvalidValue(url.to_string()));
+    }
+
+    Ok(())
+}
+
+fn validate_filter(filter: &str) -> Result<()> {
+    if Regex::new(filter).is_err() {
+        return Err(Error::InvalidValue(filter.to_string()));
+    }
+
+    Ok(())
+}
+
+pub fn delete(db: &Connection, id: i32) -> Result<usize> {
+    let (sql, values) = Query::delete()
+        .from_table(Webhooks::Table)
+        .and_where(Expr::col(Webhooks::Id).eq(id))
+        .build_rusqlite(SqliteQueryBuilder);
+
+    match db.execute(sql.as_str(), &*values.as_params())? {
+        0 => Err(Error::WebhookNotFound(id)),
+        count => Ok(count),
+    }
+}
+
+pub fn create_delivery(db: &Connection, arg: &WebhookDeliveryToCreate) -> Result<usize> {
+    let (sql, values) = Query::insert()
+        .into_table(WebhookDeliveries::Table)
+        .columns([
+            WebhookDeliveries::Webhook,
+            WebhookDeliveries::Link,
+            WebhookDeliveries::StatusCode,
+            WebhookDeliveries::Error,
+            WebhookDeliveries::Attempts,
+            WebhookDeliveries::DeliveredAt,
+        ])
+        .values_panic([
+            arg.webhook.into(),
+            arg.link.clone().into(),
+            arg.status_code.into(),
+            arg.error.clone().into(),
+            arg.attempts.into(),
+            Utc::now().into(),
+        ])
+        .build_rusqlite(SqliteQueryBuilder);
+    let count = db.execute(sql.as_str(), &*values.as_params())?;
+
+    let (sql, values) = Query::delete()
+        .from_table(WebhookDeliveries::Table)
+        .and_where(Expr::col(WebhookDeliveries::Webhook).eq(arg.webhook))
+        .and_where(
+            Expr::col(WebhookDeliveries::Id).not_in_subquery(
+                Query::select()
+                    .column(WebhookDeliveries::Id)
+                    .from(WebhookDeliveries::Table)
+                    .and_where(Expr::col(WebhookDeliveries::Webhook).eq(arg.webhook))
+                    .order_by(WebhookDeliveries::Id, Order::Desc)
+                    .limit(DELIVERIES_KEPT)
+                    .to_owned(),
+            ),
+        )
+        .build_rusqlite(SqliteQueryBuilder);
+    db.execute(sql.as_str(), &*values.as_params())?;
+
+    Ok(count)
+}
+
+pub fn read_all_deliveries(
+    db: &Connection,
+    webhook: i32,
+    limit: Option<u64>,
+) -> Result<Vec<WebhookDelivery>> {
+    let mut query = Query::select()
+        .columns([
+            WebhookDeliveries::Id,
+            WebhookDeliveries::Webhook,
+            WebhookDeliveries::Link,
+            WebhookDeliveries::StatusCode,
+            WebhookDeliveries::Error,
+            WebhookDeliveries::Attempts,
+            WebhookDeliveries::DeliveredAt,
+        ])
+        .from(WebhookDeliveries::Table)
+        .and_where(Expr::col(WebhookDeliveries::Webhook).eq(webhook))
+        .order_by(WebhookDeliveries::Id, Order::Desc)
+        .clone();
+
+    if let Some(limit) = limit {
+        query.limit(limit);
+    }
+
+    let (sql, values) = query.build_rusqlite(SqliteQueryBuilder);
+    let mut stmt = db.prepare(sql.as_str())?;
+    let rows = stmt.query_map(&*values.as_params(), |x| WebhookDelivery::try_from(x))?;
+
+    Ok(rows.collect::<rusqlite::Result<Vec<WebhookDelivery>>>()?)
+}
@@ -0,0 +1,63 @@
+use std::net::{TcpStream, ToSocketAddrs};
+use std::time::Duration;
+
+use rusqlite::Connection;
+use url::Url;
+
+use crate::http::HttpConfig;
+use crate::models::feeds::{self, FeedStatus};
+
+const PROBE_TIMEOUT: Duration = Duration::from_secs(3);
+const MAX_PROBES: usize = 3;
+
+/// Where to look for connectivity: the global proxy, since nothing is reachable without it,
+/// or else the hosts, or proxies, of a few subscribed feeds.
+pub fn probe_targets(db: &Connection) -> Vec<String> {
+    if let Some(proxy) = HttpConfig::from_settings(db).proxy {
+        return address(&proxy).into_iter().collect();
+    }
+
+    let mut targets = vec![];
+    let subscribed = feeds::read_all(db)
+        .unwrap_or_default()
+        .into_iter()
+        .filter(|x| x.status == FeedStatus::Subscribed);
+
+    for feed in subscribed {
+        let link = match feed.proxy.as_deref() {
+            Some(proxy) if !proxy.is_empty() => proxy,
+            _ => &feed.link,
+        };
+
+        if let Some(target) = address(link).filter(|x| !targets.contains(x)) {
+            targets.push(target);
+        }
+        if targets.len() == MAX_PROBES {
+            break;
+        }
+    }
+
+    targets
+}
+
+/// Whether any of the targets accepts a connection. Without targets there is nothing to
+/// fetch anyway, so the network is assumed to be up.
+pub fn is_online(targets: &[String]) -> bool {
+    targets.is_empty()
+        || targets.iter().any(|target| {
+            target
+                .to_socket_addrs()
+                .ok()
+                .and_then(|mut addrs| addrs.next())
+                .is_some_and(|addr| TcpStream::connect_timeout(&addr, PROBE_TIMEOUT).is_ok())
+        })
+}
+
+fn address(link: &str) -> Option<String> {
+    let url = Url::parse(link).ok()?;
+    Some(format!(
+        "{}:{}",
+        url.host_str()?,
+        url.port_or_known_default()?
+    ))
+}
@@ -0,0 +1,181 @@
+use std::collections::HashMap;
+use std::mem;
+use std::sync::OnceLock;
+
+use chrono::{DateTime, Duration, Local, NaiveTime};
+use regex::Regex;
+use rusqlite::Connection;
+
+use crate::models::feeds;
+use crate::models::items::{self, ItemToCreate};
+use crate::models::settings::{self, SettingKey};
+
+#[derive(Debug, PartialEq)]
+pub struct Toast {
+    pub title: String,
+    pub body: String,
+    /// The item to open when clicked. Summaries and digests open the unread items instead.
+    pub item: Option<i32>,
+}
+
+pub struct NotifierConfig {
+    pub enabled: bool,
+    pub quiet_hours: Option<(NaiveTime, NaiveTime)>,
+    pub interval: Duration,
+}
+
+impl NotifierConfig {
+    pub fn from_settings(db: &Connection) -> Self {
+        Self {
+            enabled: settings::boolean(db, &SettingKey::Notification),
+            quiet_hours: settings::time(db, &SettingKey::QuietHoursStart)
+                .zip(settings::time(db, &SettingKey::QuietHoursEnd))
+                .filter(|(start, end)| start != end),
+            interval: Duration::seconds(settings::integer(db, &SettingKey::NotificationInterval)),
+        }
+    }
+
+    // Quiet hours may span midnight, like 22:00 to 07:00.
+    fn is_quiet(&self, now: NaiveTime) -> bool {
+        match self.quiet_hours {
+            Some((start, end)) if start < end => start <= now && now < end,
+            Some((start, end)) => start <= now || now < end,
+            None => false,
+        }
+    }
+}
+
+/// Decides what to notify of. Items held back during quiet hours, or because the last
+/// notification was too recent, are shown later in a single digest.
+#[derive(Default)]
+pub struct Notifier {
+    // Titles of the held back items.
+    held: Vec<String>,
+    notified_at: Option<DateTime<Local>>,
+}
+
+impl Notifier {
+    /// Notifies of the new items of feeds that are not muted and whose titles match the
+    /// filter of their feed, if any.
+    pub fn notify(
+        &mut self,
+        db: &Connection,
+        items: &[ItemToCreate],
+        now: DateTime<Local>,
+    ) -> Vec<Toast> {
+        let config = NotifierConfig::from_settings(db);
+        if !config.enabled {
+            return vec![];
+        }
+
+        let items = notable(db, items);
+        if items.is_empty() {
+            return vec![];
+        }
+
+        if !self.held.is_empty() || !self.may_notify(&config, now) {
+            self.held.extend(items.iter().map(|x| x.title.clone()));
+            return self.flush_with(&config, now);
+        }
+
+        self.notified_at = Some(now);
+        toasts(db, &items)
+    }
+
+    /// The digest of the held back items, once they can be shown.
+    pub fn flush(&mut self, db: &Connection, now: DateTime<Local>) -> Vec<Toast> {
+        let config = NotifierConfig::from_settings(db);
+        if !config.enabled {
+            self.held.clear();
+            return vec![];
+        }
+
+        self.flush_with(&config, now)
+    }
+
+    fn flush_with(&mut self, config: &NotifierConfig, now: DateTime<Local>) -> Vec<Toast> {
+        if self.held.is_empty() || !self.may_notify(config, now) {
+            return vec![];
+        }
+
+        self.notified_at = Some(now);
+        vec![digest(&mem::take(&mut self.held))]
+    }
+
+    fn may_notify(&self, config: &NotifierConfig, now: DateTime<Local>) -> bool {
+        !config.is_quiet(now.time())
+            && self
+                .notified_at
+                .is_none_or(|notified_at| now - notified_at >= config.interval)
+    }
+}
+
+fn notable<'a>(db: &Connection, items: &'a [ItemToCreate]) -> Vec<&'a ItemToCreate> {
+    let feeds = feeds::read_all(db).unwrap_or_default();
+    let rules = feeds
+        .iter()
+        .map(|feed| {
+            let filter = feed.notification_filter.as_deref().and_then(|x| {
+                Regex::new(x)
+                    .inspect_err(|err| eprintln!("Invalid filter for feed {}: {}", feed.id, err))
+                    .ok()
+            });
+            (feed.id, (feed.notification, filter))
+        })
+        .collect::<HashMap<_, _>>();
+
+    items
+        .iter()
+        .filter(|item| match rules.get(&item.feed) {
+            Some((notification, filter)) => {
+                *notification && filter.as_ref().is_none_or(|x| x.is_match(&item.title))
+            }
+            None => true,
+        })
+        .collect()
+}
+
+fn toasts(db: &Connection, items: &[&ItemToCreate]) -> Vec<Toast> {
+    static TAG: OnceLock<Regex> = OnceLock::new();
+    let tag = TAG.get_or_init(|| Regex::new(r"<.*?>").unwrap());
+
+    if items.len() <= 3 {
+        items
+            .iter()
+            .map(|item| Toast {
+                title: item.title.clone(),
+                body: tag.replace_all(&item.description, "").to_string(),
+                item: items::read_by_fingerprint(db, &item.fingerprint())
+                    .ok()
+                    .flatten()
+                    .map(|x| x.id),
+            })
+            .collect()
+    } else {
+        vec![Toast {
+            title: "New items arrived".to_string(),
+            body: format!("There are {} items to read", items.len()),
+            item: None,
+        }]
+    }
+}
+
+fn digest(titles: &[String]) -> Toast {
+    let count = titles.len();
+    let titles = titles
+        .iter()
+        .take(3)
+        .cloned()
+        .collect::<Vec<_>>()
+        .join(", ");
+
+    Toast {
+        title: "New items arrived".to_string(),
+        body: if count > 3 {
+            format!("{} and {} more", titles, count - 3)
+        } else {
+            titles
+        },
+        item: None,
+    }
+}
@@ -1,28 +1,58 @@
 use std::collections::HashMap;
+use std::ops::ControlFlow;
 
 use chrono::{DateTime, FixedOffset, Utc};
 use rusqlite::Connection;
+use serde::Serialize;
 
-use crate::models::feeds::FeedStatus;
-use crate::syndication::RawItem;
+use crate::extractor::fetch_full_content;
+use crate::http::HttpClient;
+use crate::models::feeds::{Feed, FeedMoveReason, FeedStatus, FeedToMove};
+use crate::sanitizer::sanitize;
+use crate::syndication::{RawFeed, RawItem};
 use crate::{
     models::{
-        feeds::{self, FeedToUpdate},
+        feeds::{self, FeedMetadata, FeedToUpdate},
         items::{self, ItemOrder, ItemReadOption, ItemStatus, ItemToCreate},
     },
-    syndication::fetch_feed_items,
+    syndication::fetch_feed,
 };
 
 use crate::error::Result;
 
-pub fn create_new_items(db: &Connection, proxy: Option<&str>) -> Result<Vec<ItemToCreate>> {
-    let pairs = get_links_to_check(db);
+/// What refreshing a feed did. Errors are kept per feed so that one broken feed does not
+/// keep the others from being refreshed.
+#[derive(Serialize, Clone, Debug, PartialEq)]
+pub struct FeedOutcome {
+    pub feed: i32,
+    pub new_items: usize,
+    pub error: Option<String>,
+}
 
-    let mut inserted = vec![];
+pub struct Refreshed {
+    pub inserted: Vec<ItemToCreate>,
+    pub outcomes: Vec<FeedOutcome>,
+}
 
-    let feed_ids_to_check: Vec<i32> = pairs
+#[derive(Debug, PartialEq)]
+pub enum Progress<'a> {
+    Started { feeds: usize },
+    Fetched(&'a FeedOutcome),
+}
+
+/// Fetches the subscribed feeds, or only `feed` if given, and inserts their new items.
+/// `progress` is told what is going on and can stop the refresh before the next feed.
+pub fn create_new_items(
+    db: &Connection,
+    client: &HttpClient,
+    feed: Option<i32>,
+    mut progress: impl FnMut(Progress) -> ControlFlow<()>,
+) -> Refreshed {
+    let feeds = get_feeds_to_check(db, feed);
+
+    let feed_ids_to_check: Vec<i32> = feeds
         .iter()
-        .filter_map(|(id, _, fetch_old_items)| if !fetch_old_items { Some(*id) } else { None })
+        .filter_map(|x| if !x.fetch_old_items { Some(x.id) } else { None })
         .collect();
 
     let most_recent_items = if !feed_ids_to_check.is_empty() {
@@ -31,41 +61,137 @@ pub fn create_new_items(db: &Connection, proxy: Option<&str>) -> Result<Vec<Item
         HashMap::new()
     };
 
-    for (feed, link, fetch_old_items) in pairs {
-        let items = fetch_feed_items(&link, proxy)?;
-
-        let mut filtered_items = if !fetch_old_items && most_recent_items.get(&feed).is_none() {
-            items
-                .into_iter()
-                .max_by_key(|x| x.published_at)
-                .into_iter()
-                .collect()
-        } else {
-            items
-                .into_iter()
-                .filter(|item| {
-                    most_recent_items.get(&feed).map_or(true, |most_recent| {
-                        item.published_at
-                            .map_or(false, |published_at| published_at > *most_recent)
-                    }) || fetch_old_items
-                })
-                .collect::<Vec<_>>()
+    let mut refreshed = Refreshed {
+        inserted: vec![],
+        outcomes: vec![],
+    };
+
+    let started = progress(Progress::Started { feeds: feeds.len() });
+    if started.is_break() {
+        return refreshed;
+    }
+
+    for feed in feeds {
+        let (new_items, error) =
+            match create_feed_items(db, &feed, most_recent_items.get(&feed.id), client) {
+                Ok(new_items) => (new_items, None),
+                Err(err) => {
+                    eprintln!("Error fetching {}: {}", feed.link, err);
+                    (vec![], Some(err.to_string()))
+                }
+            };
+
+        let outcome = FeedOutcome {
+            feed: feed.id,
+            new_items: new_items.len(),
+            error,
         };
+        let fetched = progress(Progress::Fetched(&outcome));
+        refreshed.outcomes.push(outcome);
+        refreshed.inserted.extend(new_items);
 
-        filtered_items.sort_by_key(|x| x.published_at);
-        inserted.extend(insert_new_items(db, feed, &filtered_items));
+        if fetched.is_break() {
+            break;
+        }
     }
 
-    Ok(inserted)
+    refreshed
+}
+
+fn create_feed_items(
+    db: &Connection,
+    feed: &Feed,
+    most_recent: Option<&DateTime<FixedOffset>>,
+    client: &HttpClient,
+) -> Result<Vec<ItemToCreate>> {
+    let feed_client = client.for_feed(feed)?;
+    let (metadata, items) = fetch_feed(&feed.link, &feed_client)?;
+    relocate(db, feed, &metadata, &feed_client);
+    update_metadata(db, feed.id, metadata);
+
+    let fetch_old_items = feed.fetch_old_items;
+    let mut filtered_items = if !fetch_old_items && most_recent.is_none() {
+        items
+            .into_iter()
+            .max_by_key(|x| x.published_at)
+            .into_iter()
+            .collect()
+    } else {
+        items
+            .into_iter()
+            .filter(|item| {
+                most_recent.map_or(true, |most_recent| {
+                    item.published_at
+                        .map_or(false, |published_at| published_at > *most_recent)
+                }) || fetch_old_items
+            })
+            .collect::<Vec<_>>()
+    };
+
+    filtered_items.sort_by_key(|x| x.published_at);
+    Ok(insert_new_items(
+        db,
+        feed.id,
+        &filtered_items,
+        feed.fetch_full_content,
+        &feed_client,
+    ))
 }
 
-fn get_links_to_check(db: &Connection) -> Vec<(i32, String, bool)> {
+// Moves the feed if it was permanently redirected or its publisher announced a new location.
+// Announced locations are only trusted if they serve a feed.
+fn relocate(db: &Connection, feed: &Feed, metadata: &RawFeed, client: &HttpClient) {
+    let self_link_changed =
+        |x: &&String| feed.self_link.as_ref().is_some_and(|prev| prev != *x) && **x != feed.link;
+
+    let (link, reason) = if let Some(link) = &metadata.redirected_to {
+        (link, FeedMoveReason::PermanentRedirect)
+    } else if let Some(link) = metadata.new_feed_url.as_ref().filter(|x| **x != feed.link) {
+        (link, FeedMoveReason::NewFeedUrl)
+    } else if let Some(link) = metadata.self_link.as_ref().filter(self_link_changed) {
+        (link, FeedMoveReason::SelfLink)
+    } else {
+        return;
+    };
+
+    if reason != FeedMoveReason::PermanentRedirect && fetch_feed(link, client).is_err() {
+        return;
+    }
+
+    let arg = FeedToMove {
+        id: feed.id,
+        old_link: feed.link.clone(),
+        new_link: link.clone(),
+        reason,
+    };
+    if let Err(err) = feeds::relocate(db, &arg) {
+        eprintln!("Error moving {} to {}: {}", arg.old_link, arg.new_link, err);
+    }
+}
+
+fn update_metadata(db: &Connection, feed: i32, metadata: RawFeed) {
+    let _ = feeds::update_metadata(
+        db,
+        feed,
+        &FeedMetadata {
+            site_link: metadata.site_link,
+            description: metadata.description,
+            language: metadata.language,
+            image: metadata.image,
+            self_link: metadata.self_link,
+        },
+    );
+}
+
+fn get_feeds_to_check(db: &Connection, feed: Option<i32>) -> Vec<Feed> {
     if let Ok(feeds) = feeds::read_all(db) {
         let current = Utc::now().fixed_offset();
-        let filtered = feeds.iter().filter(|x| x.status == FeedStatus::Subscribed);
+        let filtered = feeds.into_iter().filter(|x| {
+            x.status == FeedStatus::Subscribed && (feed.is_none() || feed == Some(x.id))
+        });
 
         filtered
-            .map(|x| {
+            .inspect(|x| {
                 let _ = feeds::update(
                     db,
                     &(FeedToUpdate {
@@ -75,9 +201,15 @@ fn get_links_to_check(db: &Connection) -> Vec<(i32, String, bool)> {
                         status: None,
                         checked_at: Some(current),
                         fetch_old_items: None,
+                        fetch_full_content: None,
+                        user_agent: None,
+                        credentials: None,
+                        headers: None,
+                        proxy: None,
+                        notification: None,
+                        notification_filter: None,
                     }),
                 );
-                (x.id, x.link.clone(), x.fetch_old_items)
             })
             .collect()
     } else {
@@ -85,27 +217,43 @@ fn get_links_to_check(db: &Connection) -> Vec<(i32, String, bool)> {
     }
 }
 
-fn insert_new_items(db: &Connection, feed: i32, items: &[RawItem]) -> Vec<ItemToCreate> {
+fn insert_new_items(
+    db: &Connection,
+    feed: i32,
+    items: &[RawItem],
+    full_content: bool,
+    client: &HttpClient,
+) -> Vec<ItemToCreate> {
     let current = Utc::now().fixed_offset();
 
-    let args = items.iter().map(|x| ItemToCreate {
-        author: x.author.clone().map(|x| x.trim().to_string()),
-        title: x.title.trim().to_string(),
-        link: x.link.clone().unwrap_or("#".to_string()).trim().to_string(),
-        description: x
+    let args = items.iter().map(|x| {
+        let link = x.link.clone().unwrap_or("#".to_string()).trim().to_string();
+        let raw_description = x
             .content
             .clone()
             .unwrap_or(String::new())
             .trim()
-            .to_string(),
-        status: ItemStatus::Unread,
-        published_at: x.published_at.unwrap_or(current),
-        feed,
+            .to_string();
+
+        ItemToCreate {
+            author: x.author.clone().map(|x| x.trim().to_string()),
+            title: x.title.trim().to_string(),
+            description: sanitize(&raw_description, Some(&link)),
+            link,
+            status: ItemStatus::Unread,
+            published_at: x.published_at.unwrap_or(current),
+            feed,
+            thumbnail: x.thumbnail.clone(),
+            raw_description: Some(raw_description),
+        }
     });
 
     let mut inserted = vec![];
     for arg in args {
         if items::create(db, &arg).is_ok() {
+            if full_content {
+                insert_full_content(db, db.last_insert_rowid() as i32, &arg.link, client);
+            }
             inserted.push(arg);
         }
     }
@@ -113,6 +261,16 @@ fn insert_new_items(db: &Connection, feed: i32, items: &[RawItem]) -> Vec<ItemTo
     inserted
 }
 
+fn insert_full_content(db: &Connection, id: i32, link: &str, client: &HttpClient) {
+    match fetch_full_content(link, client) {
+        Ok(Some(content)) => {
+            let _ = items::update_full_content(db, id, &content);
+        }
+        Ok(None) => {}
+        Err(err) => eprintln!("Error fetching full content of {}: {}", link, err),
+    }
+}
+
 fn get_most_recent_items(
     db: &Connection,
     feed_ids: &[i32],
@@ -122,9 +280,13 @@ fn get_most_recent_items(
     for feed_id in feed_ids {
         let opt = ItemReadOption {
             ids: None,
+            since_id: None,
+            max_id: None,
             feed: Some(*feed_id),
             status: None,
             is_saved: None,
+            published_after: None,
+            published_before: None,
             order_by: Some(ItemOrder::PublishedDateDesc),
             limit: Some(1),
             offset: None,
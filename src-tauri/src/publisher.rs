@@ -0,0 +1,181 @@
+use core::fmt::{self, Display, Formatter};
+use std::fs;
+use std::path::Path;
+use std::str::FromStr;
+
+use atom_syndication::{
+    CategoryBuilder, ContentBuilder, EntryBuilder, FeedBuilder, LinkBuilder, PersonBuilder,
+};
+use chrono::{DateTime, FixedOffset};
+use rss::{ChannelBuilder, GuidBuilder, ItemBuilder};
+use rusqlite::Connection;
+
+use crate::assets;
+use crate::error::{Error, Result};
+use crate::models::items::{self, Item, ItemOrder, ItemReadOption};
+use crate::models::settings::{self, SettingKey};
+
+const TITLE: &str = "Saved items";
+const DESCRIPTION: &str = "Items saved in Collie";
+const HOMEPAGE: &str = env!("CARGO_PKG_REPOSITORY");
+
+#[derive(Debug, PartialEq)]
+pub enum PublishFormat {
+    Atom,
+    Rss,
+}
+
+impl Display for PublishFormat {
+    fn fmt(&self, f: &mut Formatter) -> fmt::Result {
+        match self {
+            Self::Atom => write!(f, "atom"),
+            Self::Rss => write!(f, "rss"),
+        }
+    }
+}
+
+impl FromStr for PublishFormat {
+    type Err = Error;
+
+    fn from_str(x: &str) -> std::result::Result<Self, Self::Err> {
+        match x {
+            "atom" => Ok(Self::Atom),
+            "rss" => Ok(Self::Rss),
+            _ => Err(Error::InvalidEnumKey(
+                x.to_string(),
+                "PublishFormat".to_string(),
+            )),
+        }
+    }
+}
+
+impl PublishFormat {
+    pub fn content_type(&self) -> &'static str {
+        match self {
+            Self::Atom => "application/atom+xml; charset=utf-8",
+            Self::Rss => "application/rss+xml; charset=utf-8",
+        }
+    }
+}
+
+/// Serializes the saved items, optionally only those of the given feed, into an Atom or RSS document.
+pub fn publish(db: &Connection, format: &PublishFormat, feed: Option<i32>) -> Result<String> {
+    let mut items = items::read_all(
+        db,
+        &ItemReadOption {
+            feed,
+            is_saved: Some(true),
+            order_by: Some(ItemOrder::ReceivedDateDesc),
+            ..Default::default()
+        },
+    )?;
+    assets::restore(db, &mut items)?;
+
+    Ok(match format {
+        PublishFormat::Atom => atom(&items),
+        PublishFormat::Rss => rss(&items),
+    })
+}
+
+/// Writes the saved items to the file at `saved_items_feed_path`, if set.
+/// The document is RSS if the file ends with `.rss`, and Atom otherwise.
+pub fn write(db: &Connection) -> Result<()> {
+    let path = settings::read(db, &SettingKey::SavedItemsFeedPath)?.value;
+    if path.trim().is_empty() {
+        return Ok(());
+    }
+
+    let path = Path::new(path.trim());
+    let format = match path.extension().and_then(|x| x.to_str()) {
+        Some("rss") => PublishFormat::Rss,
+        _ => PublishFormat::Atom,
+    };
+
+    Ok(fs::write(path, publish(db, &format, None)?)?)
+}
+
+/// Writes the saved items after they changed. The change is made anyway, so failing to write
+/// them is only logged.
+pub fn refresh(db: &Connection) {
+    if let Err(err) = write(db) {
+        eprintln!("Error writing saved items feed: {}", err);
+    }
+}
+
+fn updated_at(item: &Item) -> DateTime<FixedOffset> {
+    item.updated_at.unwrap_or(item.published_at)
+}
+
+fn atom(items: &[Item]) -> String {
+    let entries = items
+        .iter()
+        .map(|x| {
+            EntryBuilder::default()
+                .id(x.link.clone())
+                .title(x.title.clone())
+                .updated(updated_at(x))
+                .published(Some(x.published_at))
+                .links(vec![LinkBuilder::default().href(x.link.clone()).build()])
+                .authors(
+                    x.author
+                        .iter()
+                        .filter(|x| !x.is_empty())
+                        .map(|x| PersonBuilder::default().name(x.clone()).build())
+                        .collect::<Vec<_>>(),
+                )
+                .categories(vec![CategoryBuilder::default()
+                    .term(x.feed.id.to_string())
+                    .label(Some(x.feed.title.clone()))
+                    .build()])
+                .content(Some(
+                    ContentBuilder::default()
+                        .value(Some(x.description.clone()))
+                        .content_type(Some("html".to_string()))
+                        .build(),
+                ))
+                .build()
+        })
+        .collect::<Vec<_>>();
+
+    FeedBuilder::default()
+        .id(HOMEPAGE)
+        .title(TITLE)
+        .subtitle(Some(DESCRIPTION.into()))
+        .updated(items.iter().map(updated_at).max().unwrap_or_default())
+        .links(vec![LinkBuilder::default().href(HOMEPAGE).build()])
+        .entries(entries)
+        .build()
+        .to_string()
+}
+
+fn rss(items: &[Item]) -> String {
+    let items = items
+        .iter()
+        .map(|x| {
+            ItemBuilder::default()
+                .title(Some(x.title.clone()))
+                .link(Some(x.link.clone()))
+                .author(x.author.clone().filter(|x| !x.is_empty()))
+                .description(Some(x.description.clone()))
+                .guid(Some(
+                    GuidBuilder::default()
+                        .value(x.link.clone())
+                        .permalink(true)
+                        .build(),
+                ))
+                .pub_date(Some(x.published_at.to_rfc2822()))
+                .categories(vec![rss::CategoryBuilder::default()
+                    .name(x.feed.title.clone())
+                    .build()])
+                .build()
+        })
+        .collect::<Vec<_>>();
+
+    ChannelBuilder::default()
+        .title(TITLE)
+        .link(HOMEPAGE)
+        .description(DESCRIPTION)
+        .items(items)
+        .build()
+        .to_string()
+}
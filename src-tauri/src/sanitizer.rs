@@ -0,0 +1,130 @@
+use std::collections::{HashMap, HashSet};
+use std::sync::OnceLock;
+
+use ammonia::{Builder, UrlRelative};
+use regex::Regex;
+use rusqlite::Connection;
+use scraper::{ElementRef, Html, Selector};
+use url::Url;
+
+use crate::assets::PROTOCOL;
+use crate::error::Result;
+use crate::models::items;
+
+const URL_SCHEMES: [&str; 3] = ["http", "https", "mailto"];
+const TRACKERS: &str = r"(?i)feeds\.feedburner\.com/~r/|/~ff/|feedsportal\.com|pixel\.wp\.com|stats\.wordpress\.com|doubleclick\.net|/(beacon|pixel|track|open)(\.gif|\.png|/|\?)";
+
+static TRACKERS_REGEX: OnceLock<Regex> = OnceLock::new();
+
+/// Cleans up feed HTML before it is rendered in the webview. Only an allowlist of tags and
+/// attributes survives: scripts, styles, frames, event handlers and non-http(s) URLs are
+/// dropped, and so are tracking pixels. Relative URLs are resolved against `base` if given.
+pub fn sanitize(html_content: &str, base: Option<&str>) -> String {
+    let html_content = remove_tracking_pixels(html_content);
+
+    let mut builder = builder();
+    if let Some(base) = base.and_then(|x| Url::parse(x).ok()) {
+        builder.url_relative(UrlRelative::RewriteWithBase(base));
+    }
+
+    builder.clean(&html_content).to_string().trim().to_string()
+}
+
+/// Replaces the `src` of images in sanitized HTML according to `sources`.
+pub fn rewrite_images(html_content: &str, sources: &HashMap<String, String>) -> String {
+    let sources = sources.clone();
+    // Cached images are rewritten too, when they are pointed back to their original URLs.
+    let mut url_schemes = HashSet::from(URL_SCHEMES);
+    url_schemes.insert(PROTOCOL);
+
+    builder()
+        .url_schemes(url_schemes)
+        .attribute_filter(
+            move |element, attribute, value| match (element, attribute) {
+                ("img", "src") => Some(
+                    sources
+                        .get(value)
+                        .map_or(value, String::as_str)
+                        .to_string()
+                        .into(),
+                ),
+                _ => Some(value.into()),
+            },
+        )
+        .clean(html_content)
+        .to_string()
+        .trim()
+        .to_string()
+}
+
+/// Returns the `src` of every image in the HTML.
+pub fn image_sources(html_content: &str) -> Vec<String> {
+    let fragment = Html::parse_fragment(html_content);
+    let selector = Selector::parse("img[src]").unwrap();
+
+    fragment
+        .select(&selector)
+        .filter_map(|x| x.value().attr("src"))
+        .map(String::from)
+        .collect()
+}
+
+fn builder<'a>() -> Builder<'a> {
+    let mut builder = Builder::default();
+    builder
+        .url_schemes(HashSet::from(URL_SCHEMES))
+        .link_rel(Some("noopener noreferrer"))
+        .strip_comments(true);
+
+    builder
+}
+
+/// Sanitizes the items stored before descriptions were sanitized, keeping the original as raw.
+pub fn sanitize_legacy_items(db: &Connection) -> Result<usize> {
+    let legacy = items::read_all_without_raw_description(db)?;
+    for (id, description) in &legacy {
+        items::update_description(db, *id, &sanitize(description, None), description)?;
+    }
+
+    Ok(legacy.len())
+}
+
+fn remove_tracking_pixels(html_content: &str) -> String {
+    let mut fragment = Html::parse_fragment(html_content);
+    let selector = Selector::parse("img").unwrap();
+
+    let pixels = fragment
+        .select(&selector)
+        .filter(is_tracking_pixel)
+        .map(|x| x.id())
+        .collect::<Vec<_>>();
+
+    if pixels.is_empty() {
+        return html_content.to_string();
+    }
+
+    for id in pixels {
+        if let Some(mut node) = fragment.tree.get_mut(id) {
+            node.detach();
+        }
+    }
+
+    fragment.root_element().inner_html()
+}
+
+fn is_tracking_pixel(img: &ElementRef) -> bool {
+    let size = |name: &str| {
+        img.value()
+            .attr(name)
+            .and_then(|x| x.trim().trim_end_matches("px").parse::<u32>().ok())
+    };
+
+    let is_tiny = matches!((size("width"), size("height")), (Some(w), Some(h)) if w <= 1 && h <= 1);
+    let is_tracker = img.value().attr("src").is_some_and(|x| {
+        TRACKERS_REGEX
+            .get_or_init(|| Regex::new(TRACKERS).unwrap())
+            .is_match(x)
+    });
+
+    is_tiny || is_tracker
+}
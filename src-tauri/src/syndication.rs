@@ -1,8 +1,12 @@
 use chrono::{DateTime, FixedOffset, Utc};
+use reqwest::header::CONTENT_TYPE;
 use scraper::{Html, Selector};
 use std::str::FromStr;
 
+use crate::charset;
+use crate::date;
 use crate::error::{Error, Result};
+use crate::http::HttpClient;
 
 #[cfg_attr(test, derive(Debug, PartialEq))]
 pub struct RawItem {
@@ -11,6 +15,19 @@ pub struct RawItem {
     pub link: Option<String>,
     pub content: Option<String>,
     pub published_at: Option<DateTime<FixedOffset>>,
+    pub thumbnail: Option<String>,
+}
+
+#[derive(Default)]
+#[cfg_attr(test, derive(Debug, PartialEq))]
+pub struct RawFeed {
+    pub site_link: Option<String>,
+    pub description: Option<String>,
+    pub language: Option<String>,
+    pub image: Option<String>,
+    pub self_link: Option<String>,
+    pub new_feed_url: Option<String>,
+    pub redirected_to: Option<String>,
 }
 
 pub fn find_feed_link(html_content: &str) -> Result<Option<String>> {
@@ -28,18 +45,97 @@ pub fn find_feed_link(html_content: &str) -> Result<Option<String>> {
     Ok(None)
 }
 
-pub fn fetch_feed_title(link: &str, proxy: Option<&str>) -> Result<String> {
-    let content = fetch_content(link, proxy)?;
+/// Finds the feed at `link`, which is either the feed itself or a page linking to it,
+/// and returns the link and the title of the feed.
+pub fn discover_feed(link: &str, client: &HttpClient) -> Result<(String, String)> {
+    if link.is_empty() {
+        return Err(Error::EmptyString);
+    }
+
+    let html_content = fetch_content(link, client)?;
+    let link = if html_content.parse::<Feed>().is_ok() {
+        link.to_string()
+    } else if let Some(feed_link) = find_feed_link(&html_content)? {
+        feed_link
+    } else {
+        return Err(Error::InvalidFeedLink(link.to_string()));
+    };
+
+    let title = fetch_feed_title(&link, client)?;
+    Ok((link, title))
+}
+
+pub fn fetch_feed_title(link: &str, client: &HttpClient) -> Result<String> {
+    let content = fetch_content(link, client)?;
     match content.parse::<Feed>()? {
         Feed::Atom(atom) => Ok(atom.title().to_string()),
         Feed::RSS(rss) => Ok(rss.title().to_string()),
     }
 }
 
-pub fn fetch_feed_items(link: &str, proxy: Option<&str>) -> Result<Vec<RawItem>> {
-    let content = fetch_content(link, proxy)?;
-    match content.parse::<Feed>()? {
-        Feed::Atom(atom) => Ok(atom
+pub fn fetch_feed(link: &str, client: &HttpClient) -> Result<(RawFeed, Vec<RawItem>)> {
+    let content = fetch(link, client)?;
+    let feed = content.body.parse::<Feed>()?;
+    let metadata = RawFeed {
+        redirected_to: content.redirected_to,
+        ..feed_metadata(&feed)
+    };
+
+    Ok((metadata, feed_items(&feed)))
+}
+
+pub fn fetch_feed_items(link: &str, client: &HttpClient) -> Result<Vec<RawItem>> {
+    Ok(fetch_feed(link, client)?.1)
+}
+
+fn feed_metadata(feed: &Feed) -> RawFeed {
+    let non_empty = |x: &str| Some(x.trim().to_string()).filter(|x| !x.is_empty());
+
+    match feed {
+        Feed::Atom(atom) => RawFeed {
+            site_link: atom
+                .links()
+                .iter()
+                .find(|x| x.rel() == "alternate")
+                .and_then(|x| non_empty(x.href())),
+            description: atom.subtitle().and_then(|x| non_empty(&x.value)),
+            language: atom.lang().and_then(non_empty),
+            image: atom.logo().or(atom.icon()).and_then(non_empty),
+            self_link: atom
+                .links()
+                .iter()
+                .find(|x| x.rel() == "self")
+                .and_then(|x| non_empty(x.href())),
+            new_feed_url: None,
+            redirected_to: None,
+        },
+        Feed::RSS(rss) => RawFeed {
+            site_link: non_empty(rss.link()),
+            description: non_empty(rss.description()),
+            language: rss.language().and_then(non_empty),
+            image: rss.image().and_then(|x| non_empty(x.url())),
+            self_link: rss
+                .extensions()
+                .get("atom")
+                .and_then(|x| x.get("link"))
+                .and_then(|x| {
+                    x.iter()
+                        .find(|x| x.attrs().get("rel").is_some_and(|x| x == "self"))
+                })
+                .and_then(|x| x.attrs().get("href"))
+                .and_then(|x| non_empty(x)),
+            new_feed_url: rss
+                .itunes_ext()
+                .and_then(|x| x.new_feed_url())
+                .and_then(non_empty),
+            redirected_to: None,
+        },
+    }
+}
+
+fn feed_items(feed: &Feed) -> Vec<RawItem> {
+    match feed {
+        Feed::Atom(atom) => atom
             .entries()
             .iter()
             .map(|x| RawItem {
@@ -54,16 +150,29 @@ pub fn fetch_feed_items(link: &str, proxy: Option<&str>) -> Result<Vec<RawItem>>
                 link: x.links().first().map(|x| x.href().to_string()),
                 content: x
                     .content()
-                    .map(atom_syndication::Content::value)
-                    .filter(std::option::Option::is_some)
-                    .map(|x| x.unwrap().to_string()),
+                    .and_then(atom_syndication::Content::value)
+                    .map(str::to_string),
                 published_at: x
                     .published()
                     .or(Some(x.updated()))
                     .map(|x| x.with_timezone(&Utc).fixed_offset()),
+                thumbnail: x
+                    .extensions()
+                    .get("media")
+                    .and_then(|x| x.get("thumbnail"))
+                    .and_then(|x| x.first())
+                    .and_then(|x| x.attrs().get("url").cloned())
+                    .or(x
+                        .links()
+                        .iter()
+                        .find(|x| {
+                            x.rel() == "enclosure"
+                                && x.mime_type().is_some_and(|x| x.starts_with("image/"))
+                        })
+                        .map(|x| x.href().to_string())),
             })
-            .collect()),
-        Feed::RSS(rss) => Ok(rss
+            .collect(),
+        Feed::RSS(rss) => rss
             .items()
             .iter()
             .map(|x| RawItem {
@@ -76,38 +185,75 @@ pub fn fetch_feed_items(link: &str, proxy: Option<&str>) -> Result<Vec<RawItem>>
                 content: x.description().map(std::string::ToString::to_string),
                 published_at: x
                     .pub_date()
-                    .map(|x| {
-                        DateTime::parse_from_rfc2822(x)
-                            .map(|x| x.with_timezone(&Utc).fixed_offset())
+                    .and_then(date::parse)
+                    .or_else(|| {
+                        x.dublin_core_ext()
+                            .and_then(|x| x.dates().first())
+                            .and_then(|x| date::parse(x))
                     })
-                    .filter(std::result::Result::is_ok)
-                    .map(std::result::Result::unwrap),
+                    .map(|x| x.with_timezone(&Utc).fixed_offset()),
+                thumbnail: x
+                    .extensions()
+                    .get("media")
+                    .and_then(|x| x.get("thumbnail"))
+                    .and_then(|x| x.first())
+                    .and_then(|x| x.attrs().get("url").cloned())
+                    .or(x
+                        .enclosure()
+                        .filter(|x| x.mime_type().starts_with("image/"))
+                        .map(|x| x.url().to_string())),
             })
-            .collect()),
+            .collect(),
     }
 }
 
+pub struct Content {
+    pub body: String,
+    pub redirected_to: Option<String>,
+}
+
+pub fn fetch_content(link: &str, client: &HttpClient) -> Result<String> {
+    Ok(fetch(link, client)?.body)
+}
+
+// Tests read local fixtures instead of fetching, except from mock servers.
 #[cfg(test)]
-pub fn fetch_content(link: &str, _proxy: Option<&str>) -> Result<String> {
-    use std::fs;
-    Ok(fs::read_to_string(link)?)
+pub fn fetch(link: &str, client: &HttpClient) -> Result<Content> {
+    if link.starts_with("http") {
+        return request(link, client);
+    }
+
+    Ok(Content {
+        body: charset::decode(&std::fs::read(link)?, None),
+        redirected_to: None,
+    })
 }
 
 #[cfg(not(test))]
-pub fn fetch_content(link: &str, proxy: Option<&str>) -> Result<String> {
-    let client = if let Some(proxy_url) = proxy {
-        match reqwest::Proxy::all(proxy_url) {
-            Ok(p) => reqwest::blocking::Client::builder().proxy(p).build()?,
-            Err(_) => reqwest::blocking::Client::new(),
-        }
-    } else {
-        reqwest::blocking::Client::new()
-    };
-    Ok(client
-        .get(link)
-        .header("User-Agent", "Mozilla/5.0")
-        .send()?
-        .text()?)
+pub fn fetch(link: &str, client: &HttpClient) -> Result<Content> {
+    request(link, client)
+}
+
+fn request(link: &str, client: &HttpClient) -> Result<Content> {
+    let fetched = client.fetch(link)?;
+    let status = fetched.response.status();
+    if !status.is_success() {
+        return Err(Error::FeedUnreachable {
+            status: status.as_u16(),
+        });
+    }
+
+    let content_type = fetched
+        .response
+        .headers()
+        .get(CONTENT_TYPE)
+        .and_then(|x| x.to_str().ok())
+        .map(String::from);
+
+    Ok(Content {
+        body: charset::decode(&fetched.response.bytes()?, content_type.as_deref()),
+        redirected_to: fetched.redirected_to,
+    })
 }
 
 // borrowed from https://github.com/rust-syndication/syndication
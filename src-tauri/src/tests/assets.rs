@@ -0,0 +1,145 @@
+use pretty_assertions::assert_eq;
+use std::fs;
+use std::path::PathBuf;
+use std::sync::Mutex;
+use tiny_http::{Header, Response};
+
+use super::helpers;
+use crate::assets;
+use crate::models::{
+    assets as asset_models,
+    items::{self, ItemToCreate},
+    settings::{self, SettingKey, SettingToUpdate},
+};
+use crate::DbState;
+
+const PNG: &[u8] = b"\x89PNG\r\n\x1a\nfake";
+
+fn mock() -> String {
+    helpers::mock(|request| {
+        if request.url().ends_with(".png") {
+            Response::from_data(PNG)
+                .with_header(Header::from_bytes("Content-Type", "image/png").unwrap())
+                .boxed()
+        } else {
+            Response::from_string("<html></html>")
+                .with_header(Header::from_bytes("Content-Type", "text/html").unwrap())
+                .boxed()
+        }
+    })
+}
+
+fn dir(name: &str) -> PathBuf {
+    let dir = std::env::temp_dir().join(format!("collie-assets-{}-{}", name, std::process::id()));
+    let _ = fs::remove_dir_all(&dir);
+    dir
+}
+
+fn arg_description(url: &str) -> String {
+    format!(
+        r#"<p><img src="{0}/a.png"> <img src="{0}/page"> <img src="{0}/a.png"></p>"#,
+        url
+    )
+}
+
+fn pictures(url: &str) -> ItemToCreate {
+    ItemToCreate {
+        description: arg_description(url),
+        thumbnail: Some(format!("{}/thumb.png", url)),
+        ..helpers::item("Pictures")
+    }
+}
+
+#[test]
+fn cache_item_assets() {
+    let db = helpers::database();
+    let url = mock();
+    let dir = dir("cache");
+    let arg = pictures(&url);
+    items::create(&db, &arg).unwrap();
+
+    assert_eq!(
+        assets::cache_items(&db, &dir, &[arg], &helpers::client()).unwrap(),
+        2
+    );
+
+    let item = items::read(&db, 4).unwrap().unwrap();
+    let cached = asset_models::read_all(&db).unwrap();
+    assert_eq!(cached.len(), 2);
+    assert_eq!(
+        item.description,
+        format!(
+            r#"<p><img src="{0}"> <img src="{1}/page"> <img src="{0}"></p>"#,
+            assets::asset_url(&cached[0].path),
+            url
+        )
+    );
+    assert_eq!(item.thumbnail, Some(assets::asset_url(&cached[1].path)));
+
+    // Readers outside the app get the original URLs.
+    let mut restored = [items::read(&db, 4).unwrap().unwrap()];
+    assets::restore(&db, &mut restored).unwrap();
+    assert_eq!(restored[0].description, arg_description(&url));
+    assert_eq!(restored[0].thumbnail, Some(format!("{}/thumb.png", url)));
+
+    let state = DbState { db: Mutex::new(db) };
+    let (mime_type, bytes) =
+        assets::read(&state, &dir, &item.thumbnail.unwrap(), &helpers::client())
+            .unwrap()
+            .unwrap();
+    assert_eq!(mime_type, "image/png");
+    assert_eq!(bytes, PNG);
+
+    assert_eq!(
+        assets::read(
+            &state,
+            &dir,
+            "collie-asset://localhost/../collie.db",
+            &helpers::client()
+        )
+        .unwrap(),
+        None
+    );
+
+    fs::remove_dir_all(&dir).unwrap();
+}
+
+#[test]
+fn evict_assets() {
+    let db = helpers::database();
+    let url = mock();
+    let dir = dir("evict");
+    let arg = pictures(&url);
+    items::create(&db, &arg).unwrap();
+    assets::cache_items(&db, &dir, &[arg], &helpers::client()).unwrap();
+    fs::write(dir.join("unreferenced.png"), PNG).unwrap();
+
+    assert_eq!(assets::evict(&db, &dir).unwrap(), 1);
+    assert_eq!(fs::read_dir(&dir).unwrap().count(), 2);
+
+    settings::update(
+        &db,
+        &SettingToUpdate {
+            key: SettingKey::AssetCacheSize,
+            value: "0".to_string(),
+        },
+    )
+    .unwrap();
+    assert_eq!(assets::evict(&db, &dir).unwrap(), 2);
+    assert_eq!(fs::read_dir(&dir).unwrap().count(), 0);
+
+    // Evicted files are fetched from the original URL.
+    let path = &asset_models::read_all(&db).unwrap()[0].path;
+    let state = DbState { db: Mutex::new(db) };
+    let (_, bytes) = assets::read(&state, &dir, &assets::asset_url(path), &helpers::client())
+        .unwrap()
+        .unwrap();
+    assert_eq!(bytes, PNG);
+
+    let db = state.lock();
+    db.execute("DELETE FROM items WHERE id = 4", []).unwrap();
+    assets::evict(&db, &dir).unwrap();
+    assert!(asset_models::read_all(&db).unwrap().is_empty());
+
+    fs::remove_dir_all(&dir).unwrap();
+}
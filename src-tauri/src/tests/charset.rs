@@ -0,0 +1,71 @@
+use encoding_rs::{Encoding, EUC_KR, SHIFT_JIS, WINDOWS_1252};
+use pretty_assertions::assert_eq;
+
+use crate::charset::decode;
+
+fn xml(encoding: &str) -> String {
+    format!(
+        r#"<?xml version="1.0" encoding="{}"?><title>콜리</title>"#,
+        encoding
+    )
+}
+
+fn encode(encoding: &'static Encoding, text: &str) -> Vec<u8> {
+    encoding.encode(text).0.into_owned()
+}
+
+#[test]
+fn decode_with_bom() {
+    let mut bytes = vec![0xEF, 0xBB, 0xBF];
+    bytes.extend(xml("EUC-KR").as_bytes());
+    assert_eq!(
+        decode(&bytes, Some("text/xml; charset=ISO-8859-1")),
+        xml("UTF-8")
+    );
+
+    let mut bytes = vec![0xFF, 0xFE];
+    bytes.extend("콜리".encode_utf16().flat_map(u16::to_le_bytes));
+    assert_eq!(decode(&bytes, None), "콜리");
+}
+
+#[test]
+fn decode_with_content_type() {
+    let bytes = encode(EUC_KR, &xml("Shift_JIS"));
+    assert_eq!(
+        decode(&bytes, Some(r#"application/rss+xml; charset="euc-kr""#)),
+        xml("UTF-8")
+    );
+}
+
+#[test]
+fn decode_with_xml_declaration() {
+    let bytes = encode(EUC_KR, &xml("EUC-KR"));
+    assert_eq!(decode(&bytes, Some("application/rss+xml")), xml("UTF-8"));
+
+    let bytes = encode(
+        SHIFT_JIS,
+        r#"<?xml version='1.0' encoding='Shift_JIS'?>コリー"#,
+    );
+    assert_eq!(
+        decode(&bytes, None),
+        r#"<?xml version='1.0' encoding='UTF-8'?>コリー"#
+    );
+}
+
+#[test]
+fn decode_with_meta_charset() {
+    let html = r#"<html><head><meta charset="windows-1252"></head><body>Café</body></html>"#;
+    let bytes = encode(WINDOWS_1252, html);
+    assert_eq!(decode(&bytes, Some("text/html")), html);
+
+    let html = r#"<meta http-equiv="Content-Type" content="text/html; charset=euc-kr">콜리"#;
+    let bytes = encode(EUC_KR, html);
+    assert_eq!(decode(&bytes, None), html);
+}
+
+#[test]
+fn decode_as_utf8_by_default() {
+    assert_eq!(decode("콜리".as_bytes(), None), "콜리");
+    assert_eq!(decode(xml("UTF-16").as_bytes(), None), xml("UTF-8"));
+    assert_eq!(decode(xml("unknown").as_bytes(), None), xml("UTF-8"));
+}
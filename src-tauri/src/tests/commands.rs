@@ -0,0 +1,271 @@
+use std::ops::ControlFlow;
+use std::sync::Mutex;
+use std::thread;
+use tiny_http::Response;
+
+use super::helpers;
+use crate::error::Error;
+use crate::extractor::extract_item_content;
+use crate::models::feeds::{self, FeedToUpdate};
+use crate::models::items::{self, ItemReadOption, ItemStatus, ItemToUpdate};
+use crate::models::settings::{self, SettingKey, SettingToUpdate};
+use crate::models::webhooks::{self, WebhookFormat, WebhookToCreate, WebhookToUpdate};
+use crate::producer::create_new_items;
+use crate::syndication::discover_feed;
+use crate::DbState;
+
+fn mock() -> String {
+    helpers::mock(|request| match request.url() {
+        "/page" => Response::from_string("<html><head></head></html>").boxed(),
+        "/not-found" => Response::from_string("Not Found")
+            .with_status_code(404)
+            .boxed(),
+        _ => Response::from_string("not a feed").boxed(),
+    })
+}
+
+fn webhook() -> WebhookToCreate {
+    WebhookToCreate {
+        url: "http://127.0.0.1:1/hook".to_string(),
+        feed: None,
+        filter: None,
+        format: WebhookFormat::Json,
+        template: None,
+    }
+}
+
+#[test]
+fn create_feed_with_bad_links() {
+    let base = mock();
+    let client = helpers::client();
+
+    assert!(matches!(
+        discover_feed("", &client),
+        Err(Error::EmptyString)
+    ));
+    assert!(matches!(
+        discover_feed("http://", &client),
+        Err(Error::InvalidValue(_))
+    ));
+    assert!(matches!(
+        discover_feed("http://127.0.0.1:1/feed", &client),
+        Err(Error::ReqwestError { .. })
+    ));
+    assert!(matches!(
+        discover_feed(&format!("{}/not-found", base), &client),
+        Err(Error::FeedUnreachable { status: 404 })
+    ));
+    assert!(matches!(
+        discover_feed(&format!("{}/page", base), &client),
+        Err(Error::InvalidFeedLink(_))
+    ));
+}
+
+#[test]
+fn read_corrupt_rows() {
+    let db = helpers::database();
+    webhooks::create(&db, &webhook()).unwrap();
+
+    // Rows written by an older or newer version may not pass today's checks.
+    db.execute("PRAGMA ignore_check_constraints = ON", [])
+        .unwrap();
+    for sql in [
+        "UPDATE feeds SET status = 'bogus'",
+        "UPDATE items SET status = 'bogus'",
+        "UPDATE webhooks SET format = 'bogus'",
+        "INSERT INTO settings (key, value) VALUES ('bogus', '')",
+    ] {
+        db.execute(sql, []).unwrap();
+    }
+
+    assert!(feeds::read_all(&db).is_err());
+    assert!(feeds::read(&db, 1).is_err());
+    assert!(items::read_all(&db, &ItemReadOption::default()).is_err());
+    assert!(items::read(&db, 1).is_err());
+    assert!(webhooks::read_all(&db).is_err());
+    assert!(settings::read_all(&db).is_err());
+}
+
+#[test]
+fn update_with_bad_input() {
+    let db = helpers::database();
+
+    assert!(matches!(
+        settings::update(
+            &db,
+            &SettingToUpdate {
+                key: SettingKey::PollingFrequency,
+                value: "often".to_string(),
+            },
+        ),
+        Err(Error::InvalidSetting { .. })
+    ));
+    assert!(matches!(
+        feeds::update(
+            &db,
+            &FeedToUpdate {
+                id: 1,
+                title: None,
+                link: None,
+                status: None,
+                checked_at: None,
+                fetch_old_items: None,
+                fetch_full_content: None,
+                user_agent: None,
+                credentials: None,
+                headers: None,
+                proxy: None,
+                notification: None,
+                notification_filter: Some("(".to_string()),
+            },
+        ),
+        Err(Error::InvalidValue(_))
+    ));
+    assert!(matches!(
+        feeds::update(
+            &db,
+            &FeedToUpdate {
+                id: 1,
+                title: None,
+                link: None,
+                status: None,
+                checked_at: None,
+                fetch_old_items: None,
+                fetch_full_content: None,
+                user_agent: None,
+                credentials: None,
+                headers: None,
+                proxy: None,
+                notification: None,
+                notification_filter: None,
+            },
+        ),
+        Err(Error::NothingToUpdate)
+    ));
+
+    // The enum is not an input of the command, so it is not reported as the field.
+    assert_eq!("bogus".parse::<ItemStatus>().unwrap_err().field(), None);
+}
+
+#[test]
+fn update_missing_rows() {
+    let db = helpers::database();
+    webhooks::create(&db, &webhook()).unwrap();
+
+    // update_item
+    assert!(matches!(
+        items::update(
+            &db,
+            &ItemToUpdate {
+                id: 404,
+                status: Some(ItemStatus::Read),
+                is_saved: None,
+            },
+        ),
+        Err(Error::ItemNotFound(404))
+    ));
+    // update_items
+    assert!(items::check_exist(&db, &[1, 2, 3]).is_ok());
+    assert!(matches!(
+        items::check_exist(&db, &[1, 404, 405]),
+        Err(Error::ItemNotFound(404))
+    ));
+    // update_webhook
+    assert!(matches!(
+        webhooks::update(
+            &db,
+            &WebhookToUpdate {
+                id: 404,
+                url: Some("http://127.0.0.1:1/other".to_string()),
+                feed: None,
+                filter: None,
+                format: None,
+                template: None,
+            },
+        ),
+        Err(Error::WebhookNotFound(404))
+    ));
+    // delete_webhook
+    assert!(matches!(
+        webhooks::delete(&db, 404),
+        Err(Error::WebhookNotFound(404))
+    ));
+    // update_feed
+    assert!(matches!(
+        feeds::update(
+            &db,
+            &FeedToUpdate {
+                id: 404,
+                title: Some("Missing".to_string()),
+                link: None,
+                status: None,
+                checked_at: None,
+                fetch_old_items: None,
+                fetch_full_content: None,
+                user_agent: None,
+                credentials: None,
+                headers: None,
+                proxy: None,
+                notification: None,
+                notification_filter: None,
+            },
+        ),
+        Err(Error::FeedNotFound(404))
+    ));
+    // delete_feed
+    assert!(matches!(
+        feeds::delete(&db, 404),
+        Err(Error::FeedNotFound(404))
+    ));
+    // reset_setting
+    assert!(matches!(
+        settings::reset(&db, &SettingKey::DbSchemeVersion),
+        Err(Error::Forbidden)
+    ));
+    // refresh_feed reports a feed without an outcome as not found.
+    let refreshed = create_new_items(&db, &helpers::client(), Some(404), |_| {
+        ControlFlow::Continue(())
+    });
+    assert!(refreshed.outcomes.is_empty());
+
+    // Nothing was changed along the way.
+    assert_eq!(webhooks::read_all(&db).unwrap().len(), 1);
+    assert_eq!(feeds::read_all(&db).unwrap().len(), 1);
+    assert_eq!(
+        items::count_all(
+            &db,
+            &ItemReadOption {
+                status: Some(ItemStatus::Unread),
+                ..Default::default()
+            },
+        )
+        .unwrap(),
+        3
+    );
+
+    // extract_item_content
+    let state = DbState { db: Mutex::new(db) };
+    assert!(matches!(
+        extract_item_content(&state, 404, &helpers::client()),
+        Err(Error::ItemNotFound(404))
+    ));
+}
+
+#[test]
+fn recover_poisoned_database_lock() {
+    let state = DbState {
+        db: Mutex::new(helpers::database()),
+    };
+
+    thread::scope(|scope| {
+        let _ = scope
+            .spawn(|| {
+                let _db = state.lock();
+                panic!("command failed");
+            })
+            .join();
+    });
+
+    assert!(state.db.is_poisoned());
+    assert!(feeds::read_all(&state.lock()).is_ok());
+}
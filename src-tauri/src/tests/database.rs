@@ -0,0 +1,90 @@
+use pretty_assertions::assert_eq;
+use rusqlite::Connection;
+
+use super::helpers;
+use crate::models::{
+    assets::{self, AssetToCreate},
+    database,
+    feeds::{self, FeedMoveReason, FeedToMove},
+    items::{self, ItemReadOption},
+    webhooks::{self, WebhookDeliveryToCreate, WebhookFormat, WebhookToCreate},
+};
+
+fn fill(db: &Connection) {
+    webhooks::create(
+        db,
+        &WebhookToCreate {
+            url: "http://127.0.0.1/hook".to_string(),
+            feed: Some(1),
+            filter: None,
+            format: WebhookFormat::Json,
+            template: None,
+        },
+    )
+    .unwrap();
+    webhooks::create_delivery(
+        db,
+        &WebhookDeliveryToCreate {
+            webhook: 1,
+            link: "https://example.com/1".to_string(),
+            status_code: Some(200),
+            error: None,
+            attempts: 1,
+        },
+    )
+    .unwrap();
+    feeds::relocate(
+        db,
+        &FeedToMove {
+            id: 1,
+            old_link: "https://hnrss.org/frontpage".to_string(),
+            new_link: "https://hnrss.org/newest".to_string(),
+            reason: FeedMoveReason::SelfLink,
+        },
+    )
+    .unwrap();
+    assets::create(
+        db,
+        &AssetToCreate {
+            item: 1,
+            url: "https://example.com/a.png".to_string(),
+            path: "a.png".to_string(),
+        },
+    )
+    .unwrap();
+}
+
+fn assert_emptied(db: &Connection) {
+    assert_eq!(items::count_all(db, &ItemReadOption::default()).unwrap(), 0);
+    assert!(assets::read_all(db).unwrap().is_empty());
+    assert!(webhooks::read_all(db).unwrap().is_empty());
+    assert!(webhooks::read_all_deliveries(db, 1, None)
+        .unwrap()
+        .is_empty());
+    assert!(feeds::read_histories(db, 1).unwrap().is_empty());
+}
+
+#[test]
+fn delete_feed_cascades() {
+    let db = helpers::database();
+    fill(&db);
+
+    feeds::delete(&db, 1).unwrap();
+
+    assert_emptied(&db);
+}
+
+#[test]
+fn migrate_deletes_orphans() {
+    let db = helpers::database();
+    fill(&db);
+
+    // As before foreign keys were enforced.
+    db.pragma_update(None, "foreign_keys", false).unwrap();
+    feeds::delete(&db, 1).unwrap();
+    db.pragma_update(None, "foreign_keys", true).unwrap();
+
+    database::migrate(&db).unwrap();
+
+    assert_emptied(&db);
+}
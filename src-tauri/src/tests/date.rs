@@ -0,0 +1,101 @@
+use pretty_assertions::assert_eq;
+
+use crate::date;
+
+#[test]
+fn parse_real_world_dates() {
+    let samples = [
+        // RFC 2822 and RFC 3339
+        (
+            "Tue, 10 Oct 2023 12:34:56 +0000",
+            "2023-10-10T12:34:56+00:00",
+        ),
+        ("Tue, 10 Oct 2023 12:34:56 GMT", "2023-10-10T12:34:56+00:00"),
+        ("2023-10-10T12:34:56Z", "2023-10-10T12:34:56+00:00"),
+        (
+            "2023-10-10T21:34:56.789+09:00",
+            "2023-10-10T21:34:56.789+09:00",
+        ),
+        // Missing or wrong weekday
+        ("10 Oct 2023 12:34:56 +0000", "2023-10-10T12:34:56+00:00"),
+        (
+            "Mon, 10 Oct 2023 12:34:56 +0000",
+            "2023-10-10T12:34:56+00:00",
+        ),
+        (
+            "Tuesday, 10 October 2023 12:34:56 +0000",
+            "2023-10-10T12:34:56+00:00",
+        ),
+        ("Tue 10 Oct 2023 12:34 +0000", "2023-10-10T12:34:00+00:00"),
+        // Non-English weekdays
+        (
+            "Di, 10 Oct 2023 12:34:56 +0200",
+            "2023-10-10T12:34:56+02:00",
+        ),
+        (
+            "mar., 10 Oct 2023 12:34:56 +0200",
+            "2023-10-10T12:34:56+02:00",
+        ),
+        (
+            "화, 10 Oct 2023 12:34:56 +0900",
+            "2023-10-10T12:34:56+09:00",
+        ),
+        // Two-digit years
+        ("Tue, 10 Oct 23 12:34:56 +0000", "2023-10-10T12:34:56+00:00"),
+        ("Sun, 10 Oct 99 12:34:56 +0000", "1999-10-10T12:34:56+00:00"),
+        // Named and unusual timezones
+        ("Tue, 10 Oct 2023 05:34:56 PST", "2023-10-10T05:34:56-08:00"),
+        (
+            "Tue, 10 Oct 2023 14:34:56 CEST",
+            "2023-10-10T14:34:56+02:00",
+        ),
+        ("Tue, 10 Oct 2023 21:34:56 KST", "2023-10-10T21:34:56+09:00"),
+        ("Tue, 10 Oct 2023 12:34:56 UTC", "2023-10-10T12:34:56+00:00"),
+        (
+            "Tue, 10 Oct 2023 21:34:56 +09:00",
+            "2023-10-10T21:34:56+09:00",
+        ),
+        (
+            "Tue Oct 10 2023 21:34:56 GMT+0900 (Korean Standard Time)",
+            "2023-10-10T21:34:56+09:00",
+        ),
+        // ISO 8601 without timezone, assumed to be UTC
+        ("2023-10-10T12:34:56", "2023-10-10T12:34:56+00:00"),
+        ("2023-10-10 12:34:56", "2023-10-10T12:34:56+00:00"),
+        ("2023-10-10 12:34", "2023-10-10T12:34:00+00:00"),
+        ("2023-10-10", "2023-10-10T00:00:00+00:00"),
+        // Other shapes
+        (
+            "October 10, 2023 12:34:56 +0000",
+            "2023-10-10T12:34:56+00:00",
+        ),
+        (
+            "Tue, 10 Sept 2023 12:34:56 +0000",
+            "2023-09-10T12:34:56+00:00",
+        ),
+        (
+            "  Tue,  10 Oct 2023 12:34:56 +0000  ",
+            "2023-10-10T12:34:56+00:00",
+        ),
+        ("10 Oct 2023", "2023-10-10T00:00:00+00:00"),
+    ];
+
+    for (sample, expected) in samples {
+        assert_eq!(
+            (sample, date::parse(sample).map(|x| x.to_rfc3339())),
+            (sample, Some(expected.to_string()))
+        );
+    }
+}
+
+#[test]
+fn reject_invalid_dates() {
+    for sample in [
+        "",
+        "yesterday",
+        "Tue, 32 Oct 2023 12:34:56 +0000",
+        "Tue, 10 Oct 2023 12:34:56 XYZ",
+    ] {
+        assert_eq!((sample, date::parse(sample)), (sample, None));
+    }
+}
@@ -0,0 +1,55 @@
+use pretty_assertions::assert_eq;
+
+use super::helpers;
+use crate::extractor;
+use crate::models::items;
+
+#[test]
+fn extract_main_article_body() {
+    let content =
+        extractor::fetch_full_content("src/tests/fixtures/article.html", &helpers::client())
+            .unwrap()
+            .unwrap();
+
+    assert!(content.starts_with("<h1>Why SQLite is so great for the edge</h1>"));
+    assert!(content.contains("Writes, however, need to be coordinated"));
+    assert!(!content.contains("Subscribe to the newsletter"));
+    assert!(!content.contains("Great post"));
+    assert!(!content.contains("script"));
+    assert!(!content.contains("onclick"));
+}
+
+#[test]
+fn extract_resolves_relative_links() {
+    let html_content = r#"<html><body><article>
+        <p>This is a long enough paragraph, with a comma, to count as content.</p>
+        <p><img src="/images/a.png"> And another one, also with a comma, to be sure.</p>
+        </article></body></html>"#;
+
+    let content = extractor::extract(html_content, "https://example.com/posts/1").unwrap();
+    assert!(content.contains(r#"src="https://example.com/images/a.png""#));
+}
+
+#[test]
+fn extract_nothing_from_empty_page() {
+    assert_eq!(
+        extractor::extract("<html><body><p>Hi</p></body></html>", "https://example.com"),
+        None
+    );
+}
+
+#[test]
+fn update_full_content() {
+    let db = helpers::database();
+    items::update_full_content(&db, 1, "<p>Full</p>").unwrap();
+
+    assert_eq!(
+        items::read(&db, 1)
+            .unwrap()
+            .unwrap()
+            .full_content
+            .as_deref(),
+        Some("<p>Full</p>")
+    );
+    assert_eq!(items::read(&db, 2).unwrap().unwrap().full_content, None);
+}
@@ -0,0 +1,122 @@
+use pretty_assertions::assert_eq;
+use std::fs;
+use std::path::PathBuf;
+use std::sync::Mutex;
+use tiny_http::Response;
+use url::Url;
+
+use super::helpers;
+use crate::error::Error;
+use crate::favicon;
+use crate::models::feeds::{self, FeedMetadata};
+use crate::DbState;
+
+const PNG: &[u8] = b"\x89PNG\r\n\x1a\nicon";
+
+fn mock(has_icon: bool) -> String {
+    let base = helpers::mock(move |request| match request.url() {
+        "/" => Response::from_string(
+            r#"<html><head><link rel="shortcut icon" href="/static/icon.png"></head></html>"#,
+        )
+        .boxed(),
+        "/static/icon.png" if has_icon => Response::from_data(PNG).boxed(),
+        _ => Response::from_string("Not Found")
+            .with_status_code(404)
+            .boxed(),
+    });
+
+    format!("{}/", base)
+}
+
+fn dir(name: &str) -> PathBuf {
+    let dir = std::env::temp_dir().join(format!("collie-icons-{}-{}", name, std::process::id()));
+    let _ = fs::remove_dir_all(&dir);
+    dir
+}
+
+fn set_site_link(db: &rusqlite::Connection, site_link: &str) {
+    feeds::update_metadata(
+        db,
+        1,
+        &FeedMetadata {
+            site_link: Some(site_link.to_string()),
+            description: None,
+            language: None,
+            image: None,
+            self_link: None,
+        },
+    )
+    .unwrap();
+}
+
+#[test]
+fn find_favicon_links() {
+    let html_content = r#"<html><head>
+        <link rel="apple-touch-icon" href="/touch.png">
+        <link rel="stylesheet" href="/style.css">
+        <link rel="icon" type="image/png" href="icon.png">
+        </head></html>"#;
+
+    assert_eq!(
+        favicon::find_favicon_links(
+            html_content,
+            &Url::parse("https://example.com/blog/").unwrap()
+        ),
+        vec![
+            "https://example.com/blog/icon.png".to_string(),
+            "https://example.com/touch.png".to_string(),
+        ]
+    );
+}
+
+#[test]
+fn refresh_favicons() {
+    let db = helpers::database();
+    let dir = dir("refresh");
+    set_site_link(&db, &mock(true));
+
+    assert_eq!(favicon::refresh(&db, &dir, &helpers::client()).unwrap(), 1);
+    assert_eq!(favicon::refresh(&db, &dir, &helpers::client()).unwrap(), 0);
+
+    let feed = feeds::read(&db, 1).unwrap().unwrap();
+    assert_eq!(feed.icon, Some(favicon::icon_url(1)));
+    assert_eq!(
+        favicon::read(&dir, &feed.icon.unwrap()),
+        Some(("image/png", PNG.to_vec()))
+    );
+
+    fs::remove_dir_all(&dir).unwrap();
+}
+
+#[test]
+fn refresh_favicons_without_icon() {
+    let db = helpers::database();
+    let dir = dir("missing");
+    set_site_link(&db, &mock(false));
+
+    assert_eq!(favicon::refresh(&db, &dir, &helpers::client()).unwrap(), 0);
+    assert_eq!(feeds::read(&db, 1).unwrap().unwrap().icon, None);
+    assert_eq!(favicon::read(&dir, &favicon::icon_url(1)), None);
+
+    fs::remove_dir_all(&dir).unwrap();
+}
+
+#[test]
+fn refresh_favicon_of_new_feed() {
+    let db = helpers::database();
+    let dir = dir("new");
+    set_site_link(&db, &mock(true));
+    let state = DbState { db: Mutex::new(db) };
+
+    assert!(favicon::refresh_feed(&state, &dir, &helpers::client(), 1).unwrap());
+    assert_eq!(
+        feeds::read(&state.lock(), 1).unwrap().unwrap().icon,
+        Some(favicon::icon_url(1))
+    );
+    assert!(matches!(
+        favicon::refresh_feed(&state, &dir, &helpers::client(), 404),
+        Err(Error::FeedNotFound(404))
+    ));
+
+    fs::remove_dir_all(&dir).unwrap();
+}
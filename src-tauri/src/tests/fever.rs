@@ -0,0 +1,129 @@
+use pretty_assertions::assert_eq;
+use serde_json::Value;
+use std::fs;
+
+use super::helpers;
+use crate::models::settings::{self, SettingKey, SettingToUpdate};
+
+const API_KEY: &str = "432df17aebec8f8fab29642f3b125297"; // md5("collie:secret")
+
+fn spawn() -> String {
+    format!("{}/fever/", helpers::serve(helpers::database()))
+}
+
+fn call(endpoint: &str, query: &str, api_key: &str) -> Value {
+    let body = reqwest::blocking::Client::new()
+        .post(format!("{}?api&{}", endpoint, query))
+        .form(&[("api_key", api_key)])
+        .send()
+        .unwrap()
+        .text()
+        .unwrap();
+
+    serde_json::from_str(&body).unwrap()
+}
+
+#[test]
+fn fever_rejects_invalid_api_key() {
+    let endpoint = spawn();
+    let res = call(&endpoint, "feeds", "invalid");
+
+    assert_eq!(res["api_version"], 3);
+    assert_eq!(res["auth"], 0);
+    assert_eq!(res.get("feeds"), None);
+}
+
+#[test]
+fn fever_groups_and_feeds() {
+    let endpoint = spawn();
+
+    let res = call(&endpoint, "groups", API_KEY);
+    assert_eq!(res["auth"], 1);
+    assert_eq!(res["groups"][0]["id"], 1);
+    assert_eq!(res["feeds_groups"][0]["feed_ids"], "1");
+
+    let res = call(&endpoint, "feeds", API_KEY);
+    assert_eq!(res["feeds"][0]["id"], 1);
+    assert_eq!(res["feeds"][0]["title"], "Hacker News: Front Page");
+    assert_eq!(res["feeds"][0]["url"], "https://hnrss.org/frontpage");
+}
+
+#[test]
+fn fever_items() {
+    let endpoint = spawn();
+
+    let res = call(&endpoint, "items", API_KEY);
+    assert_eq!(res["total_items"], 3);
+    assert_eq!(res["items"][0]["title"], "First");
+    assert_eq!(res["items"][0]["html"], "<p>First</p>");
+    assert_eq!(res["items"][0]["is_read"], 0);
+
+    let res = call(&endpoint, "items&since_id=1", API_KEY);
+    let titles: Vec<_> = res["items"]
+        .as_array()
+        .unwrap()
+        .iter()
+        .map(|x| x["title"].as_str().unwrap())
+        .collect();
+    assert_eq!(titles, vec!["Second", "Third"]);
+
+    let res = call(&endpoint, "items&max_id=3", API_KEY);
+    assert_eq!(res["items"][0]["id"], 2);
+
+    let res = call(&endpoint, "items&with_ids=1,3", API_KEY);
+    assert_eq!(res["items"].as_array().unwrap().len(), 2);
+}
+
+#[test]
+fn fever_mark_items() {
+    let endpoint = spawn();
+
+    let res = call(&endpoint, "unread_item_ids", API_KEY);
+    assert_eq!(res["unread_item_ids"], "1,2,3");
+
+    call(&endpoint, "mark=item&as=read&id=2", API_KEY);
+    call(&endpoint, "mark=item&as=saved&id=3", API_KEY);
+    let res = call(&endpoint, "mark=item&as=read&id=404", API_KEY);
+    assert_eq!(res["auth"], 1);
+
+    let res = call(&endpoint, "unread_item_ids&saved_item_ids", API_KEY);
+    assert_eq!(res["unread_item_ids"], "1,3");
+    assert_eq!(res["saved_item_ids"], "3");
+
+    call(
+        &endpoint,
+        "mark=feed&as=read&id=1&before=1693184400",
+        API_KEY,
+    ); // 2023-08-28T01:00:00Z
+    let res = call(&endpoint, "unread_item_ids", API_KEY);
+    assert_eq!(res["unread_item_ids"], "3");
+
+    call(
+        &endpoint,
+        "mark=group&as=read&id=0&before=1893456000",
+        API_KEY,
+    );
+    let res = call(&endpoint, "unread_item_ids", API_KEY);
+    assert_eq!(res["unread_item_ids"], "");
+}
+
+#[test]
+fn fever_mark_items_as_saved_writes_saved_items_feed() {
+    let db = helpers::database();
+    let path = std::env::temp_dir().join(format!("collie-fever-{}.atom", std::process::id()));
+    settings::update(
+        &db,
+        &SettingToUpdate {
+            key: SettingKey::SavedItemsFeedPath,
+            value: path.to_string_lossy().to_string(),
+        },
+    )
+    .unwrap();
+    let endpoint = format!("{}/fever/", helpers::serve(db));
+
+    call(&endpoint, "mark=item&as=saved&id=2", API_KEY);
+    let content = fs::read_to_string(&path).unwrap();
+    fs::remove_file(&path).unwrap();
+
+    assert!(content.contains("<title>Second</title>"));
+}
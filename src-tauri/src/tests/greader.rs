@@ -0,0 +1,123 @@
+use pretty_assertions::assert_eq;
+use reqwest::blocking::Client;
+use serde_json::Value;
+
+use super::helpers;
+
+fn login(base: &str) -> String {
+    let body = Client::new()
+        .post(format!("{}/accounts/ClientLogin", base))
+        .form(&[("Email", "collie"), ("Passwd", "secret")])
+        .send()
+        .unwrap()
+        .text()
+        .unwrap();
+
+    body.lines()
+        .find_map(|x| x.strip_prefix("Auth="))
+        .unwrap()
+        .to_string()
+}
+
+fn get(base: &str, auth: &str, path: &str) -> Value {
+    let body = Client::new()
+        .get(format!("{}/reader/api/0/{}", base, path))
+        .header("Authorization", format!("GoogleLogin auth={}", auth))
+        .send()
+        .unwrap()
+        .text()
+        .unwrap();
+
+    serde_json::from_str(&body).unwrap()
+}
+
+#[test]
+fn greader_rejects_invalid_credentials() {
+    let base = helpers::serve(helpers::database());
+
+    let res = Client::new()
+        .post(format!("{}/accounts/ClientLogin", base))
+        .form(&[("Email", "collie"), ("Passwd", "wrong")])
+        .send()
+        .unwrap();
+    assert_eq!(res.status(), 401);
+
+    let res = Client::new()
+        .get(format!("{}/reader/api/0/subscription/list", base))
+        .header("Authorization", "GoogleLogin auth=collie/invalid")
+        .send()
+        .unwrap();
+    assert_eq!(res.status(), 401);
+}
+
+#[test]
+fn greader_subscription_list() {
+    let base = helpers::serve(helpers::database());
+    let auth = login(&base);
+
+    let res = get(&base, &auth, "subscription/list?output=json");
+    assert_eq!(res["subscriptions"][0]["id"], "feed/1");
+    assert_eq!(res["subscriptions"][0]["title"], "Hacker News: Front Page");
+}
+
+#[test]
+fn greader_stream_contents() {
+    let base = helpers::serve(helpers::database());
+    let auth = login(&base);
+
+    let res = get(
+        &base,
+        &auth,
+        "stream/contents/user%2F-%2Fstate%2Fcom.google%2Freading-list?n=2",
+    );
+    let titles: Vec<_> = res["items"]
+        .as_array()
+        .unwrap()
+        .iter()
+        .map(|x| x["title"].as_str().unwrap())
+        .collect();
+    assert_eq!(titles, vec!["Third", "Second"]);
+    assert_eq!(
+        res["items"][0]["id"],
+        "tag:google.com,2005:reader/item/0000000000000003"
+    );
+    assert_eq!(res["continuation"], "1");
+
+    let res = get(&base, &auth, "stream/contents/feed%2F1?n=2&c=1");
+    assert_eq!(res["items"][0]["title"], "First");
+    assert_eq!(res.get("continuation"), None);
+}
+
+#[test]
+fn greader_edit_tag() {
+    let base = helpers::serve(helpers::database());
+    let auth = login(&base);
+
+    let res = Client::new()
+        .post(format!("{}/reader/api/0/edit-tag", base))
+        .header("Authorization", format!("GoogleLogin auth={}", auth))
+        .form(&[
+            ("i", "tag:google.com,2005:reader/item/0000000000000001"),
+            ("i", "2"),
+            ("a", "user/-/state/com.google/read"),
+            ("a", "user/-/state/com.google/starred"),
+        ])
+        .send()
+        .unwrap();
+    assert_eq!(res.text().unwrap(), "OK");
+
+    let res = get(
+        &base,
+        &auth,
+        "stream/items/ids?s=user/-/state/com.google/reading-list&xt=user/-/state/com.google/read",
+    );
+    assert_eq!(res["itemRefs"].as_array().unwrap().len(), 1);
+    assert_eq!(res["itemRefs"][0]["id"], "3");
+
+    let res = get(
+        &base,
+        &auth,
+        "stream/items/ids?s=user/-/state/com.google/starred",
+    );
+    assert_eq!(res["itemRefs"].as_array().unwrap().len(), 2);
+}
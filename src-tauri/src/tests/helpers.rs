@@ -0,0 +1,129 @@
+use chrono::DateTime;
+use rusqlite::Connection;
+use std::io::Read;
+use std::thread;
+use tiny_http::{Request, ResponseBox, Server};
+
+use crate::api::server;
+use crate::http::{HttpClient, HttpConfig};
+use crate::models::{
+    database,
+    feeds::{self, FeedToCreate},
+    items::{self, ItemStatus, ItemToCreate},
+    settings::{self, SettingKey, SettingToUpdate},
+};
+
+/// Creates an in-memory database with a feed of three items and `collie:secret` API credentials.
+pub fn database() -> Connection {
+    let db = Connection::open_in_memory().unwrap();
+    db.pragma_update(None, "foreign_keys", true).unwrap();
+    database::migrate(&db).unwrap();
+
+    settings::update(
+        &db,
+        &SettingToUpdate {
+            key: SettingKey::ApiPassword,
+            value: "secret".to_string(),
+        },
+    )
+    .unwrap();
+
+    feeds::create(
+        &db,
+        &FeedToCreate {
+            title: "Hacker News: Front Page".to_string(),
+            link: "https://hnrss.org/frontpage".to_string(),
+            fetch_old_items: true,
+            proxy: None,
+        },
+    )
+    .unwrap();
+
+    for (i, title) in ["First", "Second", "Third"].iter().enumerate() {
+        items::create(
+            &db,
+            &ItemToCreate {
+                link: format!("https://example.com/{}", i),
+                published_at: DateTime::parse_from_rfc3339(&format!(
+                    "2023-08-28T0{}:00:00+00:00",
+                    i
+                ))
+                .unwrap(),
+                ..item(title)
+            },
+        )
+        .unwrap();
+    }
+
+    db
+}
+
+/// An item of the feed in `database()` that is not stored yet. Tweak it with `..item(title)`.
+pub fn item(title: &str) -> ItemToCreate {
+    ItemToCreate {
+        author: Some("parksb".to_string()),
+        title: title.to_string(),
+        description: format!("<p>{}</p>", title),
+        link: "https://example.com/new".to_string(),
+        status: ItemStatus::Unread,
+        published_at: DateTime::parse_from_rfc3339("2023-08-29T00:00:00+00:00").unwrap(),
+        feed: 1,
+        thumbnail: None,
+        raw_description: None,
+    }
+}
+
+/// Serves the API on a random local port and returns its base URL.
+pub fn serve(db: Connection) -> String {
+    let (server, base) = listen();
+    thread::spawn(move || server::serve(&db, &server));
+    base
+}
+
+/// Answers each request with what `route` returns for it, on a random local port, and returns
+/// its base URL. The route may keep state, such as the requests seen so far.
+pub fn mock<F>(mut route: F) -> String
+where
+    F: FnMut(&mut Request) -> ResponseBox + Send + 'static,
+{
+    let (server, base) = listen();
+    thread::spawn(move || {
+        for mut request in server.incoming_requests() {
+            let response = route(&mut request);
+            let _ = request.respond(response);
+        }
+    });
+    base
+}
+
+/// The value of a request header, or an empty string if it was not sent.
+pub fn header(request: &Request, name: &'static str) -> String {
+    request
+        .headers()
+        .iter()
+        .find(|x| x.field.equiv(name))
+        .map(|x| x.value.to_string())
+        .unwrap_or_default()
+}
+
+/// The body of a request, read as text.
+pub fn body(request: &mut Request) -> String {
+    let mut content = String::new();
+    let _ = request.as_reader().read_to_string(&mut content);
+    content
+}
+
+fn listen() -> (Server, String) {
+    let server = Server::http("127.0.0.1:0").unwrap();
+    let port = server.server_addr().to_ip().unwrap().port();
+    (server, format!("http://127.0.0.1:{}", port))
+}
+
+/// An HTTP client that does not retry, so that failing requests do not slow the tests down.
+pub fn client() -> HttpClient {
+    HttpClient::new(HttpConfig {
+        retries: 0,
+        ..HttpConfig::default()
+    })
+    .unwrap()
+}
@@ -0,0 +1,470 @@
+use pretty_assertions::assert_eq;
+use reqwest::Method;
+use rusqlite::Connection;
+use std::sync::atomic::{AtomicUsize, Ordering};
+use std::sync::{Arc, Mutex};
+use std::time::Duration;
+use tiny_http::{Header, Response};
+
+use super::helpers;
+use crate::error::{Error, Result};
+use crate::extractor;
+use crate::http::{HttpClient, HttpConfig, DEFAULT_USER_AGENT};
+use crate::models::feeds::{self, Credentials, FeedToUpdate};
+use crate::models::settings::{self, SettingKey, SettingToUpdate};
+use crate::syndication::fetch_content;
+use crate::DbState;
+
+// "compressed", gzipped.
+const GZIPPED: [u8; 30] = [
+    31, 139, 8, 0, 0, 0, 0, 0, 2, 3, 75, 206, 207, 45, 40, 74, 45, 46, 78, 77, 1, 0, 30, 75, 86,
+    151, 10, 0, 0, 0,
+];
+
+fn mock(failures: usize) -> (String, Arc<AtomicUsize>) {
+    let requests = Arc::new(AtomicUsize::new(0));
+    let counter = requests.clone();
+
+    let base = helpers::mock(move |request| {
+        let count = counter.fetch_add(1, Ordering::SeqCst);
+        match request.url() {
+            "/user-agent" => Response::from_string(helpers::header(request, "User-Agent")).boxed(),
+            "/headers" => Response::from_string(format!(
+                "{}|{}|{}|{}",
+                helpers::header(request, "Authorization"),
+                helpers::header(request, "Cookie"),
+                helpers::header(request, "X-Custom"),
+                helpers::header(request, "User-Agent")
+            ))
+            .boxed(),
+            url if url.starts_with("/redirect?to=") => Response::empty(302)
+                .with_header(Header::from_bytes("Location", &url[13..]).unwrap())
+                .boxed(),
+            "/gzip" => Response::from_data(GZIPPED)
+                .with_header(Header::from_bytes("Content-Encoding", "gzip").unwrap())
+                .boxed(),
+            "/flaky" if count < failures => Response::empty(503).boxed(),
+            "/flaky" => Response::from_string("ok").boxed(),
+            _ => Response::empty(404).boxed(),
+        }
+    });
+
+    (base, requests)
+}
+
+// Answers every request itself, like a forward proxy that never reaches the origin.
+fn proxy() -> String {
+    helpers::mock(|request| Response::from_string(format!("proxied {}", request.url())).boxed())
+}
+
+fn set_proxy(db: &Connection, proxy: Option<&str>) -> Result<usize> {
+    feeds::update(
+        db,
+        &FeedToUpdate {
+            id: 1,
+            title: None,
+            link: None,
+            status: None,
+            checked_at: None,
+            fetch_old_items: None,
+            fetch_full_content: None,
+            user_agent: None,
+            credentials: None,
+            headers: None,
+            proxy: Some(proxy.map(String::from)),
+            notification: None,
+            notification_filter: None,
+        },
+    )
+}
+
+#[test]
+fn override_proxy_per_feed() {
+    let db = helpers::database();
+    let (base, _) = mock(0);
+    let proxy = proxy();
+    let text = |client: &HttpClient, link: &str| {
+        let feed = feeds::read(&db, 1).unwrap().unwrap();
+        client
+            .for_feed(&feed)
+            .unwrap()
+            .get(link)
+            .unwrap()
+            .text()
+            .unwrap()
+    };
+
+    let direct = helpers::client();
+    let proxied = HttpClient::new(HttpConfig {
+        retries: 0,
+        proxy: Some(proxy.clone()),
+        ..HttpConfig::default()
+    })
+    .unwrap();
+
+    let link = "http://collie.invalid/feed";
+    assert_eq!(text(&proxied, link), format!("proxied {}", link));
+
+    set_proxy(&db, Some(&proxy)).unwrap();
+    assert_eq!(text(&direct, link), format!("proxied {}", link));
+
+    set_proxy(&db, Some("")).unwrap();
+    let link = format!("{}/user-agent", base);
+    assert_eq!(text(&proxied, &link), DEFAULT_USER_AGENT);
+
+    set_proxy(&db, None).unwrap();
+    assert_eq!(feeds::read(&db, 1).unwrap().unwrap().proxy, None);
+    assert!(set_proxy(&db, Some("http://bad host")).is_err());
+}
+
+#[test]
+fn fetch_item_pages_through_feed_proxy() {
+    let db = helpers::database();
+    set_proxy(&db, Some(&proxy())).unwrap();
+    db.execute("UPDATE items SET link = 'http://collie.invalid/0'", [])
+        .unwrap();
+    let state = DbState { db: Mutex::new(db) };
+
+    // The page is only reachable through the proxy of the feed.
+    assert!(extractor::extract_item_content(&state, 1, &helpers::client()).is_ok());
+    assert!(extractor::fetch_full_content("http://collie.invalid/0", &helpers::client()).is_err());
+}
+
+#[test]
+fn identify_as_collie() {
+    let db = helpers::database();
+    let (base, _) = mock(0);
+    let link = format!("{}/user-agent", base);
+    let client = helpers::client();
+
+    assert_eq!(
+        client.get(&link).unwrap().text().unwrap(),
+        DEFAULT_USER_AGENT
+    );
+
+    feeds::update(
+        &db,
+        &FeedToUpdate {
+            id: 1,
+            title: None,
+            link: None,
+            status: None,
+            checked_at: None,
+            fetch_old_items: None,
+            fetch_full_content: None,
+            user_agent: Some("Mozilla/5.0".to_string()),
+            credentials: None,
+            headers: None,
+            proxy: None,
+            notification: None,
+            notification_filter: None,
+        },
+    )
+    .unwrap();
+    let feed = feeds::read(&db, 1).unwrap().unwrap();
+
+    assert_eq!(
+        client
+            .for_feed(&feed)
+            .unwrap()
+            .get(&link)
+            .unwrap()
+            .text()
+            .unwrap(),
+        "Mozilla/5.0"
+    );
+}
+
+#[test]
+fn send_feed_credentials_and_headers() {
+    let db = helpers::database();
+    let update = |credentials, headers: &[(&str, &str)]| {
+        feeds::update(
+            &db,
+            &FeedToUpdate {
+                id: 1,
+                title: None,
+                link: None,
+                status: None,
+                checked_at: None,
+                fetch_old_items: None,
+                fetch_full_content: None,
+                user_agent: None,
+                credentials: Some(credentials),
+                headers: Some(
+                    headers
+                        .iter()
+                        .map(|(k, v)| (k.to_string(), v.to_string()))
+                        .collect(),
+                ),
+                proxy: None,
+                notification: None,
+                notification_filter: None,
+            },
+        )
+    };
+    let headers = |link: &str| {
+        let feed = feeds::read(&db, 1).unwrap().unwrap();
+        helpers::client()
+            .for_feed(&feed)
+            .unwrap()
+            .get(link)
+            .unwrap()
+            .text()
+            .unwrap()
+    };
+
+    let (base, _) = mock(0);
+    let (other, _) = mock(0);
+    let link = format!("{}/headers", base);
+
+    update(
+        Some(Credentials::Basic {
+            username: "collie".to_string(),
+            password: "secret".to_string(),
+        }),
+        &[("X-Custom", "1"), ("User-Agent", "Reader/1.0")],
+    )
+    .unwrap();
+    assert_eq!(headers(&link), "Basic Y29sbGllOnNlY3JldA==||1|Reader/1.0");
+    assert_eq!(
+        headers(&format!("{}/redirect?to={}/headers", base, other)),
+        format!("|||{}", DEFAULT_USER_AGENT)
+    );
+
+    update(
+        Some(Credentials::Cookie {
+            cookie: "session=abc".to_string(),
+        }),
+        &[],
+    )
+    .unwrap();
+    assert_eq!(
+        headers(&format!("{}/redirect?to=/headers", base)),
+        format!("|session=abc||{}", DEFAULT_USER_AGENT)
+    );
+
+    update(None, &[]).unwrap();
+    assert_eq!(headers(&link), format!("|||{}", DEFAULT_USER_AGENT));
+
+    assert!(update(None, &[("Bad Header", "1")]).is_err());
+}
+
+#[test]
+fn redact_feed_credentials() {
+    let db = helpers::database();
+    let update = |credentials| {
+        feeds::update(
+            &db,
+            &FeedToUpdate {
+                id: 1,
+                title: None,
+                link: None,
+                status: None,
+                checked_at: None,
+                fetch_old_items: None,
+                fetch_full_content: None,
+                user_agent: None,
+                credentials: Some(Some(credentials)),
+                headers: None,
+                proxy: None,
+                notification: None,
+                notification_filter: None,
+            },
+        )
+        .unwrap();
+        feeds::read(&db, 1).unwrap().unwrap()
+    };
+
+    let feed = update(Credentials::Bearer {
+        token: "secret".to_string(),
+    });
+    assert_eq!(
+        feed.credentials,
+        Some(Credentials::Bearer {
+            token: "secret".to_string()
+        })
+    );
+    let redacted = serde_json::to_value(&feed).unwrap()["credentials"].clone();
+    assert_eq!(redacted, serde_json::json!({ "Bearer": { "token": "" } }));
+
+    // The redacted value sent back keeps the stored one.
+    let feed = update(serde_json::from_value(redacted).unwrap());
+    assert_eq!(
+        feed.credentials,
+        Some(Credentials::Bearer {
+            token: "secret".to_string()
+        })
+    );
+
+    // Unless the kind of credentials changed.
+    let feed = update(Credentials::Basic {
+        username: "reader".to_string(),
+        password: String::new(),
+    });
+    assert_eq!(
+        feed.credentials,
+        Some(Credentials::Basic {
+            username: "reader".to_string(),
+            password: String::new()
+        })
+    );
+}
+
+#[test]
+fn redact_feed_headers() {
+    let db = helpers::database();
+    let update = |headers: &[(&str, &str)]| {
+        feeds::update(
+            &db,
+            &FeedToUpdate {
+                id: 1,
+                title: None,
+                link: None,
+                status: None,
+                checked_at: None,
+                fetch_old_items: None,
+                fetch_full_content: None,
+                user_agent: None,
+                credentials: None,
+                headers: Some(
+                    headers
+                        .iter()
+                        .map(|(name, value)| (name.to_string(), value.to_string()))
+                        .collect(),
+                ),
+                proxy: None,
+                notification: None,
+                notification_filter: None,
+            },
+        )
+        .unwrap();
+        feeds::read(&db, 1).unwrap().unwrap()
+    };
+
+    let feed = update(&[("Authorization", "Token secret"), ("X-Custom", "1")]);
+    assert_eq!(
+        serde_json::to_value(&feed).unwrap()["headers"],
+        serde_json::json!({ "Authorization": "", "X-Custom": "" })
+    );
+
+    // The redacted values sent back keep the stored ones.
+    let feed = update(&[("Authorization", ""), ("X-Custom", "2")]);
+    assert_eq!(feed.headers["Authorization"], "Token secret");
+    assert_eq!(feed.headers["X-Custom"], "2");
+}
+
+#[test]
+fn decompress_gzip() {
+    let (base, _) = mock(0);
+    let res = helpers::client().get(&format!("{}/gzip", base)).unwrap();
+
+    assert_eq!(res.text().unwrap(), "compressed");
+}
+
+#[test]
+fn retry_transient_failures() {
+    let client = HttpClient::new(HttpConfig {
+        retries: 2,
+        ..HttpConfig::default()
+    })
+    .unwrap();
+
+    let (base, requests) = mock(2);
+    let res = client.get(&format!("{}/flaky", base)).unwrap();
+    assert_eq!(res.text().unwrap(), "ok");
+    assert_eq!(requests.load(Ordering::SeqCst), 3);
+
+    let (base, requests) = mock(3);
+    let res = client.get(&format!("{}/flaky", base)).unwrap();
+    assert_eq!(res.status().as_u16(), 503);
+    assert_eq!(requests.load(Ordering::SeqCst), 3);
+
+    let (base, requests) = mock(0);
+    let res = client.get(&format!("{}/missing", base)).unwrap();
+    assert_eq!(res.status().as_u16(), 404);
+    assert_eq!(requests.load(Ordering::SeqCst), 1);
+}
+
+#[test]
+fn send_other_requests_with_the_same_config() {
+    let client = HttpClient::new(HttpConfig {
+        retries: 2,
+        user_agent: "Reader/1.0".to_string(),
+        ..HttpConfig::default()
+    })
+    .unwrap();
+
+    let (base, _) = mock(0);
+    let res = client
+        .execute(client.prepare(Method::POST, &format!("{}/user-agent", base)))
+        .unwrap();
+    assert_eq!(res.text().unwrap(), "Reader/1.0");
+
+    let (base, requests) = mock(2);
+    let res = client
+        .execute(
+            client
+                .prepare(Method::PUT, &format!("{}/flaky", base))
+                .body("{}"),
+        )
+        .unwrap();
+    assert_eq!(res.text().unwrap(), "ok");
+    assert_eq!(requests.load(Ordering::SeqCst), 3);
+}
+
+#[test]
+fn report_unreachable_feeds() {
+    let (base, _) = mock(0);
+    let err = fetch_content(&format!("{}/missing", base), &helpers::client()).unwrap_err();
+
+    assert!(matches!(err, Error::FeedUnreachable { status: 404 }));
+    assert_eq!(
+        serde_json::to_value(&err).unwrap(),
+        serde_json::json!({
+            "code": "feed_unreachable",
+            "message": "feed is unreachable: HTTP 404",
+            "field": null,
+            "detail": { "status": 404 },
+        })
+    );
+}
+
+#[test]
+fn read_config_from_settings() {
+    let db = helpers::database();
+    assert_eq!(HttpConfig::from_settings(&db), HttpConfig::default());
+
+    let update = |key, value: &str| {
+        settings::update(
+            &db,
+            &SettingToUpdate {
+                key,
+                value: value.to_string(),
+            },
+        )
+    };
+    update(SettingKey::HttpTimeout, "5").unwrap();
+    update(SettingKey::HttpRetries, "0").unwrap();
+    update(SettingKey::UserAgent, "Reader/1.0").unwrap();
+    assert!(update(SettingKey::HttpConnectTimeout, "0").is_err());
+    assert_eq!(
+        serde_json::to_value(update(SettingKey::HttpRetries, "11").unwrap_err()).unwrap(),
+        serde_json::json!({
+            "code": "invalid_setting",
+            "message": "invalid setting `HttpRetries`: must be at most 10",
+            "field": "HttpRetries",
+            "detail": "must be at most 10",
+        })
+    );
+
+    assert_eq!(
+        HttpConfig::from_settings(&db),
+        HttpConfig {
+            timeout: Duration::from_secs(5),
+            retries: 0,
+            user_agent: "Reader/1.0".to_string(),
+            ..HttpConfig::default()
+        }
+    );
+}
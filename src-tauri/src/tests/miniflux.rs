@@ -0,0 +1,141 @@
+use pretty_assertions::assert_eq;
+use serde_json::json;
+use std::sync::{Arc, Mutex};
+use tiny_http::Response;
+
+use super::helpers;
+use crate::miniflux::{self, Miniflux};
+use crate::models::{
+    feeds,
+    items::{self, ItemReadOption, ItemStatus, ItemToUpdate},
+};
+
+fn entry(
+    id: i64,
+    feed_id: i64,
+    title: &str,
+    url: &str,
+    status: &str,
+    starred: bool,
+) -> serde_json::Value {
+    json!({
+        "id": id,
+        "feed_id": feed_id,
+        "status": status,
+        "title": title,
+        "url": url,
+        "content": format!("<p>{}</p>", title),
+        "author": "",
+        "starred": starred,
+        "published_at": "2023-08-28T00:00:00Z",
+        "changed_at": "2023-09-01T00:00:00Z",
+    })
+}
+
+fn mock() -> (String, Arc<Mutex<Vec<String>>>) {
+    let requests = Arc::new(Mutex::new(vec![]));
+    let recorded = requests.clone();
+
+    let base = helpers::mock(move |request| {
+        if helpers::header(request, "X-Auth-Token") != "token" {
+            return Response::empty(401).boxed();
+        }
+
+        let url = request.url().to_string();
+        let body = if url == "/v1/feeds" {
+            json!([
+                { "id": 7, "title": "Hacker News", "feed_url": "https://hnrss.org/frontpage" },
+                { "id": 8, "title": "Lobsters", "feed_url": "https://lobste.rs/rss" },
+            ])
+        } else if url.starts_with("/v1/entries?") {
+            json!({
+                "total": 4,
+                "entries": [
+                    entry(11, 7, "First", "https://example.com/0", "read", false),
+                    entry(12, 7, "Second", "https://example.com/1", "unread", false),
+                    entry(13, 7, "Third", "https://example.com/2", "unread", false),
+                    entry(21, 8, "Fourth", "https://lobste.rs/s/1", "unread", true),
+                ],
+            })
+        } else {
+            let content = helpers::body(request);
+            recorded.lock().unwrap().push(
+                format!("{} {} {}", request.method(), url, content)
+                    .trim()
+                    .to_string(),
+            );
+            return Response::empty(204).boxed();
+        };
+
+        Response::from_string(body.to_string()).boxed()
+    });
+
+    (base, requests)
+}
+
+#[test]
+fn miniflux_sync() {
+    let db = helpers::database();
+    let (url, requests) = mock();
+
+    items::update(
+        &db,
+        &ItemToUpdate {
+            id: 2,
+            status: Some(ItemStatus::Read),
+            is_saved: None,
+        },
+    )
+    .unwrap();
+    items::update(
+        &db,
+        &ItemToUpdate {
+            id: 3,
+            status: None,
+            is_saved: Some(true),
+        },
+    )
+    .unwrap();
+
+    let changed = miniflux::sync(&db, &Miniflux::new(&url, "token", &helpers::client())).unwrap();
+    assert_eq!(changed, 2);
+
+    let feeds = feeds::read_all(&db).unwrap();
+    assert_eq!(
+        feeds
+            .iter()
+            .map(|x| (x.title.as_str(), x.remote_id))
+            .collect::<Vec<_>>(),
+        vec![("Hacker News: Front Page", Some(7)), ("Lobsters", Some(8))]
+    );
+
+    let items = items::read_all(&db, &ItemReadOption::default()).unwrap();
+    assert_eq!(
+        items
+            .iter()
+            .map(|x| (x.title.as_str(), x.remote_id, &x.status, x.is_saved))
+            .collect::<Vec<_>>(),
+        vec![
+            ("First", Some(11), &ItemStatus::Read, false),
+            ("Second", Some(12), &ItemStatus::Read, false),
+            ("Third", Some(13), &ItemStatus::Unread, true),
+            ("Fourth", Some(21), &ItemStatus::Unread, true),
+        ]
+    );
+
+    assert_eq!(
+        *requests.lock().unwrap(),
+        vec![
+            "PUT /v1/entries/13/bookmark".to_string(),
+            r#"PUT /v1/entries {"entry_ids":[12],"status":"read"}"#.to_string(),
+        ]
+    );
+}
+
+#[test]
+fn miniflux_sync_rejects_invalid_api_key() {
+    let db = helpers::database();
+    let (url, _) = mock();
+
+    assert!(miniflux::sync(&db, &Miniflux::new(&url, "invalid", &helpers::client())).is_err());
+}
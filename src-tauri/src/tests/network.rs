@@ -0,0 +1,62 @@
+use pretty_assertions::assert_eq;
+use std::net::TcpListener;
+
+use super::helpers;
+use crate::models::feeds::{self, FeedToCreate};
+use crate::models::settings::{self, SettingKey, SettingToUpdate};
+use crate::network::{is_online, probe_targets};
+
+#[test]
+fn probe_feed_hosts_or_proxy() {
+    let db = helpers::database();
+    for (title, link, proxy) in [
+        ("Same host", "https://hnrss.org/newest", None),
+        (
+            "Proxied",
+            "https://example.com/feed",
+            Some("http://127.0.0.1:8080"),
+        ),
+        ("Direct", "http://example.com:8000/feed", Some("")),
+        ("Too many", "https://example.org/feed", None),
+    ] {
+        feeds::create(
+            &db,
+            &FeedToCreate {
+                title: title.to_string(),
+                link: link.to_string(),
+                fetch_old_items: true,
+                proxy: proxy.map(String::from),
+            },
+        )
+        .unwrap();
+    }
+
+    assert_eq!(
+        probe_targets(&db),
+        vec!["hnrss.org:443", "127.0.0.1:8080", "example.com:8000"]
+    );
+
+    settings::update(
+        &db,
+        &SettingToUpdate {
+            key: SettingKey::Proxy,
+            value: "socks5://127.0.0.1:1080".to_string(),
+        },
+    )
+    .unwrap();
+    assert_eq!(probe_targets(&db), vec!["127.0.0.1:1080"]);
+}
+
+#[test]
+fn detect_connectivity() {
+    let listener = TcpListener::bind("127.0.0.1:0").unwrap();
+    let reachable = listener.local_addr().unwrap().to_string();
+    let unreachable = {
+        let closed = TcpListener::bind("127.0.0.1:0").unwrap();
+        closed.local_addr().unwrap().to_string()
+    };
+
+    assert!(is_online(&[]));
+    assert!(is_online(&[unreachable.clone(), reachable]));
+    assert!(!is_online(&[unreachable]));
+}
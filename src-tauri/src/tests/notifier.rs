@@ -0,0 +1,191 @@
+use chrono::{DateTime, Local, TimeZone};
+use pretty_assertions::assert_eq;
+use rusqlite::Connection;
+
+use super::helpers::{self, item};
+use crate::models::feeds::{self, FeedToUpdate};
+use crate::models::items::ItemToCreate;
+use crate::models::settings::{self, SettingKey, SettingToUpdate};
+use crate::notifier::{Notifier, Toast};
+
+fn at(hour: u32, min: u32) -> DateTime<Local> {
+    Local.with_ymd_and_hms(2023, 9, 1, hour, min, 0).unwrap()
+}
+
+fn set(db: &Connection, key: SettingKey, value: &str) {
+    settings::update(
+        db,
+        &SettingToUpdate {
+            key,
+            value: value.to_string(),
+        },
+    )
+    .unwrap();
+}
+
+fn set_rules(db: &Connection, notification: bool, filter: &str) {
+    feeds::update(
+        db,
+        &FeedToUpdate {
+            id: 1,
+            title: None,
+            link: None,
+            status: None,
+            checked_at: None,
+            fetch_old_items: None,
+            fetch_full_content: None,
+            user_agent: None,
+            credentials: None,
+            headers: None,
+            proxy: None,
+            notification: Some(notification),
+            notification_filter: Some(filter.to_string()),
+        },
+    )
+    .unwrap();
+}
+
+fn titles(toasts: Vec<Toast>) -> Vec<String> {
+    toasts.into_iter().map(|x| x.title).collect()
+}
+
+#[test]
+fn notify_of_new_items() {
+    let db = helpers::database();
+    let mut notifier = Notifier::default();
+
+    assert_eq!(
+        notifier.notify(&db, &[item("Rust")], at(12, 0)),
+        vec![Toast {
+            title: "Rust".to_string(),
+            body: "Rust".to_string(),
+            item: None,
+        }]
+    );
+    assert_eq!(
+        notifier.notify(&db, &["A", "B", "C", "D"].map(item), at(12, 1)),
+        vec![Toast {
+            title: "New items arrived".to_string(),
+            body: "There are 4 items to read".to_string(),
+            item: None,
+        }]
+    );
+
+    set(&db, SettingKey::Notification, "0");
+    assert_eq!(notifier.notify(&db, &[item("Rust")], at(12, 2)), vec![]);
+}
+
+#[test]
+fn mute_and_filter_feeds() {
+    let db = helpers::database();
+    let mut notifier = Notifier::default();
+    let items = ["Rust 1.72", "Go 1.21"].map(item);
+
+    set_rules(&db, false, "");
+    assert_eq!(notifier.notify(&db, &items, at(12, 0)), vec![]);
+
+    set_rules(&db, true, "^Rust");
+    assert_eq!(
+        titles(notifier.notify(&db, &items, at(12, 0))),
+        vec!["Rust 1.72"]
+    );
+
+    set_rules(&db, true, "");
+    assert_eq!(titles(notifier.notify(&db, &items, at(12, 0))).len(), 2);
+
+    assert!(feeds::update(
+        &db,
+        &FeedToUpdate {
+            id: 1,
+            title: None,
+            link: None,
+            status: None,
+            checked_at: None,
+            fetch_old_items: None,
+            fetch_full_content: None,
+            user_agent: None,
+            credentials: None,
+            headers: None,
+            proxy: None,
+            notification: None,
+            notification_filter: Some("(".to_string()),
+        },
+    )
+    .is_err());
+}
+
+#[test]
+fn hold_items_during_quiet_hours() {
+    let db = helpers::database();
+    let mut notifier = Notifier::default();
+    set(&db, SettingKey::QuietHoursStart, "22:00");
+    set(&db, SettingKey::QuietHoursEnd, "07:00");
+    assert!(settings::update(
+        &db,
+        &SettingToUpdate {
+            key: SettingKey::QuietHoursEnd,
+            value: "7am".to_string(),
+        },
+    )
+    .is_err());
+
+    assert_eq!(
+        notifier.notify(&db, &["A", "B"].map(item), at(23, 0)),
+        vec![]
+    );
+    assert_eq!(
+        notifier.notify(&db, &["C", "D"].map(item), at(3, 0)),
+        vec![]
+    );
+    assert_eq!(notifier.flush(&db, at(6, 59)), vec![]);
+    assert_eq!(
+        notifier.flush(&db, at(7, 0)),
+        vec![Toast {
+            title: "New items arrived".to_string(),
+            body: "A, B, C and 1 more".to_string(),
+            item: None,
+        }]
+    );
+    assert_eq!(notifier.flush(&db, at(7, 1)), vec![]);
+}
+
+#[test]
+fn wait_between_notifications() {
+    let db = helpers::database();
+    let mut notifier = Notifier::default();
+    set(&db, SettingKey::NotificationInterval, "600");
+
+    assert_eq!(
+        titles(notifier.notify(&db, &[item("A")], at(12, 0))),
+        vec!["A"]
+    );
+    assert_eq!(notifier.notify(&db, &[item("B")], at(12, 5)), vec![]);
+    assert_eq!(notifier.flush(&db, at(12, 9)), vec![]);
+    assert_eq!(
+        notifier.notify(&db, &[item("C")], at(12, 10)),
+        vec![Toast {
+            title: "New items arrived".to_string(),
+            body: "B, C".to_string(),
+            item: None,
+        }]
+    );
+}
+
+#[test]
+fn link_notifications_to_items() {
+    let db = helpers::database();
+    let mut notifier = Notifier::default();
+    let first = ItemToCreate {
+        link: "https://example.com/0".to_string(),
+        ..item("First")
+    };
+
+    assert_eq!(
+        notifier
+            .notify(&db, &[first, item("Unknown")], at(12, 0))
+            .into_iter()
+            .map(|x| x.item)
+            .collect::<Vec<_>>(),
+        vec![Some(1), None]
+    );
+}
@@ -0,0 +1,235 @@
+use pretty_assertions::assert_eq;
+use std::ops::ControlFlow;
+use tiny_http::{Header, Response};
+
+use super::helpers;
+use crate::models::feeds::{self, FeedMetadata, FeedMoveReason, FeedToCreate, FeedToUpdate};
+use crate::producer::{create_new_items, FeedOutcome, Progress};
+
+fn rss(extra: &str) -> String {
+    format!(
+        r#"<?xml version="1.0" encoding="UTF-8"?>
+<rss version="2.0" xmlns:atom="http://www.w3.org/2005/Atom" xmlns:itunes="http://www.itunes.com/dtds/podcast-1.0.dtd">
+  <channel>
+    <title>Moving</title>
+    <link>https://example.com</link>
+    <description>Moving</description>
+    {}
+  </channel>
+</rss>"#,
+        extra
+    )
+}
+
+fn mock() -> String {
+    helpers::mock(|request| {
+        let new = format!("http://{}/new", helpers::header(request, "Host"));
+        let redirect = |status: u16, location: &str| {
+            Response::empty(status)
+                .with_header(Header::from_bytes("Location", location).unwrap())
+                .boxed()
+        };
+        match request.url() {
+            "/old" => redirect(301, "/older"),
+            "/older" => redirect(308, "/new"),
+            "/temporary" => redirect(302, "/new"),
+            "/temporary-then-permanent" => redirect(307, "/old"),
+            "/podcast" => Response::from_string(rss(&format!(
+                "<itunes:new-feed-url>{}</itunes:new-feed-url>",
+                new
+            )))
+            .boxed(),
+            "/broken-podcast" => Response::from_string(rss(
+                "<itunes:new-feed-url>http://127.0.0.1:1/gone</itunes:new-feed-url>",
+            ))
+            .boxed(),
+            "/self" => Response::from_string(rss(&format!(
+                r#"<atom:link href="{}" rel="self" type="application/rss+xml"/>"#,
+                new
+            )))
+            .boxed(),
+            "/items" => Response::from_string(rss(
+                "<item><title>New</title><link>https://example.com/new</link></item>",
+            ))
+            .boxed(),
+            "/broken" => Response::from_string("not a feed").boxed(),
+            _ => Response::from_string(rss("")).boxed(),
+        }
+    })
+}
+
+fn set_link(db: &rusqlite::Connection, link: String) {
+    feeds::update(
+        db,
+        &FeedToUpdate {
+            id: 1,
+            title: None,
+            link: Some(link),
+            status: None,
+            checked_at: None,
+            fetch_old_items: None,
+            fetch_full_content: None,
+            user_agent: None,
+            credentials: None,
+            headers: None,
+            proxy: None,
+            notification: None,
+            notification_filter: None,
+        },
+    )
+    .unwrap();
+}
+
+fn moved(path: &str, self_link: Option<&str>) -> (String, Vec<(String, FeedMoveReason)>) {
+    let db = helpers::database();
+    let base = mock();
+    set_link(&db, format!("{}{}", base, path));
+    feeds::update_metadata(
+        &db,
+        1,
+        &FeedMetadata {
+            site_link: None,
+            description: None,
+            language: None,
+            image: None,
+            self_link: self_link.map(|x| format!("{}{}", base, x)),
+        },
+    )
+    .unwrap();
+
+    create_new_items(&db, &helpers::client(), None, |_| ControlFlow::Continue(()));
+
+    let link = feeds::read(&db, 1).unwrap().unwrap().link;
+    let histories = feeds::read_histories(&db, 1)
+        .unwrap()
+        .into_iter()
+        .map(|x| (x.new_link.replace(&base, ""), x.reason))
+        .collect();
+
+    (link.replace(&base, ""), histories)
+}
+
+#[test]
+fn relocate_permanently_redirected_feed() {
+    assert_eq!(
+        moved("/old", None),
+        (
+            "/new".to_string(),
+            vec![("/new".to_string(), FeedMoveReason::PermanentRedirect)]
+        )
+    );
+}
+
+#[test]
+fn keep_temporarily_redirected_feed() {
+    assert_eq!(
+        moved("/temporary", None),
+        ("/temporary".to_string(), vec![])
+    );
+    assert_eq!(
+        moved("/temporary-then-permanent", None),
+        ("/temporary-then-permanent".to_string(), vec![])
+    );
+}
+
+#[test]
+fn relocate_feed_with_new_feed_url() {
+    assert_eq!(
+        moved("/podcast", None),
+        (
+            "/new".to_string(),
+            vec![("/new".to_string(), FeedMoveReason::NewFeedUrl)]
+        )
+    );
+    assert_eq!(
+        moved("/broken-podcast", None),
+        ("/broken-podcast".to_string(), vec![])
+    );
+}
+
+#[test]
+fn relocate_feed_with_changed_self_link() {
+    assert_eq!(moved("/self", None), ("/self".to_string(), vec![]));
+    assert_eq!(moved("/self", Some("/new")), ("/self".to_string(), vec![]));
+    assert_eq!(
+        moved("/self", Some("/self")),
+        (
+            "/new".to_string(),
+            vec![("/new".to_string(), FeedMoveReason::SelfLink)]
+        )
+    );
+}
+
+fn two_feeds() -> rusqlite::Connection {
+    let db = helpers::database();
+    let base = mock();
+    set_link(&db, format!("{}/broken", base));
+    feeds::create(
+        &db,
+        &FeedToCreate {
+            title: "Items".to_string(),
+            link: format!("{}/items", base),
+            fetch_old_items: true,
+            proxy: None,
+        },
+    )
+    .unwrap();
+
+    db
+}
+
+#[test]
+fn refresh_feeds_independently() {
+    let db = two_feeds();
+    let refreshed = create_new_items(&db, &helpers::client(), None, |_| ControlFlow::Continue(()));
+    assert_eq!(refreshed.inserted.len(), 1);
+    assert_eq!(
+        refreshed.outcomes,
+        vec![
+            FeedOutcome {
+                feed: 1,
+                new_items: 0,
+                error: Some("failed to parse syndication feed".to_string()),
+            },
+            FeedOutcome {
+                feed: 2,
+                new_items: 1,
+                error: None,
+            },
+        ]
+    );
+
+    let refreshed = create_new_items(&db, &helpers::client(), Some(2), |_| {
+        ControlFlow::Continue(())
+    });
+    assert_eq!(
+        refreshed.outcomes,
+        vec![FeedOutcome {
+            feed: 2,
+            new_items: 0,
+            error: None,
+        }]
+    );
+}
+
+#[test]
+fn report_refresh_progress() {
+    let db = two_feeds();
+    let mut reported = vec![];
+    let refreshed = create_new_items(&db, &helpers::client(), None, |progress| {
+        reported.push(match progress {
+            Progress::Started { feeds } => format!("started {}", feeds),
+            Progress::Fetched(outcome) => format!("fetched {}", outcome.feed),
+        });
+        ControlFlow::Continue(())
+    });
+
+    assert_eq!(refreshed.outcomes.len(), 2);
+    assert_eq!(reported, vec!["started 2", "fetched 1", "fetched 2"]);
+
+    let refreshed = create_new_items(&db, &helpers::client(), None, |progress| match progress {
+        Progress::Started { .. } => ControlFlow::Continue(()),
+        Progress::Fetched(_) => ControlFlow::Break(()),
+    });
+    assert_eq!(refreshed.outcomes.len(), 1);
+}
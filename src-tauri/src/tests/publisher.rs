@@ -0,0 +1,114 @@
+use pretty_assertions::assert_eq;
+use std::fs;
+
+use super::helpers;
+use crate::models::{
+    items::{self, ItemToUpdate},
+    settings::{self, SettingKey, SettingToUpdate},
+};
+use crate::publisher::{self, PublishFormat};
+use crate::syndication::Feed;
+
+fn save(db: &rusqlite::Connection, id: i32) {
+    items::update(
+        db,
+        &ItemToUpdate {
+            id,
+            status: None,
+            is_saved: Some(true),
+        },
+    )
+    .unwrap();
+}
+
+#[test]
+fn publish_saved_items_as_atom() {
+    let db = helpers::database();
+    save(&db, 1);
+    save(&db, 3);
+
+    let content = publisher::publish(&db, &PublishFormat::Atom, None).unwrap();
+    let Ok(Feed::Atom(feed)) = content.parse::<Feed>() else {
+        panic!("not an atom feed: {}", content);
+    };
+
+    assert_eq!(
+        feed.entries()
+            .iter()
+            .map(|x| (x.title().as_str(), x.links()[0].href()))
+            .collect::<Vec<_>>(),
+        vec![
+            ("Third", "https://example.com/2"),
+            ("First", "https://example.com/0"),
+        ]
+    );
+    assert_eq!(
+        feed.entries()[0].categories()[0].label(),
+        Some("Hacker News: Front Page")
+    );
+}
+
+#[test]
+fn publish_saved_items_as_rss() {
+    let db = helpers::database();
+    save(&db, 2);
+
+    let content = publisher::publish(&db, &PublishFormat::Rss, Some(1)).unwrap();
+    let Ok(Feed::RSS(channel)) = content.parse::<Feed>() else {
+        panic!("not an rss feed: {}", content);
+    };
+
+    assert_eq!(channel.items().len(), 1);
+    assert_eq!(channel.items()[0].title(), Some("Second"));
+    assert_eq!(channel.items()[0].description(), Some("<p>Second</p>"));
+
+    let content = publisher::publish(&db, &PublishFormat::Rss, Some(2)).unwrap();
+    let Ok(Feed::RSS(channel)) = content.parse::<Feed>() else {
+        panic!("not an rss feed: {}", content);
+    };
+    assert!(channel.items().is_empty());
+}
+
+#[test]
+fn publish_writes_to_saved_items_feed_path() {
+    let db = helpers::database();
+    let path = std::env::temp_dir().join(format!("collie-saved-{}.rss", std::process::id()));
+    settings::update(
+        &db,
+        &SettingToUpdate {
+            key: SettingKey::SavedItemsFeedPath,
+            value: path.to_string_lossy().to_string(),
+        },
+    )
+    .unwrap();
+    save(&db, 1);
+
+    publisher::write(&db).unwrap();
+    let content = fs::read_to_string(&path).unwrap();
+    fs::remove_file(&path).unwrap();
+
+    let Ok(Feed::RSS(channel)) = content.parse::<Feed>() else {
+        panic!("not an rss feed: {}", content);
+    };
+    assert_eq!(channel.items()[0].title(), Some("First"));
+}
+
+#[test]
+fn publish_serves_saved_items() {
+    let db = helpers::database();
+    save(&db, 1);
+    let url = helpers::serve(db);
+
+    let res = reqwest::blocking::get(format!("{}/saved.atom", url)).unwrap();
+    assert_eq!(
+        res.headers()["Content-Type"],
+        "application/atom+xml; charset=utf-8"
+    );
+    assert!(matches!(
+        res.text().unwrap().parse::<Feed>(),
+        Ok(Feed::Atom(_))
+    ));
+
+    let res = reqwest::blocking::get(format!("{}/saved.json", url)).unwrap();
+    assert_eq!(res.status(), 404);
+}
@@ -0,0 +1,69 @@
+use pretty_assertions::assert_eq;
+use std::fs;
+
+use super::helpers;
+use crate::models::items;
+use crate::sanitizer;
+
+#[test]
+fn sanitize_hostile_content() {
+    let content = fs::read_to_string("src/tests/fixtures/hostile.html").unwrap();
+    let sanitized = sanitizer::sanitize(&content, Some("https://example.com/posts/1"));
+
+    for needle in [
+        "<script",
+        "alert",
+        "<style",
+        "onclick",
+        "onerror",
+        "style=",
+        "javascript:",
+        "data:",
+        "<iframe",
+        "<svg",
+        "<object",
+        "<form",
+        "<input",
+        "open.gif",
+        "feedburner",
+        "<!--",
+    ] {
+        assert!(!sanitized.contains(needle), "{} in {}", needle, sanitized);
+    }
+
+    assert!(sanitized.contains("<p>Hello <b>world</b></p>"));
+    assert!(sanitized.contains(r#"src="https://example.com/photo.jpg""#));
+    assert!(sanitized.contains(r#"href="https://example.com/about""#));
+    assert!(sanitized.contains(r#"rel="noopener noreferrer""#));
+}
+
+#[test]
+fn sanitize_keeps_safe_content() {
+    let content = r#"<h2>Title</h2><p>A <a href="https://example.com">link</a>, <code>code</code> and <img src="https://example.com/a.png" width="640" height="480">.</p>"#;
+
+    assert_eq!(
+        sanitizer::sanitize(content, None),
+        r#"<h2>Title</h2><p>A <a href="https://example.com" rel="noopener noreferrer">link</a>, <code>code</code> and <img src="https://example.com/a.png" width="640" height="480">.</p>"#
+    );
+}
+
+#[test]
+fn sanitize_legacy_items() {
+    let db = helpers::database();
+    items::update_description(&db, 1, "<p>Clean</p>", "<p>Clean</p>").unwrap();
+    db.execute(
+        "UPDATE items SET description = '<p onclick=\"x()\">Second</p>' WHERE id = 2",
+        [],
+    )
+    .unwrap();
+
+    assert_eq!(sanitizer::sanitize_legacy_items(&db).unwrap(), 2);
+    assert_eq!(sanitizer::sanitize_legacy_items(&db).unwrap(), 0);
+
+    let item = items::read(&db, 2).unwrap().unwrap();
+    assert_eq!(item.description, "<p>Second</p>");
+    assert_eq!(
+        items::read_all_without_raw_description(&db).unwrap(),
+        vec![]
+    );
+}
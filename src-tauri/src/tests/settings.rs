@@ -0,0 +1,189 @@
+use pretty_assertions::assert_eq;
+use rusqlite::Connection;
+
+use crate::error::Error;
+use crate::models::database;
+use crate::models::settings::{self, SettingKey, SettingToUpdate};
+
+fn database() -> Connection {
+    let db = Connection::open_in_memory().unwrap();
+    database::migrate(&db).unwrap();
+    db
+}
+
+fn update(db: &Connection, key: &SettingKey, value: &str) -> Result<usize, Error> {
+    settings::update(
+        db,
+        &SettingToUpdate {
+            key: key.clone(),
+            value: value.to_string(),
+        },
+    )
+}
+
+#[test]
+fn validate_every_key() {
+    // Key, a valid value, how it is stored and an invalid value, if any.
+    let cases = [
+        (SettingKey::PollingFrequency, "30", "30", Some("29")),
+        (SettingKey::Notification, "true", "1", Some("yes")),
+        (SettingKey::DbSchemeVersion, "2", "1", Some("2")),
+        (SettingKey::Theme, "dark", "dark", Some("blue")),
+        (
+            SettingKey::ItemsOrder,
+            "UnreadFirst",
+            "UnreadFirst",
+            Some("Random"),
+        ),
+        (
+            SettingKey::Proxy,
+            "socks5://127.0.0.1:1080",
+            "socks5://127.0.0.1:1080",
+            Some("not a proxy"),
+        ),
+        (SettingKey::FetchOldItems, "false", "0", Some("2")),
+        (SettingKey::ApiServer, "1", "1", Some("on")),
+        (
+            SettingKey::ApiServerAddress,
+            "0.0.0.0:8080",
+            "0.0.0.0:8080",
+            Some("localhost"),
+        ),
+        (SettingKey::ApiUsername, "reader", "reader", None),
+        (SettingKey::ApiPassword, "secret", "secret", None),
+        (
+            SettingKey::MinifluxUrl,
+            "https://miniflux.example.com",
+            "https://miniflux.example.com",
+            Some("miniflux"),
+        ),
+        (SettingKey::MinifluxApiKey, "key", "key", None),
+        (
+            SettingKey::MinifluxSyncedAt,
+            "1700000000",
+            "1700000000",
+            Some("-1"),
+        ),
+        (
+            SettingKey::SavedItemsFeedPath,
+            "/tmp/saved.xml",
+            "/tmp/saved.xml",
+            None,
+        ),
+        (SettingKey::AssetCache, "1", "1", Some("sure")),
+        (SettingKey::AssetCacheSize, "500", "500", Some("-5")),
+        (SettingKey::HttpTimeout, "5", "5", Some("0")),
+        (SettingKey::HttpConnectTimeout, "5", "5", Some("0")),
+        (SettingKey::HttpRetries, "10", "10", Some("11")),
+        (SettingKey::UserAgent, "Reader/1.0", "Reader/1.0", None),
+        (SettingKey::QuietHoursStart, "7:30", "07:30", Some("25:00")),
+        (SettingKey::QuietHoursEnd, "", "", Some("noon")),
+        (SettingKey::NotificationInterval, "60", "60", Some("1.5")),
+    ];
+    assert_eq!(
+        cases.iter().map(|x| x.0.clone()).collect::<Vec<_>>(),
+        SettingKey::ALL
+    );
+
+    let db = database();
+    for (key, valid, stored, invalid) in cases {
+        let schema = key.schema();
+        assert_eq!(settings::value(&db, &key), schema.default);
+
+        if schema.read_only {
+            assert!(matches!(update(&db, &key, valid), Err(Error::Forbidden)));
+        } else {
+            update(&db, &key, valid).unwrap();
+        }
+        assert_eq!(settings::value(&db, &key), stored);
+
+        if let Some(invalid) = invalid {
+            assert!(
+                update(&db, &key, invalid).is_err(),
+                "{} accepted {}",
+                key,
+                invalid
+            );
+            assert_eq!(settings::value(&db, &key), stored);
+        }
+    }
+}
+
+#[test]
+fn read_typed_values() {
+    let db = database();
+    update(&db, &SettingKey::Notification, "false").unwrap();
+    update(&db, &SettingKey::QuietHoursStart, "22:00").unwrap();
+
+    assert!(!settings::boolean(&db, &SettingKey::Notification));
+    assert!(settings::boolean(&db, &SettingKey::FetchOldItems));
+    assert_eq!(settings::integer(&db, &SettingKey::PollingFrequency), 300);
+    assert_eq!(
+        settings::time(&db, &SettingKey::QuietHoursStart),
+        chrono::NaiveTime::from_hms_opt(22, 0, 0)
+    );
+    assert_eq!(settings::time(&db, &SettingKey::QuietHoursEnd), None);
+}
+
+#[test]
+fn read_invalid_or_missing_values_as_default() {
+    let db = database();
+    db.execute(
+        "UPDATE settings SET value = 'often' WHERE key = 'polling_frequency'",
+        [],
+    )
+    .unwrap();
+    db.execute("DELETE FROM settings WHERE key = 'theme'", [])
+        .unwrap();
+
+    assert_eq!(settings::integer(&db, &SettingKey::PollingFrequency), 300);
+    assert_eq!(settings::value(&db, &SettingKey::Theme), "system");
+
+    let all = settings::read_all(&db).unwrap();
+    let polling_frequency = all
+        .iter()
+        .find(|x| x.key == SettingKey::PollingFrequency)
+        .unwrap();
+    assert_eq!(polling_frequency.value, "300");
+}
+
+#[test]
+fn reset_to_default() {
+    let db = database();
+    update(&db, &SettingKey::HttpRetries, "5").unwrap();
+
+    let setting = settings::reset(&db, &SettingKey::HttpRetries).unwrap();
+    assert_eq!(setting.value, "2");
+    assert_eq!(settings::integer(&db, &SettingKey::HttpRetries), 2);
+
+    assert!(matches!(
+        settings::reset(&db, &SettingKey::DbSchemeVersion),
+        Err(Error::Forbidden)
+    ));
+}
+
+#[test]
+fn redact_secrets() {
+    let db = database();
+    update(&db, &SettingKey::ApiPassword, "secret").unwrap();
+    update(&db, &SettingKey::ApiUsername, "reader").unwrap();
+
+    let all = serde_json::to_value(settings::read_all(&db).unwrap()).unwrap();
+    let value = |key: &str| {
+        all.as_array()
+            .unwrap()
+            .iter()
+            .find(|x| x["key"] == key)
+            .unwrap()["value"]
+            .clone()
+    };
+    assert_eq!(value("ApiPassword"), "");
+    assert_eq!(value("ApiUsername"), "reader");
+
+    // The redacted value sent back keeps the stored one.
+    update(&db, &SettingKey::ApiPassword, "").unwrap();
+    assert_eq!(settings::value(&db, &SettingKey::ApiPassword), "secret");
+
+    settings::reset(&db, &SettingKey::ApiPassword).unwrap();
+    assert_eq!(settings::value(&db, &SettingKey::ApiPassword), "");
+}
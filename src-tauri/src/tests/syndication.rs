@@ -1,8 +1,10 @@
 use chrono::DateTime;
 use pretty_assertions::assert_eq;
 use std::{fs, path::PathBuf};
+use tiny_http::{Header, Response};
 
-use crate::syndication::{self, RawItem};
+use super::helpers;
+use crate::syndication::{self, RawFeed, RawItem};
 
 fn fixture(path: &str) -> String {
     fs::canonicalize(PathBuf::from(format!("src/tests/fixtures/{}", path)))
@@ -14,19 +16,25 @@ fn fixture(path: &str) -> String {
 
 #[test]
 fn fetch_feed_title_rss() {
-    let title = syndication::fetch_feed_title(&fixture("hnrss-org-frontpage.rss"), None).unwrap();
+    let title =
+        syndication::fetch_feed_title(&fixture("hnrss-org-frontpage.rss"), &helpers::client())
+            .unwrap();
     assert_eq!(title, "Hacker News: Front Page");
 }
 
 #[test]
 fn fetch_feed_title_atom() {
-    let title = syndication::fetch_feed_title(&fixture("hnrss-org-frontpage.atom"), None).unwrap();
+    let title =
+        syndication::fetch_feed_title(&fixture("hnrss-org-frontpage.atom"), &helpers::client())
+            .unwrap();
     assert_eq!(title, "Hacker News: Front Page");
 }
 
 #[test]
 fn fetch_feed_items_rss() {
-    let items = syndication::fetch_feed_items(&fixture("hnrss-org-frontpage.rss"), None).unwrap();
+    let items =
+        syndication::fetch_feed_items(&fixture("hnrss-org-frontpage.rss"), &helpers::client())
+            .unwrap();
     assert_eq!(
         vec![
             RawItem {
@@ -35,6 +43,7 @@ fn fetch_feed_items_rss() {
                 link: Some("https://github.com/samuelclay/hackersmacker".to_string()),
                 content: Some("\n<p>Article URL: <a href=\"https://github.com/samuelclay/hackersmacker\">https://github.com/samuelclay/hackersmacker</a></p>\n<p>Comments URL: <a href=\"https://news.ycombinator.com/item?id=37288627\">https://news.ycombinator.com/item?id=37288627</a></p>\n<p>Points: 36</p>\n<p># Comments: 14</p>\n".to_string()),
                 published_at: Some(DateTime::parse_from_rfc3339("2023-08-28T01:33:24+00:00").unwrap()),
+                thumbnail: None,
             },
             RawItem {
                 title: "Writing Linux Modules in Ada – Part 1".to_string(),
@@ -42,6 +51,7 @@ fn fetch_feed_items_rss() {
                 link: Some("http://www.nihamkin.com/2016/10/23/writing-linux-modules-in-ada-part-1/#writing-linux-modules-in-ada-part-1".to_string()),
                 content: Some("\n<p>Article URL: <a href=\"http://www.nihamkin.com/2016/10/23/writing-linux-modules-in-ada-part-1/#writing-linux-modules-in-ada-part-1\">http://www.nihamkin.com/2016/10/23/writing-linux-modules-in-ada-part-1/#writing-linux-modules-in-ada-part-1</a></p>\n<p>Comments URL: <a href=\"https://news.ycombinator.com/item?id=37288446\">https://news.ycombinator.com/item?id=37288446</a></p>\n<p>Points: 27</p>\n<p># Comments: 5</p>\n".to_string()),
                 published_at: Some(DateTime::parse_from_rfc3339("2023-08-28T01:05:24+00:00").unwrap()),
+                thumbnail: None,
             },
             RawItem {
                 title: "Federal study links testicular cancer to ‘forever chemicals’".to_string(),
@@ -49,6 +59,7 @@ fn fetch_feed_items_rss() {
                 link: Some("https://undark.org/2023/08/22/federal-study-links-testicular-cancer-to-forever-chemicals/".to_string()),
                 content: Some("\n<p>Article URL: <a href=\"https://undark.org/2023/08/22/federal-study-links-testicular-cancer-to-forever-chemicals/\">https://undark.org/2023/08/22/federal-study-links-testicular-cancer-to-forever-chemicals/</a></p>\n<p>Comments URL: <a href=\"https://news.ycombinator.com/item?id=37288208\">https://news.ycombinator.com/item?id=37288208</a></p>\n<p>Points: 62</p>\n<p># Comments: 15</p>\n".to_string()),
                 published_at: Some(DateTime::parse_from_rfc3339("2023-08-28T00:32:34+00:00").unwrap()),
+                thumbnail: None,
             },
         ],
         items,
@@ -57,7 +68,9 @@ fn fetch_feed_items_rss() {
 
 #[test]
 fn fetch_feed_items_atom() {
-    let items = syndication::fetch_feed_items(&fixture("hnrss-org-frontpage.atom"), None).unwrap();
+    let items =
+        syndication::fetch_feed_items(&fixture("hnrss-org-frontpage.atom"), &helpers::client())
+            .unwrap();
     assert_eq!(
         vec![
             RawItem {
@@ -66,6 +79,7 @@ fn fetch_feed_items_atom() {
                 link: Some("https://github.com/samuelclay/hackersmacker".to_string()),
                 content: Some("\n<p>Article URL: <a href=\"https://github.com/samuelclay/hackersmacker\">https://github.com/samuelclay/hackersmacker</a></p>\n<p>Comments URL: <a href=\"https://news.ycombinator.com/item?id=37288627\">https://news.ycombinator.com/item?id=37288627</a></p>\n<p>Points: 36</p>\n<p># Comments: 14</p>\n".to_string()),
                 published_at: Some(DateTime::parse_from_rfc3339("2023-08-28T01:33:24+00:00").unwrap()),
+                thumbnail: None,
             },
             RawItem {
                 title: "Writing Linux Modules in Ada – Part 1".to_string(),
@@ -73,6 +87,7 @@ fn fetch_feed_items_atom() {
                 link: Some("http://www.nihamkin.com/2016/10/23/writing-linux-modules-in-ada-part-1/#writing-linux-modules-in-ada-part-1".to_string()),
                 content: Some("\n<p>Article URL: <a href=\"http://www.nihamkin.com/2016/10/23/writing-linux-modules-in-ada-part-1/#writing-linux-modules-in-ada-part-1\">http://www.nihamkin.com/2016/10/23/writing-linux-modules-in-ada-part-1/#writing-linux-modules-in-ada-part-1</a></p>\n<p>Comments URL: <a href=\"https://news.ycombinator.com/item?id=37288446\">https://news.ycombinator.com/item?id=37288446</a></p>\n<p>Points: 27</p>\n<p># Comments: 5</p>\n".to_string()),
                 published_at: Some(DateTime::parse_from_rfc3339("2023-08-28T01:05:24+00:00").unwrap()),
+                thumbnail: None,
             },
             RawItem {
                 title: "Federal study links testicular cancer to ‘forever chemicals’".to_string(),
@@ -80,8 +95,101 @@ fn fetch_feed_items_atom() {
                 link: Some("https://undark.org/2023/08/22/federal-study-links-testicular-cancer-to-forever-chemicals/".to_string()),
                 content: Some("\n<p>Article URL: <a href=\"https://undark.org/2023/08/22/federal-study-links-testicular-cancer-to-forever-chemicals/\">https://undark.org/2023/08/22/federal-study-links-testicular-cancer-to-forever-chemicals/</a></p>\n<p>Comments URL: <a href=\"https://news.ycombinator.com/item?id=37288208\">https://news.ycombinator.com/item?id=37288208</a></p>\n<p>Points: 62</p>\n<p># Comments: 15</p>\n".to_string()),
                 published_at: Some(DateTime::parse_from_rfc3339("2023-08-28T00:32:34+00:00").unwrap()),
+                thumbnail: None,
             },
         ],
         items,
     );
 }
+
+#[test]
+fn fetch_feed_items_thumbnail() {
+    let items =
+        syndication::fetch_feed_items(&fixture("thumbnail.rss"), &helpers::client()).unwrap();
+    assert_eq!(
+        items
+            .iter()
+            .map(|x| x.thumbnail.as_deref())
+            .collect::<Vec<_>>(),
+        vec![
+            Some("https://example.com/1.jpg"),
+            Some("https://example.com/2.png"),
+            None
+        ]
+    );
+}
+
+#[test]
+fn fetch_feed_metadata_rss() {
+    let (metadata, _) =
+        syndication::fetch_feed(&fixture("hnrss-org-frontpage.rss"), &helpers::client()).unwrap();
+    assert_eq!(
+        metadata,
+        RawFeed {
+            site_link: Some("https://news.ycombinator.com/".to_string()),
+            description: Some("Hacker News RSS".to_string()),
+            language: None,
+            image: None,
+            self_link: Some("https://hnrss.org/frontpage".to_string()),
+            new_feed_url: None,
+            redirected_to: None,
+        }
+    );
+}
+
+#[test]
+fn fetch_feed_title_euc_kr() {
+    let title = syndication::fetch_feed_title(&fixture("euc-kr.rss"), &helpers::client()).unwrap();
+    assert_eq!(title, "콜리 소식");
+}
+
+#[test]
+fn fetch_feed_items_shift_jis() {
+    let items =
+        syndication::fetch_feed_items(&fixture("shift_jis.atom"), &helpers::client()).unwrap();
+    assert_eq!(
+        items
+            .iter()
+            .map(|x| (x.title.as_str(), x.content.as_deref()))
+            .collect::<Vec<_>>(),
+        vec![("最初の記事", Some("こんにちは、世界！"))]
+    );
+}
+
+#[test]
+fn fetch_content_windows_1252() {
+    let content =
+        syndication::fetch_content(&fixture("windows-1252.html"), &helpers::client()).unwrap();
+    assert!(content.contains("<title>Café</title>"));
+    assert!(content.contains("Crème brûlée — “délicieux”"));
+}
+
+#[test]
+fn fetch_feed_declaring_encoding_only_in_prolog() {
+    let base = helpers::mock(|_| {
+        let body = fs::read("src/tests/fixtures/euc-kr.rss").unwrap();
+        let content_type = Header::from_bytes("Content-Type", "application/rss+xml").unwrap();
+        Response::from_data(body).with_header(content_type).boxed()
+    });
+
+    let link = format!("{}/feed", base);
+    let items = syndication::fetch_feed_items(&link, &helpers::client()).unwrap();
+    assert_eq!(items[0].title, "첫 번째 글");
+    assert_eq!(items[0].content.as_deref(), Some("안녕하세요, 세계!"));
+}
+
+#[test]
+fn fetch_feed_items_lenient_dates() {
+    let items = syndication::fetch_feed_items(&fixture("dates.rss"), &helpers::client()).unwrap();
+    assert_eq!(
+        items
+            .iter()
+            .map(|x| x.published_at.map(|x| x.to_rfc3339()))
+            .collect::<Vec<_>>(),
+        vec![
+            Some("2023-10-10T12:34:56+00:00".to_string()),
+            Some("2023-10-08T23:00:00+00:00".to_string()),
+            None
+        ]
+    );
+}
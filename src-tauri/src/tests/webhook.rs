@@ -0,0 +1,251 @@
+use pretty_assertions::assert_eq;
+use std::sync::{Arc, Mutex};
+use std::thread;
+use std::time::{Duration, Instant};
+use tiny_http::Response;
+
+use super::helpers::{self, item};
+use crate::error::Error;
+use crate::models::{
+    items::ItemToCreate,
+    webhooks::{
+        self, Webhook, WebhookDeliveryToCreate, WebhookFormat, WebhookToCreate, WebhookToUpdate,
+    },
+};
+use crate::webhook;
+
+fn hook_to_create() -> WebhookToCreate {
+    WebhookToCreate {
+        url: "http://127.0.0.1/hook".to_string(),
+        feed: None,
+        filter: None,
+        format: WebhookFormat::Json,
+        template: None,
+    }
+}
+
+fn hook(format: WebhookFormat, template: Option<&str>) -> Webhook {
+    Webhook {
+        id: 1,
+        url: "http://127.0.0.1".to_string(),
+        feed: None,
+        filter: None,
+        format,
+        template: template.map(String::from),
+    }
+}
+
+/// Responds with the given statuses in order, then 500, and records the request bodies.
+fn mock(statuses: Vec<u16>) -> (String, Arc<Mutex<Vec<String>>>) {
+    let requests = Arc::new(Mutex::new(vec![]));
+    let recorded = requests.clone();
+    let mut statuses = statuses.into_iter();
+
+    let base = helpers::mock(move |request| {
+        recorded.lock().unwrap().push(helpers::body(request));
+        Response::empty(statuses.next().unwrap_or(500)).boxed()
+    });
+
+    (format!("{}/hook", base), requests)
+}
+
+#[test]
+fn webhook_render_json_template() {
+    let (content_type, body) = webhook::render(
+        &hook(
+            WebhookFormat::Json,
+            Some(r#"{"text": "{{title}} {{link}}", "body": "{{description}}"}"#),
+        ),
+        &ItemToCreate {
+            description: "<p>\"Quoted\" & more</p>".to_string(),
+            ..item("Say \"hi\"")
+        },
+    );
+
+    assert_eq!(content_type, "application/json");
+    let value: serde_json::Value = serde_json::from_str(&body).unwrap();
+    assert_eq!(value["text"], "Say \"hi\" https://example.com/new");
+    assert_eq!(value["body"], "<p>\"Quoted\" & more</p>");
+}
+
+#[test]
+fn webhook_render_form() {
+    let (content_type, body) = webhook::render(&hook(WebhookFormat::Form, None), &item("A & B"));
+
+    assert_eq!(content_type, "application/x-www-form-urlencoded");
+    assert!(body.starts_with("title=A+%26+B&link=https%3A%2F%2Fexample.com%2Fnew&author=parksb"));
+}
+
+#[test]
+fn webhook_dispatch_retries_and_filters() {
+    let db = helpers::database();
+    let (url, requests) = mock(vec![503, 200]);
+
+    webhooks::create(
+        &db,
+        &WebhookToCreate {
+            url,
+            feed: Some(1),
+            filter: Some("^Rust".to_string()),
+            format: WebhookFormat::Json,
+            template: Some(r#"{"title": "{{title}}"}"#.to_string()),
+        },
+    )
+    .unwrap();
+
+    webhook::dispatch(
+        &db,
+        &[item("Rust 1.72"), item("Go 1.21")],
+        &helpers::client(),
+    )
+    .unwrap();
+
+    assert_eq!(
+        *requests.lock().unwrap(),
+        vec![
+            r#"{"title": "Rust 1.72"}"#.to_string(),
+            r#"{"title": "Rust 1.72"}"#.to_string(),
+        ]
+    );
+
+    let deliveries = webhooks::read_all_deliveries(&db, 1, None).unwrap();
+    assert_eq!(deliveries.len(), 1);
+    assert_eq!(deliveries[0].status_code, Some(200));
+    assert_eq!(deliveries[0].attempts, 2);
+    assert_eq!(deliveries[0].error, None);
+}
+
+#[test]
+fn webhook_update_clears_fields() {
+    let db = helpers::database();
+    webhooks::create(
+        &db,
+        &WebhookToCreate {
+            url: "http://127.0.0.1/hook".to_string(),
+            feed: Some(1),
+            filter: Some("^Rust".to_string()),
+            format: WebhookFormat::Json,
+            template: Some("{{title}}".to_string()),
+        },
+    )
+    .unwrap();
+
+    let arg: WebhookToUpdate =
+        serde_json::from_str(r#"{"id": 1, "feed": null, "filter": null}"#).unwrap();
+    webhooks::update(&db, &arg).unwrap();
+
+    let webhook = &webhooks::read_all(&db).unwrap()[0];
+    assert_eq!(webhook.feed, None);
+    assert_eq!(webhook.filter, None);
+    assert_eq!(webhook.template.as_deref(), Some("{{title}}"));
+}
+
+#[test]
+fn webhook_rejects_invalid_url_and_filter() {
+    let db = helpers::database();
+    let arg = WebhookToCreate {
+        url: "not a url".to_string(),
+        ..hook_to_create()
+    };
+    assert!(matches!(
+        webhooks::create(&db, &arg),
+        Err(Error::InvalidValue(_))
+    ));
+
+    let arg = WebhookToCreate {
+        filter: Some("(".to_string()),
+        ..hook_to_create()
+    };
+    assert!(matches!(
+        webhooks::create(&db, &arg),
+        Err(Error::InvalidValue(_))
+    ));
+    assert!(webhooks::read_all(&db).unwrap().is_empty());
+
+    webhooks::create(&db, &hook_to_create()).unwrap();
+    let arg: WebhookToUpdate = serde_json::from_str(r#"{"id": 1, "filter": "["}"#).unwrap();
+    assert!(matches!(
+        webhooks::update(&db, &arg),
+        Err(Error::InvalidValue(_))
+    ));
+    let arg: WebhookToUpdate = serde_json::from_str(r#"{"id": 1}"#).unwrap();
+    assert!(matches!(
+        webhooks::update(&db, &arg),
+        Err(Error::NothingToUpdate)
+    ));
+}
+
+#[test]
+fn webhook_deliveries_are_pruned() {
+    let db = helpers::database();
+    webhooks::create(&db, &hook_to_create()).unwrap();
+    webhooks::create(&db, &hook_to_create()).unwrap();
+
+    for i in 0..webhooks::DELIVERIES_KEPT + 5 {
+        webhooks::create_delivery(
+            &db,
+            &WebhookDeliveryToCreate {
+                webhook: 1,
+                link: format!("https://example.com/{}", i),
+                status_code: Some(200),
+                error: None,
+                attempts: 1,
+            },
+        )
+        .unwrap();
+    }
+    webhooks::create_delivery(
+        &db,
+        &WebhookDeliveryToCreate {
+            webhook: 2,
+            link: "https://example.com/other".to_string(),
+            status_code: Some(200),
+            error: None,
+            attempts: 1,
+        },
+    )
+    .unwrap();
+
+    let deliveries = webhooks::read_all_deliveries(&db, 1, None).unwrap();
+    assert_eq!(deliveries.len() as u64, webhooks::DELIVERIES_KEPT);
+    assert_eq!(
+        deliveries[0].link,
+        format!("https://example.com/{}", webhooks::DELIVERIES_KEPT + 4)
+    );
+    assert_eq!(
+        deliveries.last().unwrap().link,
+        "https://example.com/5".to_string()
+    );
+    assert_eq!(
+        webhooks::read_all_deliveries(&db, 2, None).unwrap().len(),
+        1
+    );
+}
+
+#[test]
+fn webhook_spawn_delivers_in_the_background() {
+    let db = helpers::database();
+    let (url, requests) = mock(vec![200]);
+    webhooks::create(
+        &db,
+        &WebhookToCreate {
+            url,
+            feed: None,
+            filter: None,
+            format: WebhookFormat::Form,
+            template: Some("{{title}}".to_string()),
+        },
+    )
+    .unwrap();
+
+    let queue = webhook::spawn(db);
+    queue
+        .send((vec![item("Queued")], helpers::client()))
+        .unwrap();
+
+    let deadline = Instant::now() + Duration::from_secs(5);
+    while requests.lock().unwrap().is_empty() && Instant::now() < deadline {
+        thread::sleep(Duration::from_millis(10));
+    }
+    assert_eq!(*requests.lock().unwrap(), vec!["Queued".to_string()]);
+}
@@ -0,0 +1,109 @@
+use rusqlite::Connection;
+use std::sync::atomic::Ordering;
+use tauri::{
+    AppHandle, CustomMenuItem, Manager, SystemTray, SystemTrayEvent, SystemTrayMenu,
+    SystemTrayMenuItem,
+};
+
+use crate::models::items::{self, ItemReadOption, ItemStatus, ItemToUpdateAll};
+use crate::worker::{self, Message};
+use crate::{show_window, DbState, WorkerState};
+
+const UNREAD: &str = "unread";
+const REFRESH: &str = "refresh";
+const READ_ALL: &str = "read_all";
+const PAUSE: &str = "pause";
+const QUIT: &str = "quit";
+
+pub fn build() -> SystemTray {
+    let menu = SystemTrayMenu::new()
+        .add_item(CustomMenuItem::new(UNREAD, "No unread items").disabled())
+        .add_native_item(SystemTrayMenuItem::Separator)
+        .add_item(CustomMenuItem::new(REFRESH, "Refresh now"))
+        .add_item(CustomMenuItem::new(READ_ALL, "Mark all read"))
+        .add_item(CustomMenuItem::new(PAUSE, "Pause updates"))
+        .add_native_item(SystemTrayMenuItem::Separator)
+        .add_item(CustomMenuItem::new(QUIT, "Quit"));
+
+    SystemTray::new().with_menu(menu)
+}
+
+pub fn handle_event(app: &AppHandle, event: SystemTrayEvent) {
+    match event {
+        SystemTrayEvent::LeftClick { .. } => show_window(app),
+        SystemTrayEvent::MenuItemClick { id, .. } => match id.as_str() {
+            REFRESH => {
+                if let Err(err) = worker::send(&app.state::<WorkerState>(), Message::Wake) {
+                    eprintln!("Error refreshing feeds: {}", err);
+                }
+            }
+            READ_ALL => read_all(app),
+            PAUSE => {
+                let worker_state = app.state::<WorkerState>();
+                let message = if worker_state.paused.load(Ordering::SeqCst) {
+                    Message::Resume
+                } else {
+                    Message::Pause
+                };
+                if let Err(err) = worker::send(&worker_state, message) {
+                    eprintln!("Error pausing updates: {}", err);
+                }
+            }
+            // Exiting stops the worker, see `main`.
+            QUIT => app.exit(0),
+            _ => {}
+        },
+        _ => {}
+    }
+}
+
+/// Shows the number of unread items in the tray menu and tooltip.
+pub fn update(app: &AppHandle, db: &Connection) {
+    let opt = ItemReadOption {
+        status: Some(ItemStatus::Unread),
+        ..Default::default()
+    };
+    let title = match items::count_all(db, &opt) {
+        Ok(0) => "No unread items".to_string(),
+        Ok(1) => "1 unread item".to_string(),
+        Ok(count) => format!("{} unread items", count),
+        Err(err) => {
+            eprintln!("Error counting unread items: {}", err);
+            return;
+        }
+    };
+
+    let tray = app.tray_handle();
+    let _ = tray.get_item(UNREAD).set_title(&title);
+    let _ = tray.set_tooltip(&format!("Collie - {}", title));
+}
+
+pub fn set_paused(app: &AppHandle, paused: bool) {
+    let title = if paused {
+        "Resume updates"
+    } else {
+        "Pause updates"
+    };
+    let _ = app.tray_handle().get_item(PAUSE).set_title(title);
+}
+
+fn read_all(app: &AppHandle) {
+    let db_state = app.state::<DbState>();
+    let db = db_state.lock();
+    let arg = ItemToUpdateAll {
+        status: Some(ItemStatus::Read),
+        is_saved: None,
+        opt: Some(ItemReadOption {
+            status: Some(ItemStatus::Unread),
+            ..Default::default()
+        }),
+    };
+
+    match items::update_all(&db, &arg) {
+        Ok(_) => {
+            update(app, &db);
+            let _ = app.emit_all("feed_updated", ());
+        }
+        Err(err) => eprintln!("Error marking items as read: {}", err),
+    }
+}
@@ -0,0 +1,164 @@
+use std::sync::mpsc::{self, Sender};
+use std::thread;
+use std::time::Duration;
+
+use regex::Regex;
+use reqwest::Method;
+use rusqlite::Connection;
+use serde_json::Value;
+use url::form_urlencoded;
+
+use crate::error::Result;
+use crate::http::HttpClient;
+use crate::models::items::ItemToCreate;
+use crate::models::webhooks::{self, Webhook, WebhookDeliveryToCreate, WebhookFormat};
+
+const MAX_ATTEMPTS: u32 = 3;
+const RETRY_DELAY: Duration = Duration::from_secs(1);
+// Bounds each attempt so that an endpoint that never answers does not hold up the queue,
+// even if the configured timeout is longer.
+const DELIVERY_TIMEOUT: Duration = Duration::from_secs(10);
+
+/// Starts delivering webhooks on their own thread, so that slow endpoints and retries do not
+/// hold up polling. Items sent to the returned queue are dispatched in order, with the client
+/// sent along. The thread stops once the queue is dropped and the items already queued are
+/// delivered.
+pub fn spawn(db: Connection) -> Sender<(Vec<ItemToCreate>, HttpClient)> {
+    let (sender, receiver) = mpsc::channel::<(Vec<ItemToCreate>, HttpClient)>();
+    thread::spawn(move || {
+        for (items, client) in receiver {
+            if let Err(err) = dispatch(&db, &items, &client) {
+                eprintln!("Error dispatching webhooks: {}", err);
+            }
+        }
+    });
+
+    sender
+}
+
+pub fn dispatch(db: &Connection, items: &[ItemToCreate], client: &HttpClient) -> Result<()> {
+    let webhooks = webhooks::read_all(db)?;
+    if webhooks.is_empty() {
+        return Ok(());
+    }
+
+    for webhook in webhooks {
+        let filter = match webhook.filter.as_deref().filter(|x| !x.is_empty()) {
+            Some(filter) => match Regex::new(filter) {
+                Ok(regex) => Some(regex),
+                Err(err) => {
+                    eprintln!("Invalid filter for webhook {}: {}", webhook.id, err);
+                    continue;
+                }
+            },
+            None => None,
+        };
+
+        for item in items.iter().filter(|x| {
+            webhook.feed.is_none_or(|feed| feed == x.feed)
+                && filter.as_ref().is_none_or(|regex| regex.is_match(&x.title))
+        }) {
+            webhooks::create_delivery(db, &deliver(client, &webhook, item))?;
+        }
+    }
+
+    Ok(())
+}
+
+fn deliver(client: &HttpClient, webhook: &Webhook, item: &ItemToCreate) -> WebhookDeliveryToCreate {
+    let (content_type, body) = render(webhook, item);
+    let mut delivery = WebhookDeliveryToCreate {
+        webhook: webhook.id,
+        link: item.link.clone(),
+        status_code: None,
+        error: None,
+        attempts: 0,
+    };
+
+    while delivery.attempts < MAX_ATTEMPTS {
+        if delivery.attempts > 0 {
+            thread::sleep(RETRY_DELAY * delivery.attempts);
+        }
+        delivery.attempts += 1;
+
+        // Retried here rather than by the client, so that every attempt is recorded.
+        let res = client
+            .prepare(Method::POST, &webhook.url)
+            .timeout(client.config().timeout.min(DELIVERY_TIMEOUT))
+            .header("Content-Type", content_type)
+            .body(body.clone())
+            .send();
+
+        match res {
+            Ok(res) => {
+                let status = res.status();
+                delivery.status_code = Some(status.as_u16());
+
+                if status.is_success() {
+                    delivery.error = None;
+                    break;
+                }
+
+                delivery.error = Some(format!("unexpected status {}", status));
+                if status.is_client_error() && status.as_u16() != 429 {
+                    break;
+                }
+            }
+            Err(err) => {
+                delivery.status_code = None;
+                delivery.error = Some(err.to_string());
+            }
+        }
+    }
+
+    delivery
+}
+
+/// Renders the payload of the webhook for the item. `{{title}}`, `{{link}}`, `{{author}}`,
+/// `{{description}}`, `{{published_at}}` and `{{feed}}` in the template are replaced with the
+/// escaped values of the item.
+pub fn render(webhook: &Webhook, item: &ItemToCreate) -> (&'static str, String) {
+    let fields = [
+        ("title", item.title.clone()),
+        ("link", item.link.clone()),
+        ("author", item.author.clone().unwrap_or_default()),
+        ("description", item.description.clone()),
+        ("published_at", item.published_at.to_rfc3339()),
+        ("feed", item.feed.to_string()),
+    ];
+    let template = webhook.template.as_deref().filter(|x| !x.trim().is_empty());
+
+    match (&webhook.format, template) {
+        (WebhookFormat::Json, None) => (
+            "application/json",
+            serde_json::to_string(item).unwrap_or_default(),
+        ),
+        (WebhookFormat::Json, Some(template)) => (
+            "application/json",
+            fill(template, &fields, |x| {
+                let quoted = Value::String(x.to_string()).to_string();
+                quoted[1..quoted.len() - 1].to_string()
+            }),
+        ),
+        (WebhookFormat::Form, None) => (
+            "application/x-www-form-urlencoded",
+            form_urlencoded::Serializer::new(String::new())
+                .extend_pairs(fields.iter())
+                .finish(),
+        ),
+        (WebhookFormat::Form, Some(template)) => (
+            "application/x-www-form-urlencoded",
+            fill(template, &fields, |x| {
+                form_urlencoded::byte_serialize(x.as_bytes()).collect()
+            }),
+        ),
+    }
+}
+
+fn fill(template: &str, fields: &[(&str, String)], escape: impl Fn(&str) -> String) -> String {
+    fields
+        .iter()
+        .fold(template.to_string(), |acc, (key, value)| {
+            acc.replace(&format!("{{{{{}}}}}", key), &escape(value))
+        })
+}
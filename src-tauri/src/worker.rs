@@ -1,83 +1,454 @@
-use regex::Regex;
+use chrono::Local;
 use rusqlite::Connection;
+use serde::Serialize;
+use std::ops::ControlFlow;
 use std::path::PathBuf;
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::mpsc::{self, Receiver, RecvTimeoutError, Sender};
+use std::sync::{Arc, Mutex, MutexGuard, PoisonError};
 use std::thread;
-use std::time;
+use std::time::{Duration, Instant, SystemTime};
 use tauri::App;
+use tauri::AppHandle;
 use tauri::Manager;
 
+#[cfg(not(all(unix, not(target_os = "macos"))))]
 use tauri::api::notification::Notification;
 
+use crate::assets;
+use crate::error::{Error, Result};
+use crate::favicon;
+use crate::http::HttpClient;
+use crate::miniflux::{self, Miniflux};
 use crate::models::database::open_connection;
 use crate::models::items::ItemToCreate;
 use crate::models::settings;
 use crate::models::settings::SettingKey;
-use crate::producer::create_new_items;
-
-pub fn start(app: &App, app_data_dir: &PathBuf) {
-    let app_handle = app.handle();
-    let app_id = app.config().tauri.bundle.identifier.clone();
-    let db = open_connection(&app_data_dir).unwrap();
-
-    thread::spawn(move || loop {
-        match create_new_items(&db, proxy(&db).as_deref()) {
-            Ok(inserted) => {
-                if !inserted.is_empty() {
-                    if notification(&db) {
-                        notify(&app_id, &inserted);
+use crate::network;
+use crate::notifier::{Notifier, Toast};
+use crate::producer::{create_new_items, FeedOutcome, Progress};
+use crate::tray;
+use crate::webhook;
+use crate::{HttpState, WorkerState};
+
+// How often the worker wakes up to notice suspends, connectivity coming back and held
+// notifications that can be shown.
+const TICK: Duration = Duration::from_secs(30);
+const SUSPEND_THRESHOLD: Duration = Duration::from_secs(60);
+
+pub enum Message {
+    /// Refreshes every feed, or only one, right away and replies with the outcome of each.
+    Refresh {
+        feed: Option<i32>,
+        reply: Sender<Vec<FeedOutcome>>,
+    },
+    /// Polls right away instead of waiting for the polling frequency, even if paused.
+    Wake,
+    Pause,
+    Resume,
+    /// Wakes the worker up to reschedule the next poll with the new polling frequency.
+    SettingsUpdated,
+    Shutdown,
+}
+
+// Payloads of the `refresh_started` and `refresh_finished` events. Each feed is reported
+// by a `feed_fetched` event in between.
+#[derive(Serialize, Clone)]
+struct RefreshStarted {
+    feeds: usize,
+}
+
+#[derive(Serialize, Clone)]
+struct RefreshFinished {
+    feeds: usize,
+    new_items: usize,
+    errors: usize,
+}
+
+struct Worker {
+    app_handle: AppHandle,
+    db: Connection,
+    assets_dir: PathBuf,
+    icons_dir: PathBuf,
+    notifier: Mutex<Notifier>,
+    webhooks: Sender<(Vec<ItemToCreate>, HttpClient)>,
+    paused: Arc<AtomicBool>,
+    stopping: Arc<AtomicBool>,
+}
+
+pub fn start(app: &App, app_data_dir: &PathBuf) -> Result<()> {
+    let db = open_connection(&app_data_dir)?;
+
+    let client = HttpClient::from_settings(&db)?;
+    app.manage(HttpState {
+        client: Mutex::new(client),
+    });
+
+    let (sender, receiver) = mpsc::channel();
+    let paused = Arc::new(AtomicBool::new(false));
+    let stopping = Arc::new(AtomicBool::new(false));
+    let worker = Worker {
+        app_handle: app.handle(),
+        db,
+        assets_dir: assets::cache_dir(app_data_dir),
+        icons_dir: favicon::icons_dir(app_data_dir),
+        notifier: Mutex::new(Notifier::default()),
+        webhooks: webhook::spawn(open_connection(app_data_dir)?),
+        paused: paused.clone(),
+        stopping: stopping.clone(),
+    };
+    let thread = thread::spawn(move || worker.run(&receiver));
+
+    app.manage(WorkerState {
+        sender: Mutex::new(sender),
+        thread: Mutex::new(Some(thread)),
+        paused,
+        stopping,
+    });
+
+    Ok(())
+}
+
+pub fn send(state: &WorkerState, message: Message) -> Result<()> {
+    state
+        .sender
+        .lock()
+        .unwrap_or_else(PoisonError::into_inner)
+        .send(message)
+        .map_err(|_| Error::WorkerStopped)
+}
+
+/// Wakes the worker to refresh every feed, or only `feed`, and waits until it is done.
+pub fn refresh(state: &WorkerState, feed: Option<i32>) -> Result<Vec<FeedOutcome>> {
+    let (reply, outcomes) = mpsc::channel();
+    send(state, Message::Refresh { feed, reply })?;
+
+    outcomes.recv().map_err(|_| Error::WorkerStopped)
+}
+
+/// Stops the worker once the feed being fetched, if any, is done and waits for it to exit.
+pub fn stop(state: &WorkerState) {
+    state.stopping.store(true, Ordering::SeqCst);
+    let _ = send(state, Message::Shutdown);
+
+    let thread = state
+        .thread
+        .lock()
+        .unwrap_or_else(PoisonError::into_inner)
+        .take();
+    if let Some(thread) = thread {
+        let _ = thread.join();
+    }
+}
+
+impl Worker {
+    fn run(&self, receiver: &Receiver<Message>) {
+        let mut polled_at: Option<Instant> = None;
+        let mut online = true;
+        let mut clock = (Instant::now(), SystemTime::now());
+
+        while !self.stopping.load(Ordering::SeqCst) {
+            let digest = self.notifier().flush(&self.db, Local::now());
+            show(&self.app_handle, digest);
+
+            if resumed_from_suspend(&mut clock) {
+                polled_at = None;
+            }
+
+            // Read every time, so that a new polling frequency applies to the current wait.
+            let next_poll = polled_at.map_or(Instant::now(), |x| x + polling_frequency(&self.db));
+            let paused = self.paused.load(Ordering::SeqCst);
+            if !paused && next_poll <= Instant::now() {
+                let was_online = online;
+                online = network::is_online(&network::probe_targets(&self.db));
+
+                if online {
+                    self.poll();
+                    polled_at = Some(Instant::now());
+                    continue;
+                } else if was_online {
+                    eprintln!("Network is unreachable, skipping refreshes until it is back");
+                }
+            }
+
+            // Offline or paused, the worker still wakes up every tick, to retry the due poll
+            // or to show digests and notice suspends.
+            let message = if online && !paused {
+                receiver.recv_timeout(
+                    next_poll
+                        .saturating_duration_since(Instant::now())
+                        .min(TICK),
+                )
+            } else {
+                receiver.recv_timeout(TICK)
+            };
+
+            match message {
+                Ok(Message::Refresh { feed, reply }) => {
+                    let _ = reply.send(self.refresh(feed));
+
+                    // Refreshing a single feed does not postpone polling the others.
+                    if feed.is_none() {
+                        self.maintain();
+                        polled_at = Some(Instant::now());
                     }
+                }
+                Ok(Message::Wake) => {
+                    self.poll();
+                    polled_at = Some(Instant::now());
+                }
+                // A poll missed while paused happens as soon as the worker is resumed.
+                Ok(Message::Pause) => self.set_paused(true),
+                Ok(Message::Resume) => self.set_paused(false),
+                // Other settings are read when they are used.
+                Ok(Message::SettingsUpdated) | Err(RecvTimeoutError::Timeout) => {}
+                Ok(Message::Shutdown) | Err(RecvTimeoutError::Disconnected) => return,
+            }
+        }
+    }
+
+    fn set_paused(&self, paused: bool) {
+        self.paused.store(paused, Ordering::SeqCst);
+        tray::set_paused(&self.app_handle, paused);
+    }
+
+    fn poll(&self) {
+        self.refresh(None);
+        self.maintain();
+    }
+
+    fn client(&self) -> HttpClient {
+        self.app_handle.state::<HttpState>().lock().clone()
+    }
+
+    fn notifier(&self) -> MutexGuard<'_, Notifier> {
+        self.notifier.lock().unwrap_or_else(PoisonError::into_inner)
+    }
+
+    fn refresh(&self, feed: Option<i32>) -> Vec<FeedOutcome> {
+        let client = self.client();
+        let refreshed = create_new_items(&self.db, &client, feed, |progress| {
+            match progress {
+                Progress::Started { feeds } => {
+                    let _ = self
+                        .app_handle
+                        .emit_all("refresh_started", RefreshStarted { feeds });
+                }
+                Progress::Fetched(outcome) => {
+                    let _ = self.app_handle.emit_all("feed_fetched", outcome);
+                }
+            }
+
+            if self.stopping.load(Ordering::SeqCst) {
+                ControlFlow::Break(())
+            } else {
+                ControlFlow::Continue(())
+            }
+        });
 
-                    let _ = app_handle.emit_all("feed_updated", ());
+        let _ = self.app_handle.emit_all(
+            "refresh_finished",
+            RefreshFinished {
+                feeds: refreshed.outcomes.len(),
+                new_items: refreshed.inserted.len(),
+                errors: refreshed
+                    .outcomes
+                    .iter()
+                    .filter(|x| x.error.is_some())
+                    .count(),
+            },
+        );
+
+        let inserted = refreshed.inserted;
+
+        if !inserted.is_empty() {
+            if assets::enabled(&self.db) {
+                if let Err(err) =
+                    assets::cache_items(&self.db, &self.assets_dir, &inserted, &client)
+                {
+                    eprintln!("Error caching assets: {}", err);
                 }
             }
-            Err(err) => {
-                eprintln!("Error fetching new items: {}", err);
+
+            let toasts = self.notifier().notify(&self.db, &inserted, Local::now());
+            show(&self.app_handle, toasts);
+
+            tray::update(&self.app_handle, &self.db);
+            let _ = self.app_handle.emit_all("feed_updated", ());
+
+            if self.webhooks.send((inserted, client)).is_err() {
+                eprintln!("Error dispatching webhooks: the delivery thread stopped");
             }
         }
 
-        thread::sleep(time::Duration::from_secs(polling_frequency(&db)));
-    });
-}
+        refreshed.outcomes
+    }
+
+    fn maintain(&self) {
+        if self.stopping.load(Ordering::SeqCst) {
+            return;
+        }
 
-fn proxy(db: &Connection) -> Option<String> {
-    match settings::read(db, &SettingKey::Proxy) {
-        Ok(x) => Some(x.value),
-        Err(_) => None,
+        match favicon::refresh(&self.db, &self.icons_dir, &self.client()) {
+            Ok(updated) => {
+                if updated > 0 {
+                    let _ = self.app_handle.emit_all("feed_updated", ());
+                }
+            }
+            Err(err) => eprintln!("Error fetching favicons: {}", err),
+        }
+
+        if let Err(err) = assets::evict(&self.db, &self.assets_dir) {
+            eprintln!("Error evicting assets: {}", err);
+        }
+
+        if let Some(miniflux) = Miniflux::from_settings(&self.db, &self.client()) {
+            match miniflux::sync(&self.db, &miniflux) {
+                Ok(changed) => {
+                    if changed > 0 {
+                        tray::update(&self.app_handle, &self.db);
+                        let _ = self.app_handle.emit_all("feed_updated", ());
+                    }
+                }
+                Err(err) => {
+                    eprintln!("Error syncing with miniflux: {}", err);
+                }
+            }
+        }
     }
 }
 
-fn polling_frequency(db: &Connection) -> u64 {
-    settings::read(db, &SettingKey::PollingFrequency)
-        .map(|x| x.value)
-        .unwrap_or("300".to_string())
-        .parse()
-        .unwrap_or(300)
+// Instants stand still while the computer sleeps, so a poll due during sleep would otherwise
+// be late by as long as it slept. The wall clock keeps going, and jumps ahead on waking up.
+fn resumed_from_suspend(clock: &mut (Instant, SystemTime)) -> bool {
+    let (instant, system_time) = *clock;
+    *clock = (Instant::now(), SystemTime::now());
+
+    let elapsed = clock.0 - instant;
+    clock
+        .1
+        .duration_since(system_time)
+        .is_ok_and(|x| x > elapsed + SUSPEND_THRESHOLD)
 }
 
-fn notification(db: &Connection) -> bool {
-    settings::read(db, &SettingKey::Notification)
-        .map(|x| x.value)
-        .unwrap_or("1".to_string())
-        .parse()
-        .unwrap_or(true)
+fn polling_frequency(db: &Connection) -> Duration {
+    Duration::from_secs(settings::integer(db, &SettingKey::PollingFrequency) as u64)
 }
 
-fn notify(app_id: &str, args: &[ItemToCreate]) {
-    if args.len() <= 3 {
-        for arg in args {
-            let _ = Notification::new(app_id)
-                .title(&arg.title)
-                .body(
-                    Regex::new(r"<.*?>")
-                        .unwrap()
-                        .replace_all(&arg.description, ""),
-                )
-                .show();
+// Only the notification servers of Linux and the BSDs tell when a notification is clicked.
+// A thread waits for each notification until it is clicked or closed, so only a few are
+// waited on at a time and they expire. Notifications beyond those are shown without action.
+#[cfg(all(unix, not(target_os = "macos")))]
+fn show(app_handle: &AppHandle, toasts: Vec<Toast>) {
+    use std::sync::atomic::AtomicUsize;
+
+    const MAX_WAITING: usize = 3;
+    const TIMEOUT: notify_rust::Timeout = notify_rust::Timeout::Milliseconds(30_000);
+    static WAITING: AtomicUsize = AtomicUsize::new(0);
+
+    for toast in toasts {
+        let mut notification = notify_rust::Notification::new();
+        notification
+            .summary(&toast.title)
+            .body(&toast.body)
+            .auto_icon()
+            .timeout(TIMEOUT);
+
+        if WAITING.fetch_add(1, Ordering::SeqCst) >= MAX_WAITING {
+            WAITING.fetch_sub(1, Ordering::SeqCst);
+            let _ = notification.show();
+            continue;
         }
-    } else {
+
+        let app_handle = app_handle.clone();
+        notification.action("default", "Open");
+        thread::spawn(move || {
+            if let Ok(handle) = notification.show() {
+                handle.wait_for_action(|action| {
+                    if action == "default" {
+                        open(&app_handle, toast.item);
+                    }
+                });
+            }
+            WAITING.fetch_sub(1, Ordering::SeqCst);
+        });
+    }
+}
+
+// Tauri's notifications do not tell when they are clicked on macOS and Windows, but clicking
+// one activates the app. Unless the window already has focus, what was notified of last is
+// kept for a while, and opened if the window gains focus in the meantime. Of several
+// notifications, the unread items are opened, as there is no telling which one was clicked.
+#[cfg(not(all(unix, not(target_os = "macos"))))]
+static NOTIFIED: Mutex<Option<(Option<i32>, Instant)>> = Mutex::new(None);
+
+#[cfg(not(all(unix, not(target_os = "macos"))))]
+const NOTIFIED_TIMEOUT: Duration = Duration::from_secs(30);
+
+#[cfg(not(all(unix, not(target_os = "macos"))))]
+fn show(app_handle: &AppHandle, toasts: Vec<Toast>) {
+    let item = match toasts.as_slice() {
+        [toast] => toast.item,
+        _ => None,
+    };
+
+    let app_id = &app_handle.config().tauri.bundle.identifier;
+    for toast in toasts {
         let _ = Notification::new(app_id)
-            .title("New items arrived")
-            .body(format!("There are {} items to read", args.len()))
+            .title(&toast.title)
+            .body(&toast.body)
             .show();
     }
+
+    let has_focus = app_handle
+        .get_window("main")
+        .is_some_and(|x| x.is_focused().unwrap_or(false));
+    *NOTIFIED.lock().unwrap_or_else(PoisonError::into_inner) =
+        (!has_focus).then(|| (item, Instant::now()));
+}
+
+/// Called when the window gains focus, to open what was notified of if that is why.
+#[cfg(not(all(unix, not(target_os = "macos"))))]
+pub fn focused(app_handle: &AppHandle) {
+    let notified = NOTIFIED
+        .lock()
+        .unwrap_or_else(PoisonError::into_inner)
+        .take();
+
+    if let Some((item, _)) = notified.filter(|(_, at)| at.elapsed() < NOTIFIED_TIMEOUT) {
+        open(app_handle, item);
+    }
+}
+
+// Clicks are reported by the notification server instead.
+#[cfg(all(unix, not(target_os = "macos")))]
+pub fn focused(_app_handle: &AppHandle) {}
+
+// Brings the window back and marks the item as read, before the UI opens it.
+fn open(app_handle: &AppHandle, item: Option<i32>) {
+    use crate::models::items::{self, ItemStatus, ItemToUpdate};
+    use crate::{show_window, DbState};
+
+    // Payload of the `notification_clicked` event. Without an item, the unread items are shown.
+    #[derive(Serialize, Clone)]
+    struct NotificationClicked {
+        item: Option<i32>,
+    }
+
+    if let Some(id) = item {
+        let db_state = app_handle.state::<DbState>();
+        let db = db_state.lock();
+        let arg = ItemToUpdate {
+            id,
+            status: Some(ItemStatus::Read),
+            is_saved: None,
+        };
+        if let Err(err) = items::update(&db, &arg) {
+            eprintln!("Error marking item {} as read: {}", id, err);
+        }
+        tray::update(app_handle, &db);
+    }
+
+    show_window(app_handle);
+    let _ = app_handle.emit_all("notification_clicked", NotificationClicked { item });
 }
@@ -1,5 +1,6 @@
 import { lazy, onMount } from "solid-js";
-import { A, Route, Routes } from "@solidjs/router";
+import { A, Route, Routes, useNavigate } from "@solidjs/router";
+import { listen } from "@tauri-apps/api/event";
 
 import "./App.css";
 import { ItemType } from "./routes/models/items";
@@ -10,6 +11,8 @@ const Feeds = lazy(() => import("./routes/Feeds"));
 const Settings = lazy(() => import("./routes/Settings"));
 
 function App() {
+  const navigate = useNavigate();
+
   const setTheme = (theme: string) => {
     document.querySelector("html")?.setAttribute("data-theme", theme);
   }
@@ -34,6 +37,11 @@ function App() {
     }
   });
 
+  // eslint-disable-next-line solid/reactivity
+  listen<{ item: number | null }>("notification_clicked", (e) => {
+    navigate(e.payload.item ? `/?item=${e.payload.item}` : "/unread");
+  });
+
   return (
     <div class="container">
       <div class="navigation row">
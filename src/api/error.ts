@@ -0,0 +1,10 @@
+// Every command rejects with this instead of a plain message.
+export interface AppError {
+  // Stable, unlike the message, e.g. "invalid_setting" or "feed_unreachable".
+  code: string,
+  message: string,
+  // The input the error is about, such as the key of an invalid setting.
+  field: string | null,
+  // e.g. the reason of an invalid setting, or `{ status: 404 }` for an unreachable feed.
+  detail: unknown,
+}
@@ -1,10 +1,32 @@
 import { invoke } from "@tauri-apps/api/tauri";
+import { AppError } from "./error";
 
 export enum FeedStatus {
   SUBSCRIBED = "Subscribed",
   UNSUBSCRIBED = "Unsubscribed",
 }
 
+export enum FeedMoveReason {
+  PERMANENT_REDIRECT = "PermanentRedirect",
+  NEW_FEED_URL = "NewFeedUrl",
+  SELF_LINK = "SelfLink",
+}
+
+export interface FeedHistory {
+    id: number,
+    feed: number,
+    old_link: string,
+    new_link: string,
+    reason: FeedMoveReason,
+    created_at: string,
+}
+
+// Secrets are never sent back by the backend, so credentials read from a feed have them empty.
+export type Credentials =
+  | { Basic: { username: string, password: string } }
+  | { Bearer: { token: string } }
+  | { Cookie: { cookie: string } };
+
 export interface Feed {
     id: number,
     title: string,
@@ -12,12 +34,34 @@ export interface Feed {
     status: FeedStatus,
     checked_at: string,
     fetch_old_items: boolean,
+    remote_id?: number | null,
+    fetch_full_content: boolean,
+    site_link?: string | null,
+    description?: string | null,
+    language?: string | null,
+    image?: string | null,
+    icon?: string | null,
+    self_link?: string | null,
+    user_agent?: string | null,
+    credentials?: Credentials | null,
+    // Values are redacted, only the names are shown.
+    headers: Record<string, string>,
+    proxy?: string | null,
+    notification: boolean,
+    notification_filter?: string | null,
+}
+
+export interface FeedOutcome {
+    feed: number,
+    new_items: number,
+    error?: string | null,
 }
 
 export interface FeedToCreate {
     title: string,
     link: string,
     fetch_old_items: boolean,
+    proxy?: string | null,
 }
 
 export interface FeedToUpdate {
@@ -26,14 +70,26 @@ export interface FeedToUpdate {
     link?: string | null,
     status?: FeedStatus | null,
     fetch_old_items?: boolean | null,
+    fetch_full_content?: boolean | null,
+    user_agent?: string | null,
+    credentials?: Credentials | null,
+    // An empty value keeps the stored one.
+    headers?: Record<string, string> | null,
+    // null goes through the global proxy, an empty string connects directly.
+    proxy?: string | null,
+    notification?: boolean | null,
+    // A regex the titles of notified items must match. An empty string clears it.
+    notification_filter?: string | null,
 }
 
-export async function createFeed(arg: FeedToCreate) {
+export async function createFeed(arg: FeedToCreate): Promise<AppError | null> {
   try {
     await invoke("create_feed", { arg });
   } catch (e) {
-    // Do nothing
+    return e as AppError;
   }
+
+  return null;
 }
 
 export async function updateFeed(arg: FeedToUpdate) {
@@ -71,3 +127,33 @@ export async function deleteFeed(id: number) {
     // Do nothing
   }
 }
+
+export async function readFeedHistories(id: number): Promise<FeedHistory[]> {
+  try {
+    return invoke("read_feed_histories", { id });
+  } catch (e) {
+    // Do nothing
+  }
+
+  return [];
+}
+
+export async function refreshAllFeeds(): Promise<FeedOutcome[]> {
+  try {
+    return invoke("refresh_all_feeds");
+  } catch (e) {
+    // Do nothing
+  }
+
+  return [];
+}
+
+export async function refreshFeed(id: number): Promise<FeedOutcome | null> {
+  try {
+    return invoke("refresh_feed", { id });
+  } catch (e) {
+    // Do nothing
+  }
+
+  return null;
+}
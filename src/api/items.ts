@@ -22,6 +22,10 @@ export interface Item {
   is_saved: boolean,
   published_at: string,
   feed: ItemFeed,
+  remote_id?: number | null,
+  updated_at?: string | null,
+  full_content?: string | null,
+  thumbnail?: string | null,
 }
 
 export interface ItemToCreate {
@@ -122,3 +126,13 @@ export async function markAs(ids: number[], status: ItemStatus) {
     // Do nothing
   }
 }
+
+export async function extractContent(id: number): Promise<string | null> {
+  try {
+    return invoke("extract_item_content", { id });
+  } catch (e) {
+    // Do nothing
+  }
+
+  return null;
+}
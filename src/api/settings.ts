@@ -1,4 +1,5 @@
 import { invoke } from "@tauri-apps/api/tauri";
+import { AppError } from "./error";
 
 export enum SettingKey {
   POLLING_FREQUENCY = "PollingFrequency",
@@ -7,7 +8,24 @@ export enum SettingKey {
   THEME = "Theme",
   ITEMS_ORDER = "ItemsOrder",
   PROXY="Proxy",
-  FETCH_OLD_ITEMS = "FetchOldItems"
+  FETCH_OLD_ITEMS = "FetchOldItems",
+  API_SERVER = "ApiServer",
+  API_SERVER_ADDRESS = "ApiServerAddress",
+  API_USERNAME = "ApiUsername",
+  API_PASSWORD = "ApiPassword",
+  MINIFLUX_URL = "MinifluxUrl",
+  MINIFLUX_API_KEY = "MinifluxApiKey",
+  MINIFLUX_SYNCED_AT = "MinifluxSyncedAt",
+  SAVED_ITEMS_FEED_PATH = "SavedItemsFeedPath",
+  ASSET_CACHE = "AssetCache",
+  ASSET_CACHE_SIZE = "AssetCacheSize",
+  HTTP_TIMEOUT = "HttpTimeout",
+  HTTP_CONNECT_TIMEOUT = "HttpConnectTimeout",
+  HTTP_RETRIES = "HttpRetries",
+  USER_AGENT = "UserAgent",
+  QUIET_HOURS_START = "QuietHoursStart",
+  QUIET_HOURS_END = "QuietHoursEnd",
+  NOTIFICATION_INTERVAL = "NotificationInterval",
 }
 
 export interface Setting {
@@ -17,6 +35,24 @@ export interface Setting {
 
 export type SettingToUpdate = Setting;
 
+export type SettingType =
+  | { type: "Boolean" }
+  | { type: "Integer", min: number, max: number | null }
+  | { type: "Choice", options: string[] }
+  | { type: "Time" }
+  | { type: "Address" }
+  | { type: "Url" }
+  | { type: "Proxy" }
+  | { type: "Text" };
+
+export type SettingSchema = SettingType & {
+  key: SettingKey,
+  default: string,
+  description: string,
+  read_only: boolean,
+  secret: boolean,
+};
+
 export async function readAllSettings(): Promise<Setting[]> {
   try {
     return invoke("read_all_settings");
@@ -37,10 +73,32 @@ export async function readSetting(key: SettingKey): Promise<Setting | null> {
   return null;
 }
 
-export async function updateSetting(arg: SettingToUpdate) {
+export async function readSettingSchemas(): Promise<SettingSchema[]> {
+  try {
+    return invoke("read_setting_schemas");
+  } catch (e) {
+    // Do nothing
+  }
+
+  return [];
+}
+
+export async function updateSetting(arg: SettingToUpdate): Promise<AppError | null> {
   try {
     await invoke("update_setting", { arg: { key: arg.key, value: arg.value } });
+  } catch (e) {
+    return e as AppError;
+  }
+
+  return null;
+}
+
+export async function resetSetting(key: SettingKey): Promise<Setting | null> {
+  try {
+    return await invoke("reset_setting", { key });
   } catch (e) {
     // Do nothing
   }
+
+  return null;
 }
@@ -0,0 +1,87 @@
+import { invoke } from "@tauri-apps/api/tauri";
+
+export enum WebhookFormat {
+  JSON = "Json",
+  FORM = "Form",
+}
+
+export interface Webhook {
+    id: number,
+    url: string,
+    feed?: number | null,
+    filter?: string | null,
+    format: WebhookFormat,
+    template?: string | null,
+}
+
+export interface WebhookToCreate {
+    url: string,
+    feed?: number | null,
+    filter?: string | null,
+    format: WebhookFormat,
+    template?: string | null,
+}
+
+export interface WebhookToUpdate {
+    id: number,
+    url?: string | null,
+    // null clears the feed, filter and template, leaving them out keeps them.
+    feed?: number | null,
+    filter?: string | null,
+    format?: WebhookFormat | null,
+    template?: string | null,
+}
+
+export interface WebhookDelivery {
+    id: number,
+    webhook: number,
+    link: string,
+    status_code?: number | null,
+    error?: string | null,
+    attempts: number,
+    delivered_at: string,
+}
+
+export async function createWebhook(arg: WebhookToCreate) {
+  try {
+    await invoke("create_webhook", { arg });
+  } catch (e) {
+    // Do nothing
+  }
+}
+
+export async function readAllWebhooks(): Promise<Webhook[]> {
+  try {
+    return invoke("read_all_webhooks");
+  } catch (e) {
+    // Do nothing
+  }
+
+  return [];
+}
+
+export async function updateWebhook(arg: WebhookToUpdate) {
+  try {
+    await invoke("update_webhook", { arg });
+  } catch (e) {
+    // Do nothing
+  }
+}
+
+export async function deleteWebhook(id: number) {
+  try {
+    await invoke("delete_webhook", { id });
+  } catch (e) {
+    // Do nothing
+  }
+}
+
+export async function readWebhookDeliveries(webhook: number, limit?: number): Promise<WebhookDelivery[]> {
+  try {
+    return invoke("read_webhook_deliveries", { webhook, limit });
+  } catch (e) {
+    // Do nothing
+  }
+
+  return [];
+}
@@ -0,0 +1,36 @@
+import { invoke } from "@tauri-apps/api/tauri";
+
+// Payloads of the events emitted while refreshing. `feed_fetched` carries a `FeedOutcome`.
+export interface RefreshStarted {
+    feeds: number,
+}
+
+export interface RefreshFinished {
+    feeds: number,
+    new_items: number,
+    errors: number,
+}
+
+export async function pauseWorker() {
+  try {
+    await invoke("pause_worker");
+  } catch (e) {
+    // Do nothing
+  }
+}
+
+export async function resumeWorker() {
+  try {
+    await invoke("resume_worker");
+  } catch (e) {
+    // Do nothing
+  }
+}
+
+export async function wakeWorker() {
+  try {
+    await invoke("wake_worker");
+  } catch (e) {
+    // Do nothing
+  }
+}
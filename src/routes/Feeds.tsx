@@ -1,6 +1,6 @@
 import { confirm } from '@tauri-apps/api/dialog';
 import { A, useNavigate } from '@solidjs/router';
-import { createSignal, For, Match, onMount, Switch } from "solid-js";
+import { createSignal, For, Match, onMount, Show, Switch } from "solid-js";
 
 import dayjs from 'dayjs';
 import relativeTime from 'dayjs/plugin/relativeTime';
@@ -13,6 +13,7 @@ dayjs.extend(timezone);
 import "../styles/Feeds.css";
 import * as feedApi from "../api/feeds";
 import * as settingApi from "../api/settings";
+import { AppError } from "../api/error";
 
 function Feeds() {
   const navigate = useNavigate();
@@ -23,13 +24,30 @@ function Feeds() {
   const [titleToUpdate, setTitleToUpdate] = createSignal<string | null>(null);
   const [linkToUpdate, setLinkToUpdate] = createSignal<string | null>(null);
   const [fetchOldItems, setFetchOldItems] = createSignal<boolean>(true);
+  const [createError, setCreateError] = createSignal<AppError | null>(null);
 
   const createFeed = async () => {
-    await feedApi.createFeed({ title: "auto", link: linkToCreate(), fetch_old_items: fetchOldItems() });
+    const err = await feedApi.createFeed({ title: "auto", link: linkToCreate(), fetch_old_items: fetchOldItems() });
+    setCreateError(err);
+    if (err) return;
+
     setFeeds(await feedApi.readAllFeeds());
     setLinkToCreate("");
   };
 
+  const errorToText = (err: AppError) => {
+    switch (err.code) {
+      case "feed_unreachable":
+        return `The feed could not be reached (HTTP ${(err.detail as { status: number }).status}).`;
+      case "invalid_feed_link":
+        return "No feed was found at this URL.";
+      case "syndication_parsing_failure":
+        return "This URL is not a valid RSS or Atom feed.";
+      default:
+        return err.message;
+    }
+  };
+
   const updateFeed = async (id: number) => {
     await feedApi.updateFeed({ id, title: titleToUpdate(), link: linkToUpdate() } );
     setFeeds(await feedApi.readAllFeeds());
@@ -90,6 +108,9 @@ function Feeds() {
           <label for="fetch_old_items"><small>Fetch old items</small></label>
         </span>
       </form>
+      <Show when={createError()}>
+        <small>{errorToText(createError()!)}</small>
+      </Show>
       <ul class="feed-list">
         <For each={feeds()}>{(feed: feedApi.Feed) =>
           <li class={`${feed.status == feedApi.FeedStatus.UNSUBSCRIBED ? "lowp" : ""}`}>
@@ -1,6 +1,6 @@
 import { listen } from '@tauri-apps/api/event';
-import { A, useParams } from '@solidjs/router';
-import { createSignal, For, Match, onMount, Show, Switch } from "solid-js";
+import { A, useParams, useSearchParams } from '@solidjs/router';
+import { createEffect, createSignal, For, Match, onMount, Show, Switch } from "solid-js";
 import DOMPurify from 'dompurify';
 
 import dayjs from 'dayjs';
@@ -24,6 +24,7 @@ interface Props {
 function Items(props: Props) {
   const LIMIT = 50;
   const params = useParams();
+  const [searchParams] = useSearchParams();
 
   const [offset, setOffset] = createSignal(0);
   const [opt, setOpt] = createSignal<api.ItemReadOption>({});
@@ -109,6 +110,18 @@ function Items(props: Props) {
     });
   };
 
+  // Opens the item of a clicked notification.
+  createEffect(async () => {
+    const id = Number(searchParams.item);
+    if (id) {
+      const [item] = await api.readItems({ ids: [id] });
+      if (item) {
+        selectItem(item);
+        await loadItems();
+      }
+    }
+  });
+
   onMount(async () => {
     const res = await settingApi.readSetting(settingApi.SettingKey.ITEMS_ORDER);
     const order = res?.value ?? api.ItemOrder.RECEIVED_DATE_DESC;
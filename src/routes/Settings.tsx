@@ -5,6 +5,7 @@ import { createSignal, For, Match, onMount, Show, Switch } from "solid-js";
 
 import "../styles/Settings.css";
 import * as api from "../api/settings";
+import { AppError } from "../api/error";
 
 function Settings() {
   const [version, setVersion] = createSignal("");
@@ -12,15 +13,9 @@ function Settings() {
   const [dataDir, setDataDir] = createSignal("");
 
   const [settings, setSettings] = createSignal<api.Setting[]>([]);
-  const [newSettings, setNewSettings] = createSignal<{ [key in api.SettingKey]: string }>({
-    [api.SettingKey.POLLING_FREQUENCY]: "",
-    [api.SettingKey.NOTIFICATION]: "",
-    [api.SettingKey.DB_SCHEME_VERSION]: "",
-    [api.SettingKey.THEME]: "",
-    [api.SettingKey.ITEMS_ORDER]: "",
-    [api.SettingKey.PROXY]: "",
-    [api.SettingKey.FETCH_OLD_ITEMS]: ""
-  });
+  const [schemas, setSchemas] = createSignal<api.SettingSchema[]>([]);
+  const [error, setError] = createSignal<AppError | null>(null);
+  const [newSettings, setNewSettings] = createSignal<{ [key in api.SettingKey]?: string }>({});
 
   const keyToText = (key: api.SettingKey) => {
     switch (key) {
@@ -41,6 +36,11 @@ function Settings() {
     setSettings(await api.readAllSettings());
   };
 
+  const schemaOf = (key: api.SettingKey) => schemas().find((x) => x.key === key);
+
+  // Settings without a schema, or only changed by migrations, have nothing to edit.
+  const editableSettings = () => settings().filter((x) => schemaOf(x.key)?.read_only === false);
+
   const validate = (key: api.SettingKey, value: string) => {
     switch (key) {
       case api.SettingKey.POLLING_FREQUENCY:
@@ -62,49 +62,92 @@ function Settings() {
         }
     }
 
-    await api.updateSetting({ key, value })
+    setError(await api.updateSetting({ key, value }));
     await load()
+
+    // Secrets are read back empty, so the saved one is cleared from the input as well.
+    if (schemaOf(key)?.secret) {
+      setNewSettings({ ...newSettings(), [key]: "" });
+    }
   };
 
   const SaveButton = (setting: api.Setting, afterUpdate: () => void = () => {}) =>
-    <Show when={validate(setting.key, newSettings()[setting.key]) && newSettings()[setting.key] !== setting.value}>
+    <Show when={validate(setting.key, newSettings()[setting.key] ?? "") && newSettings()[setting.key] !== setting.value}>
       <button onClick={() => {
-        update(setting.key, newSettings()[setting.key]);
+        update(setting.key, newSettings()[setting.key] ?? "");
         afterUpdate();
       }}>Save</button>
     </Show>;
 
+  // Edits the settings without a dedicated editor, by the type of their value.
+  const Editor = (setting: api.Setting, schema: api.SettingSchema) => {
+    const onInput = (e: { currentTarget: { value: string } }) =>
+      setNewSettings({ ...newSettings(), [setting.key]: e.currentTarget.value });
+
+    return <>
+      <span><strong>{schema.description}</strong>: </span>
+      <Switch fallback={<input type="text" value={newSettings()[setting.key]} onInput={onInput} />}>
+        <Match when={schema.type === "Boolean"}>
+          <label for={`${setting.key}-yes`}><input type="radio" id={`${setting.key}-yes`} name={setting.key} value="1"
+            checked={newSettings()[setting.key] === "1"} onChange={onInput} />Yes</label>
+          <label for={`${setting.key}-no`}><input type="radio" id={`${setting.key}-no`} name={setting.key} value="0"
+            checked={newSettings()[setting.key] === "0"} onChange={onInput} />No</label>
+        </Match>
+        <Match when={schema.type === "Integer" && schema}>{(integer) =>
+          <input type="number" min={integer().min} max={integer().max ?? undefined}
+            value={newSettings()[setting.key]} onInput={onInput} />
+        }</Match>
+        <Match when={schema.type === "Choice" && schema}>{(choice) =>
+          <select onChange={onInput}>
+            <For each={choice().options}>{(option) =>
+              <option value={option} selected={newSettings()[setting.key] === option}>{option}</option>
+            }</For>
+          </select>
+        }</Match>
+        <Match when={schema.type === "Time"}>
+          <input type="time" value={newSettings()[setting.key]} onInput={onInput} />
+        </Match>
+        <Match when={schema.secret}>
+          <input type="password" value={newSettings()[setting.key]} onInput={onInput} />
+        </Match>
+      </Switch>
+      {SaveButton(setting)}
+    </>;
+  };
+
   onMount(async () => {
     const fetchLatestVersion = async (): Promise<string> => {
       const res = await fetch("https://api.github.com/repos/parksb/collie/releases/latest");
       return (await res.json())['tag_name'];
     };
 
-    const [fetchedVersion, fetchedLatestVersion, fetchedDataDir] = await Promise.all([
+    const [fetchedVersion, fetchedLatestVersion, fetchedDataDir, fetchedSchemas] = await Promise.all([
       getVersion(),
       fetchLatestVersion(),
       appDataDir(),
+      api.readSettingSchemas(),
       load(),
     ]);
 
     setVersion(fetchedVersion);
     setLatestVersion(fetchedLatestVersion);
     setDataDir(fetchedDataDir);
+    setSchemas(fetchedSchemas);
 
     let newSettingsPlaceholder = newSettings();
     settings().forEach((setting: api.Setting) => {
         newSettingsPlaceholder[setting.key] = setting.value;
     });
-    setNewSettings({ ...newSettings, ...newSettingsPlaceholder });
+    setNewSettings({ ...newSettingsPlaceholder });
   });
 
   return (
     <div class="settings-page container">
       <h2>Settings</h2>
       <ul class="setting-list">
-        <For each={settings()}>{(setting) =>
+        <For each={editableSettings()}>{(setting) =>
           <li class="row">
-            <Switch>
+            <Switch fallback={Editor(setting, schemaOf(setting.key)!)}>
               <Match when={setting.key === api.SettingKey.POLLING_FREQUENCY}>
                 <span><strong>{keyToText(setting.key)}</strong>: Check all feeds every</span>
                 <input type="number" min="30" value={newSettings()[setting.key]}
@@ -142,6 +185,9 @@ function Settings() {
                 {SaveButton(setting)}
               </Match>
             </Switch>
+            <Show when={error()?.field === setting.key}>
+              <small>Could not save: {String(error()?.detail)}.</small>
+            </Show>
           </li>
         }</For>
         <li>